@@ -1,8 +1,13 @@
+use std::sync::Arc;
+
 use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
 
 use crate::{
-    raws::{faction_structs::Reaction, rawsmaster::faction_reaction, RAWS},
-    spatial, Faction, Map, MyTurn, Position, TileSize, WantsToMelee,
+    raws::{
+        faction_structs::Reaction,
+        rawsmaster::{faction_reaction, RawMaster},
+    },
+    Faction, Map, MyTurn, Position, TileSize, WantsToMelee,
 };
 
 pub struct AdjacentAI;
@@ -17,10 +22,12 @@ impl<'a> System<'a> for AdjacentAI {
         Entities<'a>,
         ReadExpect<'a, Entity>,
         ReadStorage<'a, TileSize>,
+        ReadExpect<'a, Arc<RawMaster>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, factions, positions, map, mut want_melee, entities, player, sizes) = data;
+        let (mut turns, factions, positions, map, mut want_melee, entities, player, sizes, raws) =
+            data;
 
         let mut turn_done = Vec::new();
         for (entity, _turn, my_fac, pos) in (&entities, &turns, &factions, &positions).join() {
@@ -28,6 +35,16 @@ impl<'a> System<'a> for AdjacentAI {
                 continue;
             }
             let mut reactions = Vec::new();
+            let mut evaluate_tile = |tgt_idx: i32| {
+                evaluate(
+                    tgt_idx,
+                    &map,
+                    &factions,
+                    &my_fac.name,
+                    &raws,
+                    &mut reactions,
+                )
+            };
             let idx = map.xy_idx(pos.x, pos.y) as i32;
             let w = map.width;
             let h = map.height;
@@ -44,7 +61,7 @@ impl<'a> System<'a> for AdjacentAI {
                     .for_each(|t| {
                         if t.0 > 0 && t.0 < w - 1 && t.1 > 0 && t.1 < h - 1 {
                             let tgt_idx = map.xy_idx(t.0, t.1) as i32;
-                            evaluate(tgt_idx, &factions, &my_fac.name, &mut reactions);
+                            evaluate_tile(tgt_idx);
                         }
                     });
                 continue;
@@ -52,28 +69,28 @@ impl<'a> System<'a> for AdjacentAI {
 
             // Add possible reactions to adjacents for each direction
             if pos.x > 0 {
-                evaluate(idx - 1, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx - 1);
             }
             if pos.x < w - 1 {
-                evaluate(idx + 1, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx + 1);
             }
             if pos.y > 0 {
-                evaluate(idx - w, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx - w);
             }
             if pos.y < h - 1 {
-                evaluate(idx + w, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx + w);
             }
             if pos.y > 0 && pos.x > 0 {
-                evaluate(idx - w - 1, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx - w - 1);
             }
             if pos.y > 0 && pos.x < w - 1 {
-                evaluate(idx - w + 1, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx - w + 1);
             }
             if pos.y < h - 1 && pos.x > 0 {
-                evaluate(idx + w - 1, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx + w - 1);
             }
             if pos.y < h - 1 && pos.x < w - 1 {
-                evaluate(idx + w + 1, &factions, &my_fac.name, &mut reactions);
+                evaluate_tile(idx + w + 1);
             }
 
             let mut done = false;
@@ -100,16 +117,16 @@ impl<'a> System<'a> for AdjacentAI {
 
 fn evaluate(
     idx: i32,
+    map: &Map,
     factions: &ReadStorage<Faction>,
     my_fac: &str,
+    raws: &RawMaster,
     reactions: &mut Vec<(Entity, Reaction)>,
 ) {
-    spatial::for_each_tile_content(idx as usize, |other_entity| {
-        if let Some(faction) = factions.get(other_entity) {
-            reactions.push((
-                other_entity,
-                faction_reaction(my_fac, &faction.name, &RAWS.lock().unwrap()),
-            ));
-        }
-    });
+    map.spatial
+        .for_each_tile_content(idx as usize, |other_entity| {
+            if let Some(faction) = factions.get(other_entity) {
+                reactions.push((other_entity, faction_reaction(my_fac, &faction.name, raws)));
+            }
+        });
 }
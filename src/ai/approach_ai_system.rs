@@ -1,4 +1,4 @@
-use specs::{Entities, Join, System, WriteExpect, WriteStorage};
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteStorage};
 
 use crate::{ApplyMove, Map, MyTurn, Position, WantsToApproach};
 
@@ -8,18 +8,18 @@ impl<'a> System<'a> for ApproachAI {
     type SystemData = (
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, WantsToApproach>,
-        WriteStorage<'a, Position>,
-        WriteExpect<'a, Map>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut want_approach, mut positions, map, entities, mut apply_move) = data;
+        let (mut turns, mut want_approach, positions, map, entities, mut apply_move) = data;
 
         let mut turn_done = Vec::new();
         for (entity, pos, approach, _myturn) in
-            (&entities, &mut positions, &want_approach, &turns).join()
+            (&entities, &positions, &want_approach, &turns).join()
         {
             turn_done.push(entity);
             let start_idx = map.xy_idx(pos.x, pos.y);
@@ -10,7 +10,7 @@ impl<'a> System<'a> for ChaseAI {
     type SystemData = (
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, Chasing>,
-        WriteStorage<'a, Position>,
+        ReadStorage<'a, Position>,
         ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
@@ -18,7 +18,7 @@ impl<'a> System<'a> for ChaseAI {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut chasing, mut positions, map, entities, mut apply_move, sizes) = data;
+        let (mut turns, mut chasing, positions, map, entities, mut apply_move, sizes) = data;
 
         let mut targets = HashMap::new();
         let mut end_chase = Vec::new();
@@ -36,7 +36,7 @@ impl<'a> System<'a> for ChaseAI {
         end_chase.clear();
 
         let mut turn_done = Vec::new();
-        for (entity, pos, _chase, _myturn) in (&entities, &mut positions, &chasing, &turns).join() {
+        for (entity, pos, _chase, _myturn) in (&entities, &positions, &chasing, &turns).join() {
             turn_done.push(entity);
             let (tgt_x, tgt_y) = targets[&entity];
             let idx = map.xy_idx(pos.x, pos.y);
@@ -1,6 +1,9 @@
-use specs::{Entities, Join, ReadExpect, System, WriteExpect, WriteStorage};
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
-use crate::{spatial, tile_walkable, ApplyMove, Map, MoveMode, Movement, MyTurn, Position};
+use crate::{tile_walkable, ApplyMove, Follower, Map, MoveMode, Movement, MyTurn, Position};
+
+/// How far a group member may drift from its leader before heading back.
+const FOLLOW_DISTANCE: f32 = 3.0;
 
 pub struct DefaultMoveAI;
 
@@ -8,23 +11,59 @@ impl<'a> System<'a> for DefaultMoveAI {
     type SystemData = (
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, MoveMode>,
-        WriteStorage<'a, Position>,
+        ReadStorage<'a, Position>,
         ReadExpect<'a, Map>,
         WriteExpect<'a, rltk::RandomNumberGenerator>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
+        ReadStorage<'a, Follower>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut move_mode, mut positions, map, mut rng, entities, mut apply_move) =
-            data;
+        let (
+            mut turns,
+            mut move_mode,
+            positions,
+            map,
+            mut rng,
+            entities,
+            mut apply_move,
+            followers,
+        ) = data;
 
         let mut turn_done = Vec::new();
-        for (entity, pos, mode, _myturn) in
-            (&entities, &mut positions, &mut move_mode, &turns).join()
-        {
+        for (entity, pos, mode, _myturn) in (&entities, &positions, &mut move_mode, &turns).join() {
             turn_done.push(entity);
 
+            // Group members head back to their leader instead of wandering off
+            if let Some(leader_pos) = followers.get(entity).and_then(|f| positions.get(f.leader)) {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(
+                    rltk::Point::new(pos.x, pos.y),
+                    rltk::Point::new(leader_pos.x, leader_pos.y),
+                );
+                if distance > FOLLOW_DISTANCE {
+                    let path = rltk::a_star_search(
+                        map.xy_idx(pos.x, pos.y),
+                        map.xy_idx(leader_pos.x, leader_pos.y),
+                        &*map,
+                    );
+                    if path.success
+                        && path.steps.len() > 1
+                        && !map.spatial.is_blocked(path.steps[1])
+                    {
+                        apply_move
+                            .insert(
+                                entity,
+                                ApplyMove {
+                                    dest_idx: path.steps[1],
+                                },
+                            )
+                            .expect("Unable to insert");
+                    }
+                    continue;
+                }
+            }
+
             match &mut mode.mode {
                 Movement::Static => {}
                 Movement::Random => {
@@ -43,7 +82,7 @@ impl<'a> System<'a> for DefaultMoveAI {
                     // TODO: Abstract bounds checking to method. Used in multiple places.
                     if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
                         let dest_idx = map.xy_idx(x, y);
-                        if !spatial::is_blocked(dest_idx) {
+                        if !map.spatial.is_blocked(dest_idx) {
                             apply_move
                                 .insert(entity, ApplyMove { dest_idx })
                                 .expect("Unable to insert");
@@ -56,7 +95,7 @@ impl<'a> System<'a> for DefaultMoveAI {
                         Some(path) => {
                             // We have a target - go there
                             if path.len() > 1 {
-                                if !spatial::is_blocked(path[1]) {
+                                if !map.spatial.is_blocked(path[1]) {
                                     apply_move
                                         .insert(entity, ApplyMove { dest_idx: path[1] })
                                         .expect("Unable to insert");
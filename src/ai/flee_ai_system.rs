@@ -1,7 +1,7 @@
 use rltk::DijkstraMap;
 use specs::{Entities, Join, System, WriteExpect, WriteStorage};
 
-use crate::{spatial, ApplyMove, Map, MyTurn, Position, WantsToFlee};
+use crate::{ApplyMove, Map, MyTurn, Position, WantsToFlee};
 
 pub struct FleeAI;
 
@@ -31,7 +31,7 @@ impl<'a> System<'a> for FleeAI {
                 100.0,
             );
             if let Some(flee_tgt_idx) = DijkstraMap::find_highest_exit(&flee_map, my_idx, &*map) {
-                if !spatial::is_blocked(flee_tgt_idx) {
+                if !map.spatial.is_blocked(flee_tgt_idx) {
                     apply_move
                         .insert(
                             entity,
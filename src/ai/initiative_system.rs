@@ -1,7 +1,7 @@
 use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    effects::{add_effect, EffectType, Targets},
+    effects::{EffectQueue, EffectType, Targets},
     Attributes, DamageOverTime, Duration, EquipmentChanged, Initiative, MyTurn, Pools, Position,
     RunState, StatusEffect,
 };
@@ -24,6 +24,7 @@ impl<'a> System<'a> for InitiativeSystem {
         WriteStorage<'a, EquipmentChanged>,
         WriteStorage<'a, Duration>,
         ReadStorage<'a, DamageOverTime>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -42,6 +43,7 @@ impl<'a> System<'a> for InitiativeSystem {
             mut equipment_dirty,
             mut durations,
             dots,
+            mut effects,
         ) = data;
 
         if *runstate != RunState::Ticking {
@@ -101,7 +103,7 @@ impl<'a> System<'a> for InitiativeSystem {
                     duration.turns -= 1;
                     // DOT could be its own system but doesn't seem too important.
                     if let Some(dot) = dots.get(effect_entity) {
-                        add_effect(
+                        effects.add_effect(
                             None,
                             EffectType::Damage { amount: dot.damage },
                             Targets::Single {
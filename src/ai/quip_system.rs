@@ -1,6 +1,9 @@
 use specs::{Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
-use crate::{gamelog::Logger, MyTurn, Name, Quips, Viewshed};
+use crate::{
+    gamelog::{GameLog, Logger},
+    MyTurn, Name, Quips, Viewshed,
+};
 
 pub struct QuipSystem;
 
@@ -12,10 +15,11 @@ impl<'a> System<'a> for QuipSystem {
         ReadExpect<'a, rltk::Point>,
         ReadStorage<'a, Viewshed>,
         WriteExpect<'a, rltk::RandomNumberGenerator>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut quips, names, turns, player_pos, viewsheds, mut rng) = data;
+        let (mut quips, names, turns, player_pos, viewsheds, mut rng, mut gamelog) = data;
 
         for (quip, name, viewshed, _turn) in (&mut quips, &names, &viewsheds, &turns).join() {
             if !quip.available.is_empty()
@@ -32,7 +36,7 @@ impl<'a> System<'a> for QuipSystem {
                     .yellow(&name.name)
                     .white("says")
                     .cyan(&quip.available[quip_index])
-                    .log();
+                    .log(&mut gamelog);
                 quip.available.remove(quip_index);
             }
         }
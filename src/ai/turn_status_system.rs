@@ -1,9 +1,9 @@
 use std::collections::HashSet;
 
-use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteStorage};
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    effects::{add_effect, EffectType, Targets},
+    effects::{EffectQueue, EffectType, Targets},
     Confusion, MyTurn, RunState, StatusEffect,
 };
 
@@ -16,11 +16,12 @@ impl<'a> System<'a> for TurnStatusSystem {
         Entities<'a>,
         ReadExpect<'a, RunState>,
         ReadStorage<'a, StatusEffect>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         // Iterates confused entities, decrements their turn counter. If still confused, removes MyTurn tag. If recovered, removes Confusion tag
-        let (mut turns, confusion, entities, runstate, statuses) = data;
+        let (mut turns, confusion, entities, runstate, statuses, mut effects) = data;
 
         if *runstate != RunState::Ticking {
             return;
@@ -38,7 +39,7 @@ impl<'a> System<'a> for TurnStatusSystem {
             if entity_turns.contains(&status_effect.target) {
                 // Skip turn for confusion
                 if confusion.get(effect_entity).is_some() {
-                    add_effect(
+                    effects.add_effect(
                         None,
                         EffectType::Particle {
                             glyph: rltk::to_cp437('?'),
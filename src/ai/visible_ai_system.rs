@@ -1,10 +1,15 @@
+use std::sync::Arc;
+
 use rltk::{DistanceAlg, Point};
 use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    raws::{faction_structs::Reaction, rawsmaster::faction_reaction, RAWS},
-    spatial, Chasing, Equipped, Faction, Map, MyTurn, Name, Position, SpecialAbilities,
-    SpellTemplate, Viewshed, WantsToApproach, WantsToCastSpell, WantsToFlee, WantsToShoot, Weapon,
+    raws::{
+        faction_structs::Reaction,
+        rawsmaster::{faction_reaction, RawMaster},
+    },
+    Chasing, Equipped, Faction, Map, MyTurn, Name, Position, SpecialAbilities, SpellTemplate,
+    Viewshed, WantsToApproach, WantsToCastSpell, WantsToFlee, WantsToShoot, Weapon,
 };
 
 pub struct VisibleAI;
@@ -29,6 +34,7 @@ impl<'a> System<'a> for VisibleAI {
         ReadStorage<'a, Weapon>,
         WriteStorage<'a, WantsToShoot>,
         ReadStorage<'a, Equipped>,
+        ReadExpect<'a, Arc<RawMaster>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -51,6 +57,7 @@ impl<'a> System<'a> for VisibleAI {
             weapons,
             mut wants_shoot,
             equipped,
+            raws,
         ) = data;
 
         for (entity, _turn, my_faction, pos, viewshed) in
@@ -66,7 +73,14 @@ impl<'a> System<'a> for VisibleAI {
             for visible_tile in viewshed.visible_tiles.iter() {
                 let idx = map.xy_idx(visible_tile.x, visible_tile.y);
                 if my_idx != idx {
-                    evaluate(idx, &factions, &my_faction.name, &mut reactions);
+                    evaluate(
+                        idx,
+                        &map,
+                        &factions,
+                        &my_faction.name,
+                        &raws,
+                        &mut reactions,
+                    );
                 }
             }
 
@@ -153,15 +167,17 @@ impl<'a> System<'a> for VisibleAI {
 
 fn evaluate(
     idx: usize,
+    map: &Map,
     factions: &ReadStorage<Faction>,
     my_fac: &str,
+    raws: &RawMaster,
     reactions: &mut Vec<(usize, Reaction, Entity)>,
 ) {
-    spatial::for_each_tile_content(idx, |other_entity| {
+    map.spatial.for_each_tile_content(idx, |other_entity| {
         if let Some(faction) = factions.get(other_entity) {
             reactions.push((
                 idx,
-                faction_reaction(my_fac, &faction.name, &RAWS.lock().unwrap()),
+                faction_reaction(my_fac, &faction.name, raws),
                 other_entity,
             ));
         }
@@ -7,7 +7,7 @@ use specs::{
 use specs_derive::*;
 use std::{collections::HashMap, convert::Infallible as NoError};
 
-use crate::{gamelog::LogFragment, map};
+use crate::{dice::DiceExpr, gamelog::LogFragment, map, saveload_system::SaveSettings};
 
 #[derive(Component, ConvertSaveload, Clone, Default, Copy)]
 pub struct Position {
@@ -65,7 +65,7 @@ pub struct Item {
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct ProvidesHealing {
-    pub heal_amount: i32,
+    pub heal_amount: DiceExpr,
 }
 
 #[derive(Component, Debug, ConvertSaveload)]
@@ -142,6 +142,8 @@ pub struct DMSerializationHelper {
     pub map: map::dungeon::MasterDungeonMap,
     pub log: Vec<Vec<LogFragment>>,
     pub events: HashMap<String, i32>,
+    #[serde(default)]
+    pub settings: SaveSettings,
 }
 
 #[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
@@ -181,9 +183,7 @@ pub enum WeaponAttribute {
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Weapon {
     pub attribute: WeaponAttribute,
-    pub damage_n_dice: i32,
-    pub damage_die_type: i32,
-    pub damage_bonus: i32,
+    pub damage: DiceExpr,
     pub hit_bonus: i32,
     pub proc_chance: Option<f32>,
     pub proc_target: Option<String>,
@@ -316,9 +316,7 @@ pub struct Pools {
 // Not actually a component, its just used by one. Doesn't need to be registered in saveload and main
 pub struct NaturalAttack {
     pub name: String,
-    pub damage_n_dice: i32,
-    pub damage_die_type: i32,
-    pub damage_bonus: i32,
+    pub damage: DiceExpr,
     pub hit_bonus: i32,
 }
 
@@ -333,6 +331,12 @@ pub struct LootTable {
     pub name: String,
 }
 
+/// A pile of gold on the floor, added to the purse of whoever picks it up.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Gold {
+    pub amount: i32,
+}
+
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct OtherLevelPosition {
     // TODO(aalhendi): Can this be a Position directly?
@@ -387,9 +391,46 @@ pub struct Chasing {
     pub target: Entity,
 }
 
+/// A member of a spawn group, which stays near the group's leader while it isn't busy.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Follower {
+    pub leader: Entity,
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct EquipmentChanged {}
 
+/// Marks an item as a piece of the item set named `set` in the raws.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ItemSetPiece {
+    pub set: String,
+}
+
+/// The item sets an entity has pieces of equipped, and the skill and armor bonuses they
+/// currently grant. Attribute and initiative bonuses go straight into `Attributes` and `Pools`.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ItemSetBonuses {
+    pub sets: Vec<ItemSetProgress>,
+    pub skills: HashMap<Skill, i32>,
+    pub armor_class: i32,
+}
+
+impl ItemSetBonuses {
+    pub fn skill(&self, skill: Skill) -> i32 {
+        self.skills.get(&skill).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+// Not actually a component, its just used by one
+pub struct ItemSetProgress {
+    pub name: String,
+    pub equipped: i32,
+    pub pieces: i32,
+    /// Whether enough pieces are equipped for at least one of the set's bonuses.
+    pub active: bool,
+}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Vendor {
     pub categories: Vec<String>,
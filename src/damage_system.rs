@@ -1,10 +1,8 @@
 use crate::{
     effects::{add_effect, targetting::aoe_tiles, EffectType, Targets},
-    gamelog::Logger,
-    raws::{
-        rawsmaster::{find_spell_entity, get_item_drop, spawn_named_item, SpawnType},
-        RAWS,
-    },
+    gamelog::{GameLog, Logger},
+    raws::rawsmaster::find_spell_entity,
+    spawner::drop_loot,
     AreaOfEffect, Equipped, InBackpack, LootTable, Map, OnDeath, Pools,
 };
 
@@ -29,7 +27,7 @@ pub fn delete_the_dead(ecs: &mut World) {
                             Logger::new()
                                 .yellow(&victim_name.name)
                                 .white("is dead.")
-                                .log()
+                                .log(&mut ecs.fetch_mut::<GameLog>())
                         }
                         dead.push(entity)
                     }
@@ -51,7 +49,6 @@ pub fn delete_the_dead(ecs: &mut World) {
         let mut carried = ecs.write_storage::<InBackpack>();
         let mut positions = ecs.write_storage::<Position>();
         let loot_tables = ecs.read_storage::<LootTable>();
-        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
 
         for victim in dead.iter() {
             let pos = positions.get(*victim);
@@ -75,12 +72,8 @@ pub fn delete_the_dead(ecs: &mut World) {
             }
 
             // Drop loot items
-            if let Some(table) = loot_tables.get(*victim) {
-                if let Some(tag) = get_item_drop(&RAWS.lock().unwrap(), &mut rng, &table.name) {
-                    if let Some(pos) = pos {
-                        to_spawn.push((tag, *pos));
-                    }
-                }
+            if let (Some(table), Some(pos)) = (loot_tables.get(*victim), pos) {
+                to_spawn.push((table.name.clone(), *pos));
             }
         }
 
@@ -93,16 +86,8 @@ pub fn delete_the_dead(ecs: &mut World) {
         }
     }
 
-    for (key_name, spawn_pos) in to_spawn.iter() {
-        spawn_named_item(
-            &RAWS.lock().unwrap(),
-            ecs,
-            key_name,
-            SpawnType::AtPosition {
-                x: spawn_pos.x,
-                y: spawn_pos.y,
-            },
-        );
+    for (table, spawn_pos) in to_spawn.iter() {
+        drop_loot(ecs, table, spawn_pos.x, spawn_pos.y);
     }
 
     for victim in dead.iter() {
@@ -129,6 +114,7 @@ pub fn delete_the_dead(ecs: &mut World) {
                     Targets::Tile { tile_idx }
                 };
                 add_effect(
+                    ecs,
                     None,
                     EffectType::SpellUse {
                         spell: find_spell_entity(ecs, &effect.spell).unwrap(),
@@ -0,0 +1,531 @@
+use rltk::RandomNumberGenerator;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, str::FromStr};
+
+/// How many times a single exploding die may explode, so large rolls stay bounded.
+const MAX_EXPLOSIONS: i32 = 100;
+/// Most dice one term can roll, and most sides a die can have, so a roll can't overflow.
+const MAX_DICE: i32 = 100;
+const MAX_SIDES: i32 = 1000;
+
+/// A dice expression, such as `2d6+1d4+3`. Each dice term can explode (`1d6!` rolls again and
+/// adds whenever a die shows its highest face) or keep only its best or worst dice (`4d6kh3`,
+/// `2d20kl1`). The total can be clamped with `min` and `max` suffixes: `1d6-2min1`.
+///
+/// Raws can write a plain number wherever an expression is expected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiceExpr {
+    terms: Vec<Term>,
+    min: Option<i32>,
+    max: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    Dice {
+        negative: bool,
+        count: i32,
+        sides: i32,
+        explode: bool,
+        keep: Option<Keep>,
+    },
+    Constant(i32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Keep {
+    Highest(i32),
+    Lowest(i32),
+}
+
+#[derive(Debug)]
+pub struct DiceError {
+    expr: String,
+    reason: String,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid dice [{}]: {}", self.expr, self.reason)
+    }
+}
+
+impl DiceExpr {
+    /// `count` dice with `sides` faces each.
+    pub fn dice(count: i32, sides: i32) -> Self {
+        DiceExpr {
+            terms: vec![Term::Dice {
+                negative: false,
+                count,
+                sides,
+                explode: false,
+                keep: None,
+            }],
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn constant(value: i32) -> Self {
+        DiceExpr {
+            terms: vec![Term::Constant(value)],
+            min: None,
+            max: None,
+        }
+    }
+
+    /// This expression with `bonus` added, folded into its constant term if it has one.
+    pub fn plus(&self, bonus: i32) -> Self {
+        let mut result = self.clone();
+        match result.terms.last_mut() {
+            Some(Term::Constant(value)) => *value = value.saturating_add(bonus),
+            _ => result.terms.push(Term::Constant(bonus)),
+        }
+        if result.terms.len() > 1 && result.terms.last() == Some(&Term::Constant(0)) {
+            result.terms.pop();
+        }
+        result
+    }
+
+    /// Rolls the expression. Totals too large for an `i32` saturate rather than overflow.
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> i32 {
+        let mut total = self
+            .terms
+            .iter()
+            .fold(0i32, |total, term| total.saturating_add(term.roll(rng)));
+        if let Some(min) = self.min {
+            total = i32::max(total, min);
+        }
+        if let Some(max) = self.max {
+            total = i32::min(total, max);
+        }
+        total
+    }
+}
+
+impl Term {
+    fn roll(&self, rng: &mut RandomNumberGenerator) -> i32 {
+        match *self {
+            Term::Constant(value) => value,
+            Term::Dice {
+                negative,
+                count,
+                sides,
+                explode,
+                keep,
+            } => {
+                let mut rolls: Vec<i32> =
+                    (0..count).map(|_| roll_die(rng, sides, explode)).collect();
+                rolls.sort_unstable();
+                let kept = match keep {
+                    None => &rolls[..],
+                    Some(Keep::Highest(n)) => &rolls[rolls.len() - n as usize..],
+                    Some(Keep::Lowest(n)) => &rolls[..n as usize],
+                };
+                let total = kept
+                    .iter()
+                    .fold(0i32, |total, roll| total.saturating_add(*roll));
+                if negative {
+                    total.saturating_neg()
+                } else {
+                    total
+                }
+            }
+        }
+    }
+}
+
+fn roll_die(rng: &mut RandomNumberGenerator, sides: i32, explode: bool) -> i32 {
+    let mut total = 0;
+    for _ in 0..=MAX_EXPLOSIONS {
+        let roll = rng.roll_dice(1, sides);
+        total = i32::saturating_add(total, roll);
+        if !explode || roll < sides {
+            break;
+        }
+    }
+    total
+}
+
+impl fmt::Display for DiceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            match *term {
+                Term::Constant(value) if i > 0 && value >= 0 => write!(f, "+{}", value)?,
+                Term::Constant(value) => write!(f, "{}", value)?,
+                Term::Dice {
+                    negative,
+                    count,
+                    sides,
+                    explode,
+                    keep,
+                } => {
+                    if negative {
+                        write!(f, "-")?;
+                    } else if i > 0 {
+                        write!(f, "+")?;
+                    }
+                    write!(f, "{}d{}", count, sides)?;
+                    if explode {
+                        write!(f, "!")?;
+                    }
+                    match keep {
+                        Some(Keep::Highest(n)) => write!(f, "kh{}", n)?,
+                        Some(Keep::Lowest(n)) => write!(f, "kl{}", n)?,
+                        None => (),
+                    }
+                }
+            }
+        }
+        if let Some(min) = self.min {
+            write!(f, "min{}", min)?;
+        }
+        if let Some(max) = self.max {
+            write!(f, "max{}", max)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DiceExpr {
+    type Err = DiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+        };
+        parser.expr().map_err(|reason| DiceError {
+            expr: s.to_string(),
+            reason,
+        })
+    }
+}
+
+/// Recursive descent over the grammar:
+///
+/// ```text
+/// expr  := ['+' | '-'] term (('+' | '-') term)* ('min' int | 'max' int)*
+/// term  := int | [int] 'd' int ['!'] [('kh' | 'kl') int]
+/// ```
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn expr(&mut self) -> Result<DiceExpr, String> {
+        let mut result = DiceExpr {
+            terms: Vec::new(),
+            min: None,
+            max: None,
+        };
+
+        let mut negative = self.eat("-");
+        if !negative {
+            self.eat("+");
+        }
+        loop {
+            result.terms.push(self.term(negative)?);
+            if self.eat("+") {
+                negative = false;
+            } else if self.eat("-") {
+                negative = true;
+            } else {
+                break;
+            }
+        }
+
+        loop {
+            if self.eat("min") {
+                result.min = Some(self.signed_int("min")?);
+            } else if self.eat("max") {
+                result.max = Some(self.signed_int("max")?);
+            } else {
+                break;
+            }
+        }
+
+        if let Some(c) = self.peek() {
+            return Err(format!("unexpected '{}'", c));
+        }
+        if let (Some(min), Some(max)) = (result.min, result.max) {
+            if min > max {
+                return Err(format!("min {} is above max {}", min, max));
+            }
+        }
+        Ok(result)
+    }
+
+    fn term(&mut self, negative: bool) -> Result<Term, String> {
+        let count = self.int()?;
+        if !self.eat("d") {
+            return match count {
+                Some(value) if negative => Ok(Term::Constant(-value)),
+                Some(value) => Ok(Term::Constant(value)),
+                None => Err(self.expected("a number or dice")),
+            };
+        }
+
+        let count = count.unwrap_or(1);
+        let sides = self
+            .int()?
+            .ok_or_else(|| self.expected("the number of sides"))?;
+        if count < 1 || sides < 1 {
+            return Err(format!("{}d{} has no dice to roll", count, sides));
+        }
+        if count > MAX_DICE {
+            return Err(format!("can't roll more than {} dice at once", MAX_DICE));
+        }
+        if sides > MAX_SIDES {
+            return Err(format!("dice can't have more than {} sides", MAX_SIDES));
+        }
+        let explode = self.eat("!");
+        if explode && sides < 2 {
+            return Err(format!("a d{} can't explode", sides));
+        }
+
+        let keep = if self.eat("kh") {
+            Some(Keep::Highest(self.keep_count(count)?))
+        } else if self.eat("kl") {
+            Some(Keep::Lowest(self.keep_count(count)?))
+        } else {
+            None
+        };
+
+        Ok(Term::Dice {
+            negative,
+            count,
+            sides,
+            explode,
+            keep,
+        })
+    }
+
+    fn keep_count(&mut self, count: i32) -> Result<i32, String> {
+        let keep = self
+            .int()?
+            .ok_or_else(|| self.expected("how many dice to keep"))?;
+        if keep < 1 || keep > count {
+            return Err(format!("can't keep {} of {} dice", keep, count));
+        }
+        Ok(keep)
+    }
+
+    fn signed_int(&mut self, clamp: &str) -> Result<i32, String> {
+        let negative = self.eat("-");
+        let value = self
+            .int()?
+            .ok_or_else(|| self.expected(&format!("a number after '{}'", clamp)))?;
+        Ok(if negative { -value } else { value })
+    }
+
+    /// The number at the current position, if there is one.
+    fn int(&mut self) -> Result<Option<i32>, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("number too large: {}", digits))
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let matches = token
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += token.chars().count();
+        }
+        matches
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expected(&self, what: &str) -> String {
+        match self.peek() {
+            Some(c) => format!("expected {}, found '{}'", what, c),
+            None => format!("expected {}", what),
+        }
+    }
+}
+
+impl Serialize for DiceExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DiceExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DiceVisitor)
+    }
+}
+
+struct DiceVisitor;
+
+impl<'de> Visitor<'de> for DiceVisitor {
+    type Value = DiceExpr;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a dice expression such as 2d6+1, or a whole number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<DiceExpr, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<DiceExpr, E> {
+        i32::try_from(v)
+            .map(DiceExpr::constant)
+            .map_err(|_| E::custom(format!("{} is too large for dice", v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<DiceExpr, E> {
+        i32::try_from(v)
+            .map(DiceExpr::constant)
+            .map_err(|_| E::custom(format!("{} is too large for dice", v)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> DiceExpr {
+        s.parse().unwrap()
+    }
+
+    fn error(s: &str) -> String {
+        s.parse::<DiceExpr>().unwrap_err().reason
+    }
+
+    fn rolls(expr: &DiceExpr) -> Vec<i32> {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        (0..500).map(|_| expr.roll(&mut rng)).collect()
+    }
+
+    #[test]
+    fn parses_dice_count_and_sides() {
+        assert_eq!(parse("2d6"), DiceExpr::dice(2, 6));
+        assert_eq!(parse("d8"), DiceExpr::dice(1, 8));
+        assert_eq!(parse("5"), DiceExpr::constant(5));
+        assert_eq!(parse(" 1d4 + 2 ").to_string(), "1d4+2");
+        assert!(rolls(&parse("3d6")).iter().all(|r| (3..=18).contains(r)));
+    }
+
+    #[test]
+    fn rejects_dice_without_faces_or_count() {
+        assert_eq!(error("0d6"), "0d6 has no dice to roll");
+        assert_eq!(error("1d0"), "1d0 has no dice to roll");
+        assert_eq!(error("1d"), "expected the number of sides");
+        assert_eq!(error("2d6+"), "expected a number or dice");
+        assert_eq!(error("2x6"), "unexpected 'x'");
+    }
+
+    #[test]
+    fn caps_dice_count_and_sides() {
+        assert!("100d1000".parse::<DiceExpr>().is_ok());
+        assert_eq!(error("101d6"), "can't roll more than 100 dice at once");
+        assert_eq!(error("1d1001"), "dice can't have more than 1000 sides");
+    }
+
+    #[test]
+    fn reports_numbers_too_large() {
+        assert_eq!(error("99999999999d6"), "number too large: 99999999999");
+        assert_eq!(error("1d6+4294967296"), "number too large: 4294967296");
+        assert_eq!(error("1d6min-3000000000"), "number too large: 3000000000");
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        assert_eq!(rolls(&parse("2147483647+1")), vec![i32::MAX; 500]);
+        assert_eq!(rolls(&parse("-2147483647-1d6")), vec![i32::MIN; 500]);
+        assert_eq!(
+            parse("1d6+2147483647").plus(1).to_string(),
+            "1d6+2147483647"
+        );
+        assert_eq!(parse("1d6-5").plus(i32::MIN).to_string(), "1d6-2147483648");
+    }
+
+    #[test]
+    fn explodes_on_highest_face() {
+        let expr = parse("1d2!");
+        let results = rolls(&expr);
+        assert!(results.iter().all(|r| *r >= 1));
+        assert!(results.iter().any(|r| *r > 2));
+        assert_eq!(error("1d1!"), "a d1 can't explode");
+    }
+
+    #[test]
+    fn keeps_highest_or_lowest() {
+        assert!(rolls(&parse("4d6kh3")).iter().all(|r| (3..=18).contains(r)));
+        assert!(rolls(&parse("2d20kl1"))
+            .iter()
+            .all(|r| (1..=20).contains(r)));
+        assert_eq!(parse("4d6kh4").to_string(), "4d6kh4");
+        assert_eq!(error("2d20kh3"), "can't keep 3 of 2 dice");
+        assert_eq!(error("2d20kl0"), "can't keep 0 of 2 dice");
+    }
+
+    #[test]
+    fn clamps_with_min_and_max() {
+        assert!(rolls(&parse("1d6-2min1"))
+            .iter()
+            .all(|r| (1..=4).contains(r)));
+        assert!(rolls(&parse("1d20max10"))
+            .iter()
+            .all(|r| (1..=10).contains(r)));
+        assert_eq!(error("1d6min4max2"), "min 4 is above max 2");
+    }
+
+    #[test]
+    fn plus_folds_into_the_constant() {
+        assert_eq!(DiceExpr::dice(1, 6).plus(2).to_string(), "1d6+2");
+        assert_eq!(parse("1d6+2").plus(3).to_string(), "1d6+5");
+        assert_eq!(parse("1d6+2").plus(-2).to_string(), "1d6");
+        assert_eq!(parse("1d6").plus(-1).to_string(), "1d6-1");
+        assert_eq!(DiceExpr::constant(0).plus(0).to_string(), "0");
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in [
+            "2d6",
+            "-1d4+3",
+            "1d6!+2d8kh1-1",
+            "4d6kl2",
+            "1d6-2min1",
+            "2d10min-5max15",
+        ] {
+            assert_eq!(parse(s).to_string(), s);
+            assert_eq!(parse(&parse(s).to_string()), parse(s));
+        }
+    }
+
+    #[test]
+    fn deserializes_strings_and_integers() {
+        let expr: DiceExpr = serde_json::from_str("\"2d6+1\"").unwrap();
+        assert_eq!(expr, parse("2d6+1"));
+        let expr: DiceExpr = serde_json::from_str("7").unwrap();
+        assert_eq!(expr, DiceExpr::constant(7));
+        let expr: DiceExpr = serde_json::from_str("-3").unwrap();
+        assert_eq!(expr, DiceExpr::constant(-3));
+        assert!(serde_json::from_str::<DiceExpr>("3000000000").is_err());
+        assert!(serde_json::from_str::<DiceExpr>("\"2d\"").is_err());
+        assert!(serde_json::from_str::<DiceExpr>("1.5").is_err());
+        assert_eq!(serde_json::to_string(&parse("1d6!")).unwrap(), "\"1d6!\"");
+    }
+}
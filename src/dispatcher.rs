@@ -0,0 +1,115 @@
+use specs::prelude::*;
+
+use crate::{
+    ai::{
+        adjacent_ai_system::AdjacentAI, approach_ai_system::ApproachAI, chase_ai_system::ChaseAI,
+        default_move_ai::DefaultMoveAI, initiative_system::InitiativeSystem,
+        quip_system::QuipSystem, turn_status_system::TurnStatusSystem,
+        visible_ai_system::VisibleAI,
+    },
+    effects,
+    encumbrance_system::EncumbranceSystem,
+    hunger_system::HungerSystem,
+    inventory_system::{
+        collection_system::ItemCollectionSystem,
+        drop_system::ItemDropSystem,
+        identification_system::ItemIdentificationSystem,
+        remove_system::ItemRemoveSystem,
+        use_equip::ItemEquipOnUse,
+        use_system::{ItemUseSystem, SpellUseSystem},
+    },
+    lighting_system::LightingSystem,
+    map_indexing_system::MapIndexingSystem,
+    melee_combat_system::MeleeCombatSystem,
+    movement_system::MovementSystem,
+    particle_system::ParticleSpawnSystem,
+    ranged_combat_system::RangedCombatSystem,
+    trigger_system::TriggerSystem,
+    visibility_system::VisibilitySystem,
+};
+
+/// Schedules the game systems. Each system lists the systems whose results it needs; the
+/// dispatcher runs systems in parallel where neither depends on the other and their data
+/// doesn't overlap. Systems sharing a resource without a dependency (the log, the RNG, the
+/// effect queue) still run one at a time, in an order fixed when the dispatcher is built, so
+/// seeded runs stay reproducible.
+pub struct SystemsDispatcher {
+    turn: Dispatcher<'static, 'static>,
+    after_effects: Dispatcher<'static, 'static>,
+}
+
+impl SystemsDispatcher {
+    pub fn new() -> Self {
+        let turn = DispatcherBuilder::new()
+            .with(MapIndexingSystem, "map_indexing", &[])
+            .with(VisibilitySystem, "visibility", &["map_indexing"])
+            .with(EncumbranceSystem, "encumbrance", &[])
+            // Speed depends on the attributes encumbrance just recalculated
+            .with(InitiativeSystem, "initiative", &["encumbrance"])
+            .with(TurnStatusSystem, "turn_status", &["initiative"])
+            // Quips need to see turns before the AI systems consume them
+            .with(QuipSystem, "quips", &["turn_status", "visibility"])
+            .with(AdjacentAI, "adjacent_ai", &["quips", "map_indexing"])
+            .with(VisibleAI, "visible_ai", &["adjacent_ai", "visibility"])
+            .with(ApproachAI, "approach_ai", &["visible_ai"])
+            .with(ChaseAI, "chase_ai", &["approach_ai"])
+            .with(DefaultMoveAI, "default_move_ai", &["chase_ai"])
+            .with(MovementSystem, "movement", &["default_move_ai"])
+            .with(TriggerSystem, "triggers", &["movement"])
+            .with(MeleeCombatSystem, "melee_combat", &["adjacent_ai"])
+            .with(
+                RangedCombatSystem,
+                "ranged_combat",
+                &["visible_ai", "movement"],
+            )
+            .with(ItemCollectionSystem, "item_collection", &[])
+            // Equipping claims its use requests before they are treated as consumables
+            .with(ItemEquipOnUse, "item_equip", &[])
+            .with(ItemUseSystem, "item_use", &["item_equip"])
+            .with(SpellUseSystem, "spell_use", &["visible_ai"])
+            .with(
+                ItemIdentificationSystem,
+                "item_identification",
+                &["item_use", "spell_use"],
+            )
+            .with(
+                ItemDropSystem,
+                "item_drop",
+                &["item_collection", "item_identification"],
+            )
+            .with(ItemRemoveSystem, "item_remove", &["item_equip"])
+            // Only the turns left over by the AI systems tick hunger
+            .with(HungerSystem, "hunger", &["default_move_ai"])
+            .build();
+
+        // Effects request particles, so these wait until the effects queue has been applied
+        let after_effects = DispatcherBuilder::new()
+            .with(ParticleSpawnSystem, "particle_spawn", &[])
+            .with(LightingSystem, "lighting", &[])
+            .build();
+
+        SystemsDispatcher {
+            turn,
+            after_effects,
+        }
+    }
+
+    /// Runs one pass of every game system, followed by the effects queue.
+    pub fn run(&mut self, ecs: &mut World) {
+        dispatch(&mut self.turn, ecs);
+        effects::run_effects_queue(ecs);
+        dispatch(&mut self.after_effects, ecs);
+        ecs.maintain();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn dispatch(dispatcher: &mut Dispatcher<'static, 'static>, ecs: &World) {
+    dispatcher.dispatch(ecs);
+}
+
+// There are no threads to dispatch to in the browser
+#[cfg(target_arch = "wasm32")]
+fn dispatch(dispatcher: &mut Dispatcher<'static, 'static>, ecs: &World) {
+    dispatcher.dispatch_seq(ecs);
+}
@@ -5,10 +5,10 @@ use specs::{
 };
 
 use crate::{
-    gamelog::{events::record_event, Logger},
+    gamelog::{GameEvents, GameLog, Logger},
     gamesystem::{mana_at_level, player_hp_at_level},
-    spatial, Attributes, Confusion, DamageOverTime, Duration, EquipmentChanged, IsSerialized, Map,
-    Name, Player, Pools, Skills, Slow, StatusEffect,
+    Attributes, Confusion, DamageOverTime, Duration, EquipmentChanged, IsSerialized, Map, Name,
+    Player, Pools, Skills, Slow, StatusEffect,
 };
 
 use super::{add_effect, targetting::entity_position, EffectSpawner, EffectType, Targets};
@@ -30,8 +30,14 @@ pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
 
         if let EffectType::Damage { amount } = damage.effect_type {
             pool.hit_points.current -= amount;
-            add_effect(None, EffectType::Bloodstain, Targets::Single { target });
             add_effect(
+                ecs,
+                None,
+                EffectType::Bloodstain,
+                Targets::Single { target },
+            );
+            add_effect(
+                ecs,
                 None,
                 EffectType::Particle {
                     glyph: rltk::to_cp437('‼'),
@@ -43,14 +49,17 @@ pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
             );
 
             if target == *player_entity {
-                record_event("Damage Taken", amount);
+                ecs.fetch_mut::<GameEvents>()
+                    .record_event("Damage Taken", amount);
             }
             if damage.creator == Some(*player_entity) {
-                record_event("Damage Inflicted", amount);
+                ecs.fetch_mut::<GameEvents>()
+                    .record_event("Damage Inflicted", amount);
             }
 
             if pool.hit_points.current < 1 {
                 add_effect(
+                    ecs,
                     damage.creator,
                     EffectType::EntityDeath,
                     Targets::Single { target },
@@ -73,7 +82,9 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
     let mut attributes = ecs.write_storage::<Attributes>();
 
     if let Some(pos) = entity_position(ecs, target) {
-        spatial::remove_entity(target, pos as usize);
+        ecs.fetch_mut::<Map>()
+            .spatial
+            .remove_entity(target, pos as usize);
     }
 
     if effect.creator.is_none() {
@@ -103,7 +114,7 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
                     "Congratulations, you are now level {}",
                     player_stats.level
                 ))
-                .log();
+                .log(&mut ecs.fetch_mut::<GameLog>());
 
             // Improve a random attribute
             let mut rng = ecs.fetch_mut::<RandomNumberGenerator>();
@@ -111,22 +122,30 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
             match attr_to_boost {
                 1 => {
                     player_attributes.might.base += 1;
-                    Logger::new().green("You feel stronger!").log();
+                    Logger::new()
+                        .green("You feel stronger!")
+                        .log(&mut ecs.fetch_mut::<GameLog>());
                 }
 
                 2 => {
                     player_attributes.fitness.base += 1;
-                    Logger::new().green("You feel healther!").log();
+                    Logger::new()
+                        .green("You feel healther!")
+                        .log(&mut ecs.fetch_mut::<GameLog>());
                 }
 
                 3 => {
                     player_attributes.quickness.base += 1;
-                    Logger::new().green("You feel quicker!").log();
+                    Logger::new()
+                        .green("You feel quicker!")
+                        .log(&mut ecs.fetch_mut::<GameLog>());
                 }
 
                 _ => {
                     player_attributes.intelligence.base += 1;
-                    Logger::new().green("You feel smarter!").log();
+                    Logger::new()
+                        .green("You feel smarter!")
+                        .log(&mut ecs.fetch_mut::<GameLog>());
                 }
             }
 
@@ -158,6 +177,7 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
             for i in 0..10 {
                 if player_pos.y - i > 1 {
                     add_effect(
+                        ecs,
                         None,
                         EffectType::Particle {
                             glyph: rltk::to_cp437('░'),
@@ -187,6 +207,7 @@ pub fn heal_damage(ecs: &mut World, heal: &EffectSpawner, target: Entity) {
     if let EffectType::Healing { amount } = heal.effect_type {
         pool.hit_points.current = i32::min(pool.hit_points.max, pool.hit_points.current + amount);
         add_effect(
+            ecs,
             None,
             EffectType::Particle {
                 glyph: rltk::to_cp437('‼'),
@@ -239,6 +260,7 @@ pub fn restore_mana(ecs: &mut World, mana: &EffectSpawner, target: Entity) {
         if let EffectType::Mana { amount } = mana.effect_type {
             pool.mana.current = i32::min(pool.mana.max, pool.mana.current + amount);
             add_effect(
+                ecs,
                 None,
                 EffectType::Particle {
                     glyph: rltk::to_cp437('‼'),
@@ -1,9 +1,8 @@
 use std::collections::{HashSet, VecDeque};
-use std::sync::Mutex;
 
 use specs::{Entity, World};
 
-use crate::{spatial, AttributeBonus};
+use crate::{AttributeBonus, Map};
 
 use self::targetting::entity_position;
 
@@ -14,10 +13,6 @@ mod particles;
 pub mod targetting;
 mod triggers;
 
-lazy_static! {
-    pub static ref EFFECT_QUEUE: Mutex<VecDeque<EffectSpawner>> = Mutex::new(VecDeque::new());
-}
-
 pub enum EffectType {
     Damage {
         amount: i32,
@@ -74,6 +69,11 @@ pub enum EffectType {
     DamageOverTime {
         damage: i32,
     },
+    /// The effects of the `bonus`th bonus of an item set, as it becomes active.
+    ItemSetBonus {
+        set: String,
+        bonus: usize,
+    },
 }
 
 #[derive(Clone)]
@@ -91,19 +91,39 @@ pub struct EffectSpawner {
     dedupe: HashSet<Entity>,
 }
 
-pub fn add_effect(creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
-    EFFECT_QUEUE.lock().unwrap().push_back(EffectSpawner {
-        creator,
-        effect_type,
-        targets,
-        dedupe: HashSet::new(),
-    });
+/// Effects waiting to be applied, stored as a `World` resource. Systems queue effects here and
+/// `run_effects_queue` applies them once the systems have run.
+#[derive(Default)]
+pub struct EffectQueue {
+    queue: VecDeque<EffectSpawner>,
+}
+
+impl EffectQueue {
+    pub fn add_effect(
+        &mut self,
+        creator: Option<Entity>,
+        effect_type: EffectType,
+        targets: Targets,
+    ) {
+        self.queue.push_back(EffectSpawner {
+            creator,
+            effect_type,
+            targets,
+            dedupe: HashSet::new(),
+        });
+    }
+}
+
+/// Queues an effect on `ecs`'s queue, for code that holds the world rather than system data.
+pub fn add_effect(ecs: &World, creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
+    ecs.fetch_mut::<EffectQueue>()
+        .add_effect(creator, effect_type, targets);
 }
 
 pub fn run_effects_queue(ecs: &mut World) {
     loop {
-        let lock = EFFECT_QUEUE.lock().unwrap().pop_front();
-        if let Some(mut effect) = lock {
+        let next = ecs.fetch_mut::<EffectQueue>().queue.pop_front();
+        if let Some(mut effect) = next {
             target_applicator(ecs, &mut effect);
         } else {
             break;
@@ -127,6 +147,11 @@ fn target_applicator(ecs: &mut World, effect: &mut EffectSpawner) {
         return;
     }
 
+    if let EffectType::ItemSetBonus { set, bonus } = &effect.effect_type {
+        triggers::item_set_trigger(effect.creator, set, *bonus, &effect.targets, ecs);
+        return;
+    }
+
     match &effect.targets.clone() {
         Targets::Tile { tile_idx } => affect_tile(ecs, effect, *tile_idx),
         Targets::Tiles { tiles } => tiles
@@ -156,9 +181,13 @@ fn tile_effect_hits_entities(effect: &EffectType) -> bool {
 
 fn affect_tile(ecs: &mut World, effect: &mut EffectSpawner, tile_idx: i32) {
     if tile_effect_hits_entities(&effect.effect_type) {
-        spatial::for_each_tile_content(tile_idx as usize, |entity| {
-            affect_entity(ecs, effect, entity)
-        });
+        let mut content = Vec::new();
+        ecs.fetch::<Map>()
+            .spatial
+            .for_each_tile_content(tile_idx as usize, |entity| content.push(entity));
+        content
+            .iter()
+            .for_each(|entity| affect_entity(ecs, effect, *entity));
     }
 
     match effect.effect_type {
@@ -199,6 +228,7 @@ fn affect_entity(ecs: &mut World, effect: &mut EffectSpawner, target: Entity) {
         EffectType::ParticleProjectile { .. }
         | EffectType::ItemUse { .. }
         | EffectType::SpellUse { .. }
-        | EffectType::TriggerFire { .. } => (),
+        | EffectType::TriggerFire { .. }
+        | EffectType::ItemSetBonus { .. } => (),
     }
 }
@@ -1,12 +1,18 @@
+use rltk::RandomNumberGenerator;
 use specs::{Entity, World, WorldExt};
 
 use crate::{
-    gamelog::Logger, raws::rawsmaster::find_spell_entity, AlwaysTargetsSelf, AreaOfEffect,
-    AttributeBonus, Confusion, Consumable, DamageOverTime, Duration, Hidden, InflictsDamage,
-    KnownSpell, KnownSpells, MagicMapper, Map, Name, Pools, Position, ProvidesFood,
-    ProvidesHealing, ProvidesIdentification, ProvidesMana, ProvidesRemoveCurse, RunState,
-    SingleActivation, Slow, SpawnParticleBurst, SpawnParticleLine, SpellTemplate, TeachesSpell,
-    TeleportTo, TownPortal,
+    gamelog::{GameLog, Logger},
+    raws::{
+        get_raws,
+        rawsmaster::{find_spell_entity, spawn_item_set_effects},
+    },
+    spawner::drop_loot,
+    AlwaysTargetsSelf, AreaOfEffect, AttributeBonus, Confusion, Consumable, DamageOverTime,
+    Duration, Hidden, InflictsDamage, KnownSpell, KnownSpells, LootTable, MagicMapper, Map, Name,
+    Pools, Position, ProvidesFood, ProvidesHealing, ProvidesIdentification, ProvidesMana,
+    ProvidesRemoveCurse, RunState, SingleActivation, Slow, SpawnParticleBurst, SpawnParticleLine,
+    SpellTemplate, TeachesSpell, TeleportTo, TownPortal,
 };
 
 use super::{
@@ -24,7 +30,7 @@ pub fn item_trigger(creator: Option<Entity>, item: Entity, targets: &Targets, ec
                 Logger::new()
                     .cyan(&ecs.read_storage::<Name>().get(item).unwrap().name)
                     .white("is out of charges!")
-                    .log();
+                    .log(&mut ecs.fetch_mut::<GameLog>());
                 return;
             }
             std::cmp::Ordering::Equal | std::cmp::Ordering::Greater => c.charges -= 1,
@@ -104,6 +110,21 @@ pub fn trigger(creator: Option<Entity>, trigger: Entity, targets: &Targets, ecs:
     }
 }
 
+/// Applies the effects of the `bonus`th bonus of `set`, then discards them.
+pub fn item_set_trigger(
+    creator: Option<Entity>,
+    set: &str,
+    bonus: usize,
+    targets: &Targets,
+    ecs: &mut World,
+) {
+    let raws = get_raws(ecs);
+    if let Some(effects) = spawn_item_set_effects(&raws, ecs, set, bonus) {
+        event_trigger(creator, effects, targets, ecs);
+        ecs.delete_entity(effects).expect("Unable to delete");
+    }
+}
+
 fn event_trigger(
     creator: Option<Entity>,
     entity: Entity,
@@ -115,6 +136,7 @@ fn event_trigger(
     // Simple particle spawn
     if let Some(part) = ecs.read_storage::<SpawnParticleBurst>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::Particle {
                 glyph: part.glyph,
@@ -152,19 +174,21 @@ fn event_trigger(
 
     // Providing food
     if ecs.read_storage::<ProvidesFood>().get(entity).is_some() {
-        add_effect(creator, EffectType::WellFed, targets.clone());
+        add_effect(ecs, creator, EffectType::WellFed, targets.clone());
         let names = ecs.read_storage::<Name>();
         Logger::new()
             .white("You eat the")
             .cyan(&names.get(entity).unwrap().name)
-            .log();
+            .log(&mut ecs.fetch_mut::<GameLog>());
         did_something = true;
     }
 
     // Magic mapper
     if ecs.read_storage::<MagicMapper>().get(entity).is_some() {
         let mut runstate = ecs.fetch_mut::<RunState>();
-        Logger::new().white("The map is revealed to you!").log();
+        Logger::new()
+            .white("The map is revealed to you!")
+            .log(&mut ecs.fetch_mut::<GameLog>());
         *runstate = RunState::MagicMapReveal { row: 0 };
         did_something = true;
     }
@@ -197,22 +221,41 @@ fn event_trigger(
         if map.depth == 1 {
             Logger::new()
                 .white("You are already in town, so the scroll does nothing.")
-                .log();
+                .log(&mut ecs.fetch_mut::<GameLog>());
         } else {
-            Logger::new().white("You are telported back to town!").log();
+            Logger::new()
+                .white("You are telported back to town!")
+                .log(&mut ecs.fetch_mut::<GameLog>());
             let mut runstate = ecs.fetch_mut::<RunState>();
             *runstate = RunState::TownPortal;
             did_something = true;
         }
     }
 
+    // Loot, e.g. opening a chest
+    let loot = ecs.read_storage::<LootTable>().get(entity).cloned();
+    let loot_pos = ecs.read_storage::<Position>().get(entity).copied();
+    if let (Some(loot), Some(pos)) = (loot, loot_pos) {
+        if let Some(name) = ecs.read_storage::<Name>().get(entity) {
+            Logger::new()
+                .white("The")
+                .cyan(&name.name)
+                .white("springs open!")
+                .log(&mut ecs.fetch_mut::<GameLog>());
+        }
+        drop_loot(ecs, &loot.name, pos.x, pos.y);
+        did_something = true;
+    }
+
     // Healing
     if let Some(heal) = ecs.read_storage::<ProvidesHealing>().get(entity) {
+        let amount = heal
+            .heal_amount
+            .roll(&mut ecs.fetch_mut::<RandomNumberGenerator>());
         add_effect(
+            ecs,
             creator,
-            EffectType::Healing {
-                amount: heal.heal_amount,
-            },
+            EffectType::Healing { amount },
             targets.clone(),
         );
         did_something = true;
@@ -221,6 +264,7 @@ fn event_trigger(
     // Mana
     if let Some(mana) = ecs.read_storage::<ProvidesMana>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::Mana {
                 amount: mana.mana_amount,
@@ -233,6 +277,7 @@ fn event_trigger(
     // Damage
     if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::Damage {
                 amount: damage.damage,
@@ -248,6 +293,7 @@ fn event_trigger(
         ecs.read_storage::<Duration>().get(entity),
     ) {
         add_effect(
+            ecs,
             creator,
             EffectType::Confusion {
                 turns: duration.turns,
@@ -260,6 +306,7 @@ fn event_trigger(
     // Teleport
     if let Some(teleport) = ecs.read_storage::<TeleportTo>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::TeleportTo {
                 x: teleport.x,
@@ -275,6 +322,7 @@ fn event_trigger(
     // Attribute Modifiers
     if let Some(attr) = ecs.read_storage::<AttributeBonus>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::AttributeEffect {
                 bonus: attr.clone(),
@@ -313,6 +361,7 @@ fn event_trigger(
     // Slow
     if let Some(slow) = ecs.read_storage::<Slow>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::Slow {
                 initiative_penalty: slow.initiative_penalty,
@@ -325,6 +374,7 @@ fn event_trigger(
     // Damage Over Time
     if let Some(damage) = ecs.read_storage::<DamageOverTime>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::DamageOverTime {
                 damage: damage.damage,
@@ -344,6 +394,7 @@ fn spawn_line_particles(ecs: &World, start: i32, end: i32, part: &SpawnParticleL
     let line = rltk::line2d(rltk::LineAlg::Bresenham, start_pt, end_pt);
     for pt in line.iter() {
         add_effect(
+            ecs,
             None,
             EffectType::Particle {
                 glyph: part.glyph,
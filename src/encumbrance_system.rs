@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
-use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    gamelog::Logger, gamesystem::attr_bonus, AttributeBonus, Attributes, EquipmentChanged,
-    Equipped, InBackpack, Item, Pools, Slow, StatusEffect,
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, Logger},
+    gamesystem::attr_bonus,
+    raws::rawsmaster::{get_item_set, string_to_skill, RawMaster},
+    AttributeBonus, Attributes, EquipmentChanged, Equipped, InBackpack, Item, ItemSetBonuses,
+    ItemSetPiece, ItemSetProgress, Pools, Slow, StatusEffect,
 };
 
 pub struct EncumbranceSystem;
@@ -22,6 +26,11 @@ impl<'a> System<'a> for EncumbranceSystem {
         ReadStorage<'a, AttributeBonus>,
         ReadStorage<'a, StatusEffect>,
         ReadStorage<'a, Slow>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, ItemSetPiece>,
+        WriteStorage<'a, ItemSetBonuses>,
+        ReadExpect<'a, Arc<RawMaster>>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -37,6 +46,11 @@ impl<'a> System<'a> for EncumbranceSystem {
             attrbonuses,
             statuses,
             slowed,
+            mut gamelog,
+            set_pieces,
+            mut set_bonuses,
+            raws,
+            mut effects,
         ) = data;
 
         #[derive(Default)]
@@ -47,6 +61,8 @@ impl<'a> System<'a> for EncumbranceSystem {
             fitness: i32,
             quickness: i32,
             intelligence: i32,
+            /// Equipped pieces of each item set
+            set_pieces: HashMap<String, i32>,
         }
 
         if equip_dirty.is_empty() {
@@ -73,6 +89,85 @@ impl<'a> System<'a> for EncumbranceSystem {
                     totals.quickness += attr.quickness.unwrap_or(0);
                     totals.intelligence += attr.intelligence.unwrap_or(0);
                 }
+                if let Some(piece) = set_pieces.get(entity) {
+                    *totals.set_pieces.entry(piece.set.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Total up item set bonuses
+        for (entity, totals) in to_update.iter_mut() {
+            let mut bonuses = ItemSetBonuses::default();
+            let mut sets: Vec<_> = totals.set_pieces.iter().collect();
+            sets.sort();
+            for (set_name, equipped) in sets {
+                let Some(item_set) = get_item_set(&raws, set_name) else {
+                    continue;
+                };
+                let was_equipped = set_bonuses
+                    .get(*entity)
+                    .and_then(|old| old.sets.iter().find(|s| s.name == *set_name))
+                    .map_or(0, |s| s.equipped);
+                let mut active = false;
+                for (i, bonus) in item_set.bonuses.iter().enumerate() {
+                    if bonus.pieces > *equipped {
+                        continue;
+                    }
+                    active = true;
+                    // Effects apply once, as the bonus becomes active
+                    if bonus.effects.is_some() && bonus.pieces > was_equipped {
+                        effects.add_effect(
+                            Some(*entity),
+                            EffectType::ItemSetBonus {
+                                set: set_name.clone(),
+                                bonus: i,
+                            },
+                            Targets::Single { target: *entity },
+                        );
+                    }
+                    if let Some(attr) = &bonus.attributes {
+                        totals.might += attr.might.unwrap_or(0);
+                        totals.fitness += attr.fitness.unwrap_or(0);
+                        totals.quickness += attr.quickness.unwrap_or(0);
+                        totals.intelligence += attr.intelligence.unwrap_or(0);
+                    }
+                    for (skill, value) in bonus.skills.iter().flatten() {
+                        if let Some(skill) = string_to_skill(skill) {
+                            *bonuses.skills.entry(skill).or_insert(0) += value;
+                        }
+                    }
+                    bonuses.armor_class += bonus.armor_class.unwrap_or(0);
+                    // A set can lift the penalty of its pieces, but not speed the wearer up
+                    totals.initiative =
+                        f32::max(0.0, totals.initiative - bonus.initiative.unwrap_or(0.0));
+                }
+
+                // Let the player know when a set starts paying off
+                let was_active = set_bonuses
+                    .get(*entity)
+                    .is_some_and(|old| old.sets.iter().any(|s| s.name == *set_name && s.active));
+                if active && !was_active && *entity == *player {
+                    Logger::new()
+                        .white("Your")
+                        .cyan(set_name)
+                        .white("set bonus is active.")
+                        .log(&mut gamelog);
+                }
+
+                bonuses.sets.push(ItemSetProgress {
+                    name: set_name.clone(),
+                    equipped: *equipped,
+                    pieces: item_set.pieces.len() as i32,
+                    active,
+                });
+            }
+
+            if bonuses.sets.is_empty() {
+                set_bonuses.remove(*entity);
+            } else {
+                set_bonuses
+                    .insert(*entity, bonuses)
+                    .expect("Unable to insert item set bonuses");
             }
         }
 
@@ -130,7 +225,7 @@ impl<'a> System<'a> for EncumbranceSystem {
                                 .orange(
                                     "You are overburdened, and suffering an initiative penalty.",
                                 )
-                                .log();
+                                .log(&mut gamelog);
                         }
                     }
                 }
@@ -1,6 +1,6 @@
 use rltk::RGB;
 
-use super::{logstore::append_entry, LogFragment};
+use super::{GameLog, LogFragment};
 
 pub struct Logger {
     // current_color: RGB,
@@ -61,7 +61,7 @@ impl Logger {
         self.add_fragment(text.into(), RGB::named(rltk::MAGENTA))
     }
 
-    pub fn log(self) {
-        append_entry(self.fragments)
+    pub fn log(self, log: &mut GameLog) {
+        log.append_entry(self.fragments)
     }
 }
@@ -1,37 +1,33 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::collections::HashMap;
 
-lazy_static! {
-    static ref EVENTS: Mutex<HashMap<String, i32>> = Mutex::new(HashMap::new());
+/// Running totals of notable events (turns taken, damage dealt...), stored as a `World` resource.
+#[derive(Clone, Default)]
+pub struct GameEvents {
+    counts: HashMap<String, i32>,
 }
 
-pub fn clear_events() {
-    EVENTS.lock().unwrap().clear();
-}
+impl GameEvents {
+    pub fn clear_events(&mut self) {
+        self.counts.clear();
+    }
 
-pub fn record_event<T: Into<String>>(event: T, n: i32) {
-    let event_name = event.into();
-    let mut events_lock = EVENTS.lock();
-    let events = events_lock.as_mut().unwrap();
+    pub fn record_event<T: Into<String>>(&mut self, event: T, n: i32) {
+        self.counts
+            .entry(event.into())
+            .and_modify(|e| *e += n)
+            .or_insert(n);
+    }
 
-    events
-        .entry(event_name)
-        .and_modify(|e| *e += n)
-        .or_insert(n);
-}
+    pub fn get_event_count<T: Into<String>>(&self, event: T) -> i32 {
+        self.counts.get(&event.into()).map_or(0, |e| *e)
+    }
 
-pub fn get_event_count<T: Into<String>>(event: T) -> i32 {
-    let events = EVENTS.lock().unwrap();
-    events.get(&event.into()).map_or(0, |e| *e)
-}
-
-#[cfg(not(target_arch = "wasm32"))]
-pub fn clone_events() -> HashMap<String, i32> {
-    EVENTS.lock().unwrap().clone()
-}
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn clone_events(&self) -> HashMap<String, i32> {
+        self.counts.clone()
+    }
 
-pub fn load_events(events: HashMap<String, i32>) {
-    EVENTS.lock().unwrap().clear();
-    events.iter().for_each(|(k, v)| {
-        EVENTS.lock().unwrap().insert(k.to_string(), *v);
-    });
+    pub fn load_events(&mut self, events: HashMap<String, i32>) {
+        self.counts = events;
+    }
 }
@@ -1,47 +1,49 @@
-use std::sync::Mutex;
-
 use rltk::{Console, Point, RGB};
 
 use crate::gamelog::LogFragment;
 
-lazy_static! {
-    static ref LOG: Mutex<Vec<Vec<LogFragment>>> = Mutex::new(Vec::new());
-}
-
-pub fn append_entry(fragments: Vec<LogFragment>) {
-    LOG.lock().unwrap().push(fragments);
+/// The message log of a game, stored as a `World` resource.
+#[derive(Clone, Default)]
+pub struct GameLog {
+    entries: Vec<Vec<LogFragment>>,
 }
 
-pub fn clear_log() {
-    LOG.lock().unwrap().clear();
-}
-
-pub fn print_log(console: &mut Box<dyn Console>, pos: Point) {
-    let mut y = pos.y;
-    let mut x = pos.x;
-    LOG.lock().unwrap().iter().rev().take(6).for_each(|log| {
-        log.iter().for_each(|frag| {
-            console.print_color(
-                x,
-                y,
-                frag.color.into(),
-                RGB::named(rltk::BLACK).into(),
-                &frag.text,
-            );
-            x += frag.text.len() as i32;
-            x += 1;
+impl GameLog {
+    pub fn append_entry(&mut self, fragments: Vec<LogFragment>) {
+        self.entries.push(fragments);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn print_log(&self, console: &mut Box<dyn Console>, pos: Point) {
+        let mut y = pos.y;
+        let mut x = pos.x;
+        self.entries.iter().rev().take(6).for_each(|log| {
+            log.iter().for_each(|frag| {
+                console.print_color(
+                    x,
+                    y,
+                    frag.color.into(),
+                    RGB::named(rltk::BLACK).into(),
+                    &frag.text,
+                );
+                x += frag.text.len() as i32;
+                x += 1;
+            });
+            y += 1;
+            x = pos.x;
         });
-        y += 1;
-        x = pos.x;
-    });
-}
+    }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn clone_log() -> Vec<Vec<LogFragment>> {
-    LOG.lock().unwrap().clone()
-}
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn clone_log(&self) -> Vec<Vec<LogFragment>> {
+        self.entries.clone()
+    }
 
-pub fn restore_log(log: &mut Vec<Vec<LogFragment>>) {
-    LOG.lock().unwrap().clear();
-    LOG.lock().unwrap().append(log);
+    pub fn restore_log(&mut self, log: &mut Vec<Vec<LogFragment>>) {
+        self.entries.clear();
+        self.entries.append(log);
+    }
 }
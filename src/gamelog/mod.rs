@@ -1,12 +1,11 @@
 use rltk::RGB;
 
 mod builder;
-pub mod events;
+mod events;
 mod logstore;
 pub use builder::*;
-#[cfg(not(target_arch = "wasm32"))]
-pub use logstore::clone_log;
-pub use logstore::{clear_log, print_log, restore_log};
+pub use events::GameEvents;
+pub use logstore::GameLog;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -4,14 +4,14 @@ use rltk::{ColorPair, DrawBatch, Point, Rltk, TextBlock, RGB};
 use specs::{Entity, Join, World, WorldExt};
 
 use crate::{
-    gamelog,
+    gamelog::GameLog,
     gui::{
         item_render::{get_item_color, get_item_display_name},
         tooltips,
     },
     map::camera::PANE_WIDTH,
     Attribute, Attributes, Consumable, Duration, Equipped, HungerClock, HungerState, InBackpack,
-    KnownSpells, Map, Name, Pools, StatusEffect, Weapon,
+    ItemSetBonuses, KnownSpells, Map, Name, Pools, StatusEffect, Weapon,
 };
 
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
@@ -146,20 +146,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
             y += 1;
 
             if let Some(weapon) = weapons.get(entity) {
-                let mut weapon_info = match weapon.damage_bonus.cmp(&0) {
-                    Ordering::Less => format!(
-                        "┤ {} ({}d{}{})",
-                        &name, weapon.damage_n_dice, weapon.damage_die_type, weapon.damage_bonus
-                    ),
-                    Ordering::Equal => format!(
-                        "┤ {} ({}d{})",
-                        &name, weapon.damage_n_dice, weapon.damage_die_type
-                    ),
-                    Ordering::Greater => format!(
-                        "┤ {} ({}d{}+{})",
-                        &name, weapon.damage_n_dice, weapon.damage_die_type, weapon.damage_bonus
-                    ),
-                };
+                let mut weapon_info = format!("┤ {} ({})", &name, weapon.damage);
 
                 if let Some(range) = weapon.range {
                     weapon_info += &format!(" (range: {range}, F to fire, V cycle targets)");
@@ -174,6 +161,19 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         }
     }
 
+    // Item set progress
+    if let Some(set_bonuses) = ecs.read_storage::<ItemSetBonuses>().get(*player_entity) {
+        for set in set_bonuses.sets.iter() {
+            let color = if set.active { green } else { box_gray };
+            draw_batch.print_color(
+                Point::new(51, y),
+                format!("{} set {}/{}", set.name, set.equipped, set.pieces),
+                ColorPair::new(color, black),
+            );
+            y += 1;
+        }
+    }
+
     // Consumables
     y += 1;
     let consumables = ecs.read_storage::<Consumable>();
@@ -250,7 +250,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 
     // Draw the log
     let block = TextBlock::new(1, 46, 79, 58);
-    gamelog::print_log(
+    ecs.fetch::<GameLog>().print_log(
         &mut rltk::BACKEND_INTERNAL.lock().consoles[1].console,
         Point::new(1, 23),
     );
@@ -12,11 +12,12 @@ pub enum CheatMenuResult {
     Heal,
     GodMode,
     GetRich,
+    ReloadRaws,
 }
 
 pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
     let mut draw_batch = DrawBatch::new();
-    let count = 6;
+    let count = 7;
     let y = 25 - (count / 2);
 
     print_item_menu(&mut draw_batch, y, 31, count as usize, "Cheating!");
@@ -55,6 +56,13 @@ pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
         &String::from("Get Rich (+100g)"),
         None,
     );
+    print_item_label(
+        &mut draw_batch,
+        y + 5,
+        'R',
+        &String::from("Reload raws"),
+        None,
+    );
 
     let _ = draw_batch.submit(6000);
 
@@ -66,6 +74,7 @@ pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
             VirtualKeyCode::H => CheatMenuResult::Heal,
             VirtualKeyCode::G => CheatMenuResult::GodMode,
             VirtualKeyCode::L => CheatMenuResult::GetRich,
+            VirtualKeyCode::R => CheatMenuResult::ReloadRaws,
             VirtualKeyCode::Escape => CheatMenuResult::Cancel,
             _ => CheatMenuResult::NoResponse,
         },
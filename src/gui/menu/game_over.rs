@@ -1,6 +1,7 @@
 use rltk::{ColorPair, DrawBatch, Rltk, RGB};
+use specs::World;
 
-use crate::gamelog::events::get_event_count;
+use crate::gamelog::GameEvents;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum GameOverResult {
@@ -8,8 +9,9 @@ pub enum GameOverResult {
     QuitToMenu,
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
     let mut draw_batch = DrawBatch::new();
+    let events = ecs.fetch::<GameEvents>();
 
     let yellow = RGB::named(rltk::YELLOW);
     let black = RGB::named(rltk::BLACK);
@@ -23,18 +25,18 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         ColorPair::new(white, black),
     );
 
-    let turns_txt = &format!("You lived for {} turns.", get_event_count("Turn"));
+    let turns_txt = &format!("You lived for {} turns.", events.get_event_count("Turn"));
     draw_batch.print_color_centered(19, turns_txt, ColorPair::new(white, black));
 
     let dmg_out_txt = &format!(
         "You inflicted {} points of damage.",
-        get_event_count("Damage Inflicted")
+        events.get_event_count("Damage Inflicted")
     );
     draw_batch.print_color_centered(20, dmg_out_txt, ColorPair::new(white, black));
 
     let dmg_in_txt = &format!(
         "You suffered {} points of damage.",
-        get_event_count("Damage Taken")
+        events.get_event_count("Damage Taken")
     );
     draw_batch.print_color_centered(21, dmg_in_txt, ColorPair::new(white, black));
 
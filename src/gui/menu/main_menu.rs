@@ -7,6 +7,7 @@ use super::print_menu_item;
 #[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuSelection {
     NewGame,
+    NewIronmanGame,
     LoadGame,
     Quit,
 }
@@ -19,7 +20,7 @@ pub enum MainMenuResult {
 
 pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     let mut draw_batch = DrawBatch::new();
-    let save_exists = saveload_system::save_exists();
+    let save_exists = saveload_system::save_exists(&*gs.storage);
     let runstate = gs.ecs.fetch::<RunState>();
 
     draw_batch.print_color_centered(
@@ -32,27 +33,22 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
         menu_selection: cur_hovering,
     } = *runstate
     {
-        print_menu_item(
-            &mut draw_batch,
-            "Begin New Game",
-            24,
-            cur_hovering == MainMenuSelection::NewGame,
-        );
-
-        if save_exists {
+        for (i, entry) in menu_entries(save_exists).iter().enumerate() {
             print_menu_item(
                 &mut draw_batch,
-                "Load Game",
-                25,
-                cur_hovering == MainMenuSelection::LoadGame,
+                entry_label(*entry),
+                24 + i as i32,
+                cur_hovering == *entry,
+            );
+        }
+
+        if let Some(error) = &gs.menu_error {
+            draw_batch.print_color_centered(
+                32,
+                error,
+                ColorPair::new(RGB::named(rltk::RED), RGB::named(rltk::BLACK)),
             );
         }
-        print_menu_item(
-            &mut draw_batch,
-            "Quit",
-            26,
-            cur_hovering == MainMenuSelection::Quit,
-        );
 
         let _ = draw_batch.submit(6000);
 
@@ -98,34 +94,41 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     }
 }
 
+/// The entries shown, top to bottom. Loading is only offered once a save exists.
+fn menu_entries(save_exists: bool) -> Vec<MainMenuSelection> {
+    let mut entries = vec![
+        MainMenuSelection::NewGame,
+        MainMenuSelection::NewIronmanGame,
+    ];
+    if save_exists {
+        entries.push(MainMenuSelection::LoadGame);
+    }
+    entries.push(MainMenuSelection::Quit);
+    entries
+}
+
+fn entry_label(entry: MainMenuSelection) -> &'static str {
+    match entry {
+        MainMenuSelection::NewGame => "Begin New Game",
+        MainMenuSelection::NewIronmanGame => "Begin Ironman Game",
+        MainMenuSelection::LoadGame => "Load Game",
+        MainMenuSelection::Quit => "Quit",
+    }
+}
+
 fn cycle_hovering(
     cur_hovering: MainMenuSelection,
     is_positive_direction: bool,
     save_exists: bool,
 ) -> MainMenuSelection {
+    let entries = menu_entries(save_exists);
+    let current = entries
+        .iter()
+        .position(|entry| *entry == cur_hovering)
+        .unwrap_or(0);
     if is_positive_direction {
-        match cur_hovering {
-            MainMenuSelection::NewGame => MainMenuSelection::Quit,
-            MainMenuSelection::LoadGame => MainMenuSelection::NewGame,
-            MainMenuSelection::Quit => {
-                if save_exists {
-                    MainMenuSelection::LoadGame
-                } else {
-                    MainMenuSelection::NewGame
-                }
-            }
-        }
+        entries[(current + entries.len() - 1) % entries.len()]
     } else {
-        match cur_hovering {
-            MainMenuSelection::NewGame => {
-                if save_exists {
-                    MainMenuSelection::LoadGame
-                } else {
-                    MainMenuSelection::Quit
-                }
-            }
-            MainMenuSelection::LoadGame => MainMenuSelection::Quit,
-            MainMenuSelection::Quit => MainMenuSelection::NewGame,
-        }
+        entries[(current + 1) % entries.len()]
     }
 }
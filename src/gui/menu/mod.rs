@@ -4,6 +4,7 @@ pub mod identify;
 pub mod main_menu;
 pub mod ranged_target;
 pub mod remove_curse;
+pub mod save_slots;
 pub mod vendor;
 
 use rltk::{ColorPair, DrawBatch, Point, Rect, Rltk, VirtualKeyCode, RGB};
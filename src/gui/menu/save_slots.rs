@@ -0,0 +1,109 @@
+use rltk::{ColorPair, DrawBatch, Rltk, VirtualKeyCode, RGB};
+
+use crate::saveload_system::{SaveFormat, SaveSlot, SaveStorage, SAVE_SLOT_COUNT};
+
+use super::print_menu_item;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SlotMenuMode {
+    NewGame { ironman: bool, format: SaveFormat },
+    LoadGame,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SaveSlotMenuResult {
+    NoSelection {
+        highlighted: SaveSlot,
+    },
+    ChangeMode {
+        mode: SlotMenuMode,
+        highlighted: SaveSlot,
+    },
+    Selected {
+        slot: SaveSlot,
+    },
+    Cancel,
+}
+
+/// Slots offered by the menu: any manual slot for a new game, only existing saves for loading.
+pub fn menu_slots(mode: SlotMenuMode, storage: &dyn SaveStorage) -> Vec<SaveSlot> {
+    match mode {
+        SlotMenuMode::NewGame { .. } => (0..SAVE_SLOT_COUNT).map(SaveSlot::Manual).collect(),
+        SlotMenuMode::LoadGame => SaveSlot::all()
+            .filter(|slot| storage.exists(*slot))
+            .collect(),
+    }
+}
+
+pub fn save_slot_menu(
+    ctx: &mut Rltk,
+    storage: &dyn SaveStorage,
+    mode: SlotMenuMode,
+    highlighted: SaveSlot,
+) -> SaveSlotMenuResult {
+    let mut draw_batch = DrawBatch::new();
+    let slots = menu_slots(mode, storage);
+    if slots.is_empty() {
+        return SaveSlotMenuResult::Cancel;
+    }
+
+    let title = match mode {
+        SlotMenuMode::NewGame { ironman: false, .. } => "Choose a slot for the new game",
+        SlotMenuMode::NewGame { ironman: true, .. } => "Choose a slot for the ironman game",
+        SlotMenuMode::LoadGame => "Load which game?",
+    };
+    draw_batch.print_color_centered(
+        15,
+        title,
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK)),
+    );
+    if let SlotMenuMode::NewGame { format, .. } = mode {
+        draw_batch.print_color_centered(
+            17,
+            &format!("Save format: {} (Tab to change)", format.name()),
+            ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK)),
+        );
+    }
+
+    for (i, slot) in slots.iter().enumerate() {
+        let label = match mode {
+            SlotMenuMode::NewGame { .. } if storage.exists(*slot) => {
+                format!("{} (overwrite)", slot.name())
+            }
+            SlotMenuMode::NewGame { .. } => format!("{} (empty)", slot.name()),
+            SlotMenuMode::LoadGame => slot.name(),
+        };
+        print_menu_item(&mut draw_batch, &label, 24 + i as i32, *slot == highlighted);
+    }
+
+    let _ = draw_batch.submit(6000);
+
+    let current = slots
+        .iter()
+        .position(|slot| *slot == highlighted)
+        .unwrap_or(0);
+    match ctx.key {
+        None => SaveSlotMenuResult::NoSelection { highlighted },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => SaveSlotMenuResult::Cancel,
+            VirtualKeyCode::Up => SaveSlotMenuResult::NoSelection {
+                highlighted: slots[(current + slots.len() - 1) % slots.len()],
+            },
+            VirtualKeyCode::Down => SaveSlotMenuResult::NoSelection {
+                highlighted: slots[(current + 1) % slots.len()],
+            },
+            VirtualKeyCode::Return => SaveSlotMenuResult::Selected { slot: highlighted },
+            VirtualKeyCode::Tab => match mode {
+                SlotMenuMode::NewGame { ironman, format } => SaveSlotMenuResult::ChangeMode {
+                    mode: SlotMenuMode::NewGame {
+                        ironman,
+                        format: format.toggled(),
+                    },
+                    highlighted,
+                },
+                SlotMenuMode::LoadGame => SaveSlotMenuResult::NoSelection { highlighted },
+            },
+            _ => SaveSlotMenuResult::NoSelection { highlighted },
+        },
+    }
+}
@@ -3,7 +3,7 @@ use specs::{Entity, Join, WorldExt};
 
 use crate::{
     gui::item_render::{get_item_color, get_item_display_name},
-    raws::{rawsmaster::get_vendor_items, RAWS},
+    raws::{get_raws, rawsmaster::get_vendor_items},
     InBackpack, Item, Name, State, Vendor, VendorMode,
 };
 
@@ -109,10 +109,7 @@ fn vendor_buy_menu(
     let mut draw_batch = DrawBatch::new();
 
     let vendors = gs.ecs.read_storage::<Vendor>();
-    let inventory = get_vendor_items(
-        &vendors.get(vendor).unwrap().categories,
-        &RAWS.lock().unwrap(),
-    );
+    let inventory = get_vendor_items(&vendors.get(vendor).unwrap().categories, &get_raws(&gs.ecs));
     let count = inventory.len();
 
     let mut y = (25 - (count / 2)) as i32;
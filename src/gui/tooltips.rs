@@ -1,11 +1,12 @@
 use std::cmp::Ordering;
 
 use rltk::{ColorPair, DrawBatch, Point, Rect, Rltk, RGB};
-use specs::{Join, World, WorldExt};
+use specs::{Entity, Join, World, WorldExt};
 
 use crate::{
     map::camera::{self, PANE_WIDTH},
-    spatial, Attributes, Duration, Hidden, Map, Name, Pools, StatusEffect,
+    raws::{get_raws, rawsmaster::get_item_set},
+    Attributes, Duration, Hidden, ItemSetBonuses, ItemSetPiece, Map, Name, Pools, StatusEffect,
 };
 
 use super::item_render::get_item_display_name;
@@ -71,6 +72,10 @@ pub fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let statuses = ecs.read_storage::<StatusEffect>();
     let durations = ecs.read_storage::<Duration>();
     let names = ecs.read_storage::<Name>();
+    let set_pieces = ecs.read_storage::<ItemSetPiece>();
+    let set_bonuses = ecs.read_storage::<ItemSetBonuses>();
+    let player_entity = ecs.fetch::<Entity>();
+    let raws = get_raws(ecs);
 
     let mouse_pos = ctx.mouse_pos();
     let mut mouse_map_pos = mouse_pos;
@@ -94,7 +99,7 @@ pub fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
 
     // Check if mouse-pos is on an entity, add its TT
     let mut tip_boxes: Vec<Tooltip> = Vec::new();
-    spatial::for_each_tile_content(mouse_idx, |entity| {
+    map.spatial.for_each_tile_content(mouse_idx, |entity| {
         if hidden.get(entity).is_some() {
             return;
         }
@@ -137,6 +142,33 @@ pub fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
             tip.add_line(tip_text);
         }
 
+        // Comment on item set progress
+        if let Some(piece) = set_pieces.get(entity) {
+            if let Some(item_set) = get_item_set(&raws, &piece.set) {
+                let equipped = set_bonuses
+                    .get(*player_entity)
+                    .and_then(|bonuses| bonuses.sets.iter().find(|s| s.name == piece.set))
+                    .map_or(0, |progress| progress.equipped);
+                tip.add_line(format!(
+                    "{} set ({}/{} worn)",
+                    item_set.name,
+                    equipped,
+                    item_set.pieces.len()
+                ));
+                for bonus in item_set.bonuses.iter() {
+                    let effects: Vec<String> = bonus
+                        .effects
+                        .iter()
+                        .flatten()
+                        .filter_map(|effect| effect.describe())
+                        .collect();
+                    if !effects.is_empty() {
+                        tip.add_line(format!("{} worn: {}", bonus.pieces, effects.join(", ")));
+                    }
+                }
+            }
+        }
+
         // Comment on pools
         let stat = pools.get(entity);
         if let Some(stat) = stat {
@@ -0,0 +1,236 @@
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+use crate::{
+    damage_system,
+    gamelog::GameEvents,
+    init_game_world,
+    map::{Map, TileType},
+    particle_system,
+    player::{perform_command, PlayerCommand},
+    register_components,
+    replay::{load_replay, ReplayPlayer},
+    run_seed::new_run_seed,
+    saveload_system::MemoryStorage,
+    Pools, RunState, State,
+};
+
+/// Simulated frame time used to age particles, since there is no render loop to drive them.
+const FRAME_TIME_MS: f32 = 100.0;
+
+/// Drives the game state machine without an Rltk context. Player actions are fed in as
+/// `PlayerCommand`s; menus can't be answered headlessly, so they are treated as cancelled.
+pub struct HeadlessGame {
+    pub gs: State,
+    pub turn: i32,
+}
+
+impl HeadlessGame {
+    pub fn new(seed: u64) -> Self {
+        let mut gs = State::new(RunState::PreRun);
+        // Headless runs must never touch the player's saves
+        gs.storage = Box::new(MemoryStorage::default());
+        register_components(&mut gs.ecs);
+        init_game_world(&mut gs.ecs, seed);
+        gs.generate_world_map(1, 0);
+
+        let mut game = HeadlessGame { gs, turn: 0 };
+        game.advance(RunState::PreRun);
+        game
+    }
+
+    pub fn runstate(&self) -> RunState {
+        *self.gs.ecs.fetch::<RunState>()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.runstate() == RunState::GameOver
+    }
+
+    /// Applies a player command and runs the world until the player is asked for input again.
+    /// Returns the state the game settled in: `AwaitingInput`, or `GameOver` if the player died.
+    pub fn step(&mut self, command: PlayerCommand) -> RunState {
+        if self.runstate() != RunState::AwaitingInput {
+            return self.runstate();
+        }
+
+        let newrunstate = perform_command(&mut self.gs.ecs, command);
+        if newrunstate != RunState::AwaitingInput {
+            self.turn += 1;
+        }
+        self.advance(newrunstate)
+    }
+
+    /// Steps through every state that doesn't need the player, mirroring `GameState::tick`.
+    fn advance(&mut self, mut newrunstate: RunState) -> RunState {
+        while newrunstate != RunState::AwaitingInput && newrunstate != RunState::GameOver {
+            newrunstate = match newrunstate {
+                RunState::PreRun => {
+                    self.gs.run_systems();
+                    self.gs.ecs.maintain();
+                    RunState::AwaitingInput
+                }
+                RunState::Ticking => self.gs.run_ticking(),
+                RunState::NextLevel => {
+                    self.gs.goto_level(1);
+                    RunState::PreRun
+                }
+                RunState::PreviousLevel => {
+                    self.gs.goto_level(-1);
+                    RunState::PreRun
+                }
+                RunState::TownPortal => {
+                    self.gs.town_portal();
+                    RunState::PreRun
+                }
+                RunState::TeleportingToOtherLevel { x, y, depth } => {
+                    self.gs.teleport_to_other_level(x, y, depth);
+                    RunState::PreRun
+                }
+                RunState::MagicMapReveal { .. } => {
+                    let mut map = self.gs.ecs.fetch_mut::<Map>();
+                    map.revealed_tiles.iter_mut().for_each(|v| *v = true);
+                    RunState::Ticking
+                }
+                RunState::MapGeneration => self.gs.mapgen_next_state.unwrap_or(RunState::PreRun),
+                _ => RunState::AwaitingInput,
+            };
+
+            *self.gs.ecs.write_resource::<RunState>() = newrunstate;
+            damage_system::delete_the_dead(&mut self.gs.ecs);
+            particle_system::update_particles(&mut self.gs.ecs, FRAME_TIME_MS);
+            newrunstate = self.runstate();
+        }
+        newrunstate
+    }
+}
+
+/// Returns the number of turns to simulate if the game was started with `--headless [turns]`.
+/// Combine with `--seed <n>` to repeat a run, or with `--replay <path>` to play back a recording.
+pub fn turns_from_args() -> Option<i32> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--headless");
+    args.next()?;
+    Some(args.next().and_then(|n| n.parse().ok()).unwrap_or(1000))
+}
+
+/// Plays a game with a random-walking player that takes every staircase down it finds, then
+/// prints a summary. Intended for quick balance checks on machines without a display.
+pub fn run_balance_experiment(turns: i32) {
+    let seed = new_run_seed();
+    let mut game = HeadlessGame::new(seed);
+    let mut rng = RandomNumberGenerator::seeded(seed);
+
+    while game.turn < turns && !game.is_game_over() {
+        let command = random_walk_command(&game, &mut rng);
+        game.step(command);
+    }
+
+    print_summary(&game, seed);
+}
+
+/// Takes the stairs down if the player is on them, otherwise moves in a random direction or
+/// waits.
+fn random_walk_command(game: &HeadlessGame, rng: &mut RandomNumberGenerator) -> PlayerCommand {
+    let on_down_stairs = {
+        let map = game.gs.ecs.fetch::<Map>();
+        let ppos = game.gs.ecs.fetch::<rltk::Point>();
+        map.tiles[map.xy_idx(ppos.x, ppos.y)] == TileType::DownStairs
+    };
+    if on_down_stairs {
+        return PlayerCommand::DescendStairs;
+    }
+    match rng.roll_dice(1, 9) {
+        1 => PlayerCommand::Move { dx: -1, dy: 0 },
+        2 => PlayerCommand::Move { dx: 1, dy: 0 },
+        3 => PlayerCommand::Move { dx: 0, dy: -1 },
+        4 => PlayerCommand::Move { dx: 0, dy: 1 },
+        5 => PlayerCommand::Move { dx: -1, dy: -1 },
+        6 => PlayerCommand::Move { dx: 1, dy: 1 },
+        7 => PlayerCommand::Move { dx: -1, dy: 1 },
+        8 => PlayerCommand::Move { dx: 1, dy: -1 },
+        _ => PlayerCommand::SkipTurn,
+    }
+}
+
+/// Plays a recorded run back to its end and prints a summary. Exits with an error code if the
+/// run diverged from the recording, so replays can be used as regression tests.
+pub fn run_replay(path: &str) {
+    let replay = load_replay(path);
+    let seed = replay.seed;
+    let mut game = HeadlessGame::new(seed);
+    let mut player = ReplayPlayer::new(replay);
+
+    while !game.is_game_over() {
+        match player.next_command(&game.gs.ecs) {
+            Some(command) => game.step(command),
+            None => break,
+        };
+    }
+
+    print_summary(&game, seed);
+    if player.has_diverged() {
+        println!("Replay diverged from the recording");
+        std::process::exit(1);
+    }
+}
+
+fn print_summary(game: &HeadlessGame, seed: u64) {
+    let ecs = &game.gs.ecs;
+    let player_entity = *ecs.fetch::<Entity>();
+    let depth = ecs.fetch::<Map>().depth;
+    let pools = ecs.read_storage::<Pools>();
+    let player_pools = pools.get(player_entity).unwrap();
+    let events = ecs.fetch::<GameEvents>();
+    println!("Seed: {}", seed);
+    println!("Turns: {}", events.get_event_count("Turn"));
+    println!("Depth: {}", depth);
+    println!("Died: {}", game.is_game_over());
+    println!(
+        "Level: {}, XP: {}, HP: {}/{}, Gold: {}",
+        player_pools.level,
+        player_pools.xp,
+        player_pools.hit_points.current,
+        player_pools.hit_points.max,
+        player_pools.gold
+    );
+    println!(
+        "Damage inflicted: {}, damage taken: {}",
+        events.get_event_count("Damage Inflicted"),
+        events.get_event_count("Damage Taken")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where a random-walking run with `seed` ends up after `turns` player turns.
+    fn play(seed: u64, turns: i32) -> (i32, i32, rltk::Point, i32, i32, i32) {
+        let mut game = HeadlessGame::new(seed);
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        while game.turn < turns && !game.is_game_over() {
+            let command = random_walk_command(&game, &mut rng);
+            game.step(command);
+        }
+
+        let ecs = &game.gs.ecs;
+        let player_entity = *ecs.fetch::<Entity>();
+        let pools = ecs.read_storage::<Pools>();
+        let player_pools = pools.get(player_entity).unwrap();
+        (
+            game.turn,
+            ecs.fetch::<Map>().depth,
+            *ecs.fetch::<rltk::Point>(),
+            player_pools.hit_points.current,
+            player_pools.xp,
+            ecs.fetch::<GameEvents>().get_event_count("Turn"),
+        )
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same() {
+        let first = play(1234, 200);
+        assert!(first.0 > 0, "no turns were taken");
+        assert_eq!(play(1234, 200), first);
+    }
+}
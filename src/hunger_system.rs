@@ -1,6 +1,6 @@
 use crate::{
-    effects::{add_effect, EffectType, Targets},
-    gamelog::Logger,
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, Logger},
 };
 
 use super::{HungerClock, HungerState, MyTurn, RunState};
@@ -15,10 +15,13 @@ impl<'a> System<'a> for HungerSystem {
         ReadExpect<'a, Entity>, // The player
         ReadExpect<'a, RunState>,
         ReadStorage<'a, MyTurn>,
+        WriteExpect<'a, EffectQueue>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut hunger_clock, player_entity, _runstate, turns) = data;
+        let (entities, mut hunger_clock, player_entity, _runstate, turns, mut effects, mut gamelog) =
+            data;
 
         for (entity, clock, _myturn) in (&entities, &mut hunger_clock, &turns).join() {
             clock.duration -= 1;
@@ -31,21 +34,23 @@ impl<'a> System<'a> for HungerSystem {
                     clock.state = HungerState::Normal;
                     clock.duration = 200;
                     if entity == *player_entity {
-                        Logger::new().orange("You are no longer well fed").log();
+                        Logger::new()
+                            .orange("You are no longer well fed")
+                            .log(&mut gamelog);
                     }
                 }
                 HungerState::Normal => {
                     clock.state = HungerState::Hungry;
                     clock.duration = 200;
                     if entity == *player_entity {
-                        Logger::new().orange("You are hungry").log();
+                        Logger::new().orange("You are hungry").log(&mut gamelog);
                     }
                 }
                 HungerState::Hungry => {
                     clock.state = HungerState::Starving;
                     clock.duration = 200;
                     if entity == *player_entity {
-                        Logger::new().red("You are starving!").log();
+                        Logger::new().red("You are starving!").log(&mut gamelog);
                     }
                 }
                 HungerState::Starving => {
@@ -53,9 +58,9 @@ impl<'a> System<'a> for HungerSystem {
                     if entity == *player_entity {
                         Logger::new()
                             .red("Your hunger pangs are getting painful! You suffer 1 hp damage.")
-                            .log();
+                            .log(&mut gamelog);
                     }
-                    add_effect(
+                    effects.add_effect(
                         None,
                         EffectType::Damage { amount: 1 },
                         Targets::Single { target: entity },
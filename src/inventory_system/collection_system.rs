@@ -1,8 +1,10 @@
-use specs::{Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    dungeon::MasterDungeonMap, gamelog::Logger, EquipmentChanged, InBackpack, MagicItem, Name,
-    ObfuscatedName, Position, WantsToPickupItem,
+    dungeon::MasterDungeonMap,
+    gamelog::{GameLog, Logger},
+    EquipmentChanged, Gold, InBackpack, MagicItem, Name, ObfuscatedName, Pools, Position,
+    WantsToPickupItem,
 };
 
 use super::obfuscate_name;
@@ -20,6 +22,10 @@ impl<'a> System<'a> for ItemCollectionSystem {
         ReadStorage<'a, MagicItem>,
         ReadStorage<'a, ObfuscatedName>,
         ReadExpect<'a, MasterDungeonMap>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        ReadStorage<'a, Gold>,
+        WriteStorage<'a, Pools>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -33,9 +39,28 @@ impl<'a> System<'a> for ItemCollectionSystem {
             magic_items,
             obfuscated_names,
             dm,
+            mut gamelog,
+            entities,
+            gold,
+            mut pools,
         ) = data;
 
         for pickup in wants_pickup.join() {
+            // Gold goes straight into the purse rather than the backpack
+            if let Some(gold) = gold.get(pickup.item) {
+                if let Some(purse) = pools.get_mut(pickup.collected_by) {
+                    purse.gold += gold.amount as f32;
+                }
+                entities.delete(pickup.item).expect("Unable to delete gold");
+                if pickup.collected_by == *player_entity {
+                    Logger::new()
+                        .white("You pick up")
+                        .yellow(format!("{} gold", gold.amount))
+                        .log(&mut gamelog);
+                }
+                continue;
+            }
+
             positions.remove(pickup.item);
             backpack
                 .insert(
@@ -59,7 +84,7 @@ impl<'a> System<'a> for ItemCollectionSystem {
                         &obfuscated_names,
                         &dm,
                     ))
-                    .log();
+                    .log(&mut gamelog);
             }
         }
 
@@ -1,8 +1,9 @@
-use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    dungeon::MasterDungeonMap, gamelog::Logger, EquipmentChanged, InBackpack, MagicItem, Name,
-    ObfuscatedName, Position, WantsToDropItem,
+    dungeon::MasterDungeonMap,
+    gamelog::{GameLog, Logger},
+    EquipmentChanged, InBackpack, MagicItem, Name, ObfuscatedName, Position, WantsToDropItem,
 };
 
 use super::obfuscate_name;
@@ -21,6 +22,7 @@ impl<'a> System<'a> for ItemDropSystem {
         ReadStorage<'a, MagicItem>,
         ReadStorage<'a, ObfuscatedName>,
         ReadExpect<'a, MasterDungeonMap>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -35,6 +37,7 @@ impl<'a> System<'a> for ItemDropSystem {
             magic_items,
             obfuscated_names,
             dm,
+            mut gamelog,
         ) = data;
 
         for (entity, to_drop) in (&entities, &wants_drop).join() {
@@ -58,7 +61,7 @@ impl<'a> System<'a> for ItemDropSystem {
                         &obfuscated_names,
                         &dm,
                     ))
-                    .log();
+                    .log(&mut gamelog);
             }
         }
         wants_drop.clear();
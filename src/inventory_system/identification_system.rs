@@ -1,8 +1,11 @@
-use specs::{Entities, Join, ReadStorage, System, WriteExpect, WriteStorage};
+use std::sync::Arc;
+
+use specs::{Entities, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    dungeon::MasterDungeonMap, raws::rawsmaster::is_tag_magic, IdentifiedItem, Item, Name,
-    ObfuscatedName, Player,
+    dungeon::MasterDungeonMap,
+    raws::rawsmaster::{is_tag_magic, RawMaster},
+    IdentifiedItem, Item, MagicItem, Name, ObfuscatedName, Player,
 };
 
 pub struct ItemIdentificationSystem;
@@ -16,13 +19,30 @@ impl<'a> System<'a> for ItemIdentificationSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, ObfuscatedName>,
         Entities<'a>,
+        ReadExpect<'a, Arc<RawMaster>>,
+        ReadStorage<'a, MagicItem>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player, mut identified, mut dm, items, names, mut obfuscated_names, entities) = data;
+        let (
+            player,
+            mut identified,
+            mut dm,
+            items,
+            names,
+            mut obfuscated_names,
+            entities,
+            raws,
+            magic_items,
+        ) = data;
 
         for (_p, id) in (&player, &identified).join() {
-            if !dm.identified_items.contains(&id.name) && is_tag_magic(&id.name) {
+            // Items with rolled affixes aren't in the raws, but are still magic
+            let is_magic = is_tag_magic(&id.name, &raws)
+                || (&items, &names, &magic_items)
+                    .join()
+                    .any(|(_item, name, _magic)| name.name == id.name);
+            if !dm.identified_items.contains(&id.name) && is_magic {
                 dm.identified_items.insert(id.name.clone());
 
                 for (entity, _item, name) in (&entities, &items, &names).join() {
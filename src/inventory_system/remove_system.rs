@@ -1,6 +1,9 @@
-use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
-use crate::{gamelog::Logger, CursedItem, Equipped, InBackpack, Name, WantsToRemoveItem};
+use crate::{
+    gamelog::{GameLog, Logger},
+    CursedItem, Equipped, InBackpack, Name, WantsToRemoveItem,
+};
 
 pub struct ItemRemoveSystem;
 
@@ -13,11 +16,20 @@ impl<'a> System<'a> for ItemRemoveSystem {
         ReadExpect<'a, Entity>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, CursedItem>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_remove, mut equipped, mut backpack, player_entity, names, cursed) =
-            data;
+        let (
+            entities,
+            mut wants_remove,
+            mut equipped,
+            mut backpack,
+            player_entity,
+            names,
+            cursed,
+            mut gamelog,
+        ) = data;
 
         for (entity, to_remove) in (&entities, &wants_remove).join() {
             if cursed.get(to_remove.item).is_some() {
@@ -25,7 +37,7 @@ impl<'a> System<'a> for ItemRemoveSystem {
                     .white("You cannot unequip")
                     .cyan(&names.get(to_remove.item).unwrap().name)
                     .white("- it is cursed!")
-                    .log();
+                    .log(&mut gamelog);
                 continue;
             }
 
@@ -37,7 +49,7 @@ impl<'a> System<'a> for ItemRemoveSystem {
                 Logger::new()
                     .white("You unequip")
                     .cyan(&names.get(to_remove.item).unwrap().name)
-                    .log();
+                    .log(&mut gamelog);
             }
         }
 
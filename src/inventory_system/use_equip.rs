@@ -1,8 +1,9 @@
-use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    gamelog::Logger, CursedItem, EquipmentChanged, Equippable, Equipped, IdentifiedItem,
-    InBackpack, Name, WantsToUseItem,
+    gamelog::{GameLog, Logger},
+    CursedItem, EquipmentChanged, Equippable, Equipped, IdentifiedItem, InBackpack, Name,
+    WantsToUseItem,
 };
 
 pub struct ItemEquipOnUse;
@@ -19,6 +20,7 @@ impl<'a> System<'a> for ItemEquipOnUse {
         WriteStorage<'a, EquipmentChanged>,
         WriteStorage<'a, IdentifiedItem>,
         ReadStorage<'a, CursedItem>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -33,6 +35,7 @@ impl<'a> System<'a> for ItemEquipOnUse {
             mut dirty,
             mut identified_item,
             cursed,
+            mut gamelog,
         ) = data;
 
         let mut remove_use = Vec::new();
@@ -55,11 +58,14 @@ impl<'a> System<'a> for ItemEquipOnUse {
                             .white("You cannot unequip")
                             .cyan(&name.name)
                             .white("- it is cursed!")
-                            .log();
+                            .log(&mut gamelog);
                     } else {
                         to_unequip.push(item_entity);
                         if target == *player_entity {
-                            Logger::new().white("You unequip").cyan(&name.name).log();
+                            Logger::new()
+                                .white("You unequip")
+                                .cyan(&name.name)
+                                .log(&mut gamelog);
                         }
                     }
                 }
@@ -99,7 +105,7 @@ impl<'a> System<'a> for ItemEquipOnUse {
                     Logger::new()
                         .white("You equip")
                         .cyan(&names.get(useitem.item).unwrap().name)
-                        .log();
+                        .log(&mut gamelog);
                 }
             }
 
@@ -1,7 +1,7 @@
 use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    effects::{add_effect, targetting::aoe_tiles, EffectType, Targets},
+    effects::{targetting::aoe_tiles, EffectQueue, EffectType, Targets},
     AreaOfEffect, EquipmentChanged, IdentifiedItem, Map, Name, WantsToCastSpell, WantsToUseItem,
 };
 
@@ -10,13 +10,14 @@ pub struct ItemUseSystem;
 impl<'a> System<'a> for ItemUseSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, WantsToUseItem>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, AreaOfEffect>,
         WriteStorage<'a, EquipmentChanged>,
         WriteStorage<'a, IdentifiedItem>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -29,6 +30,7 @@ impl<'a> System<'a> for ItemUseSystem {
             aoe,
             mut dirty,
             mut identified_item,
+            mut effects,
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
@@ -49,7 +51,7 @@ impl<'a> System<'a> for ItemUseSystem {
             }
 
             // Call the effects system
-            add_effect(
+            effects.add_effect(
                 Some(entity),
                 EffectType::ItemUse { item: useitem.item },
                 match useitem.target {
@@ -80,13 +82,14 @@ pub struct SpellUseSystem;
 impl<'a> System<'a> for SpellUseSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, WantsToCastSpell>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, AreaOfEffect>,
         WriteStorage<'a, EquipmentChanged>,
         WriteStorage<'a, IdentifiedItem>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -99,6 +102,7 @@ impl<'a> System<'a> for SpellUseSystem {
             aoe,
             mut dirty,
             mut identified_item,
+            mut effects,
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
@@ -119,7 +123,7 @@ impl<'a> System<'a> for SpellUseSystem {
             }
 
             // Call the effects system
-            add_effect(
+            effects.add_effect(
                 Some(entity),
                 EffectType::SpellUse {
                     spell: useitem.spell,
@@ -1,32 +1,19 @@
-use ai::{
-    adjacent_ai_system::AdjacentAI, approach_ai_system::ApproachAI, chase_ai_system::ChaseAI,
-    default_move_ai::DefaultMoveAI, initiative_system::InitiativeSystem, quip_system::QuipSystem,
-    turn_status_system::TurnStatusSystem, visible_ai_system::VisibleAI,
-};
-use encumbrance_system::EncumbranceSystem;
-use gamelog::Logger;
+use dispatcher::SystemsDispatcher;
+use gamelog::{GameEvents, GameLog, Logger};
 use gui::menu::{
     cheat::{show_cheat_mode, CheatMenuResult},
     game_over::{game_over, GameOverResult},
     identify::identify_menu,
     main_menu::{main_menu, MainMenuResult, MainMenuSelection},
     remove_curse::remove_curse_menu,
+    save_slots::{menu_slots, save_slot_menu, SaveSlotMenuResult, SlotMenuMode},
     vendor::{show_vendor_menu, VendorResult},
     ItemMenuResult,
 };
-use movement_system::MovementSystem;
-use ranged_combat_system::RangedCombatSystem;
-use raws::{
-    rawsmaster::{spawn_named_item, SpawnType},
-    RAWS,
-};
+use replay::{ReplayPlayer, ReplayRecorder};
 use rltk::{GameState, Point, Rltk};
-use specs::{
-    prelude::*,
-    saveload::{SimpleMarker, SimpleMarkerAllocator},
-};
-#[macro_use]
-extern crate lazy_static;
+use saveload_system::{register_components, SaveFormat, SaveSettings, SaveSlot, SaveStorage};
+use specs::{prelude::*, saveload::SimpleMarkerAllocator};
 
 pub mod map;
 use map::{
@@ -40,40 +27,35 @@ pub mod player;
 use player::*;
 pub mod rect;
 use rect::Rect;
-pub mod visibility_system;
-use trigger_system::TriggerSystem;
-use visibility_system::VisibilitySystem;
-pub mod map_indexing_system;
-use map_indexing_system::MapIndexingSystem;
-pub mod melee_combat_system;
-use melee_combat_system::MeleeCombatSystem;
 pub mod damage_system;
 mod gamelog;
 mod gui;
 pub mod inventory_system;
+pub mod map_indexing_system;
+pub mod melee_combat_system;
 pub mod spawner;
-use inventory_system::identification_system::ItemIdentificationSystem;
-use inventory_system::remove_system::ItemRemoveSystem;
-use inventory_system::use_system::ItemUseSystem;
-use inventory_system::{collection_system::ItemCollectionSystem, use_equip::ItemEquipOnUse};
-use inventory_system::{drop_system::ItemDropSystem, use_system::SpellUseSystem};
+pub mod visibility_system;
 use map::dungeon::MasterDungeonMap;
+mod ai;
+mod dice;
+mod dispatcher;
+mod effects;
 mod gamesystem;
+mod headless;
 mod hunger_system;
 mod lighting_system;
 pub mod map_builders;
+mod movement_system;
 mod particle_system;
 mod random_table;
+mod ranged_combat_system;
 mod raws;
+mod replay;
 mod rex_assets;
+mod run_seed;
 mod saveload_system;
-mod trigger_system;
-use lighting_system::LightingSystem;
-mod ai;
-mod effects;
-mod movement_system;
-mod ranged_combat_system;
 pub mod spatial;
+mod trigger_system;
 
 const SHOW_MAPGEN_VISUALIZER: bool = false;
 const SHOW_FPS: bool = true;
@@ -95,6 +77,7 @@ pub enum RunState {
     ShowRemoveItem,
     ShowTargeting { range: i32, item: Entity },
     MainMenu { menu_selection: MainMenuSelection },
+    SaveSlotMenu { mode: SlotMenuMode, slot: SaveSlot },
     SaveGame,
     NextLevel,
     PreviousLevel,
@@ -110,6 +93,21 @@ pub enum RunState {
 }
 
 impl RunState {
+    /// States that wait on a key press or menu pick from the player.
+    pub fn awaits_player(&self) -> bool {
+        matches!(
+            self,
+            RunState::AwaitingInput
+                | RunState::ShowInventory
+                | RunState::ShowDropItem
+                | RunState::ShowRemoveItem
+                | RunState::ShowTargeting { .. }
+                | RunState::ShowVendor { .. }
+                | RunState::ShowRemoveCurse
+                | RunState::ShowIdentify
+        )
+    }
+
     pub fn buy_vendor(vendor: Entity) -> Self {
         Self::ShowVendor {
             vendor,
@@ -131,9 +129,29 @@ pub struct State {
     mapgen_history: Vec<Map>,
     mapgen_index: usize,
     mapgen_timer: f32,
+    replay: Option<ReplayPlayer>,
+    systems: SystemsDispatcher,
+    storage: Box<dyn SaveStorage>,
+    /// What went wrong on the way back to the main menu, such as a save that couldn't be
+    /// loaded, shown on the menu.
+    menu_error: Option<String>,
 }
 
 impl State {
+    pub fn new(mapgen_next_state: RunState) -> Self {
+        State {
+            ecs: World::new(),
+            mapgen_next_state: Some(mapgen_next_state),
+            mapgen_index: 0,
+            mapgen_history: Vec::new(),
+            mapgen_timer: 0.0,
+            replay: None,
+            systems: SystemsDispatcher::new(),
+            storage: saveload_system::default_storage(),
+            menu_error: None,
+        }
+    }
+
     fn generate_world_map(&mut self, new_depth: i32, offset: i32) {
         self.mapgen_index = 0;
         self.mapgen_timer = 0.0;
@@ -160,99 +178,107 @@ impl State {
     fn game_over_cleanup(&mut self) {
         self.ecs.delete_all();
 
-        // Spawn a new player
-        {
-            let player_entity = spawner::player(&mut self.ecs, Position { x: 0, y: 0 });
-            let mut player_entity_writer = self.ecs.write_resource::<Entity>();
-            *player_entity_writer = player_entity;
-        }
-
-        // Replace the world maps
-        self.ecs.insert(MasterDungeonMap::new());
+        // Start the new run from the same state a fresh game does, with its own seed
+        init_game_world(&mut self.ecs, run_seed::new_run_seed());
 
         // Build a new map and place the player
         self.generate_world_map(1, 0);
     }
 
-    fn run_systems(&mut self) {
-        let mut mapindex = MapIndexingSystem;
-        mapindex.run_now(&self.ecs);
-
-        let mut vis = VisibilitySystem;
-        vis.run_now(&self.ecs);
-
-        let mut encumbrance_system = EncumbranceSystem;
-        encumbrance_system.run_now(&self.ecs);
-
-        let mut initiative_system = InitiativeSystem;
-        initiative_system.run_now(&self.ecs);
-
-        let mut turn_status_system = TurnStatusSystem;
-        turn_status_system.run_now(&self.ecs);
-
-        let mut quip_system = QuipSystem;
-        quip_system.run_now(&self.ecs);
-
-        let mut adjecent_ai_system = AdjacentAI;
-        adjecent_ai_system.run_now(&self.ecs);
-
-        let mut visibile_ai_system = VisibleAI;
-        visibile_ai_system.run_now(&self.ecs);
-
-        let mut approach_ai_system = ApproachAI;
-        approach_ai_system.run_now(&self.ecs);
-
-        let mut chase_ai_system = ChaseAI;
-        chase_ai_system.run_now(&self.ecs);
-
-        let mut default_move_ai = DefaultMoveAI;
-        default_move_ai.run_now(&self.ecs);
-
-        let mut movement_system = MovementSystem;
-        movement_system.run_now(&self.ecs);
-
-        let mut trigger_system = TriggerSystem;
-        trigger_system.run_now(&self.ecs);
-
-        let mut melee_combat_system = MeleeCombatSystem;
-        melee_combat_system.run_now(&self.ecs);
-
-        let mut ranged_combat_system = RangedCombatSystem;
-        ranged_combat_system.run_now(&self.ecs);
-
-        let mut item_collection_system = ItemCollectionSystem;
-        item_collection_system.run_now(&self.ecs);
-
-        let mut item_equip_use_system = ItemEquipOnUse;
-        item_equip_use_system.run_now(&self.ecs);
-
-        let mut item_use_system = ItemUseSystem;
-        item_use_system.run_now(&self.ecs);
-
-        let mut spell_use_system = SpellUseSystem;
-        spell_use_system.run_now(&self.ecs);
-
-        let mut item_identification_system = ItemIdentificationSystem;
-        item_identification_system.run_now(&self.ecs);
+    /// Saves the run's replay on the way to the main menu, which shows any error.
+    fn save_replay(&mut self) {
+        if let Err(error) = replay::save_replay(&self.ecs) {
+            Logger::new()
+                .red("Unable to save the replay:")
+                .white(error.to_string())
+                .log(&mut self.ecs.fetch_mut::<GameLog>());
+            self.menu_error = Some(format!("Unable to save the replay: {}", error));
+        }
+    }
 
-        let mut item_drop_system = ItemDropSystem;
-        item_drop_system.run_now(&self.ecs);
+    fn run_systems(&mut self) {
+        self.systems.run(&mut self.ecs);
+    }
 
-        let mut item_remove_system = ItemRemoveSystem;
-        item_remove_system.run_now(&self.ecs);
+    fn autosave(&mut self) {
+        // Replays mustn't overwrite the saves of the run being watched
+        if self.replay.is_some() {
+            return;
+        }
+        let slot = self.ecs.fetch::<SaveSettings>().autosave_slot();
+        if let Err(error) = saveload_system::save_game(&mut self.ecs, &mut *self.storage, slot) {
+            log_save_error(&self.ecs, slot, error);
+        }
+    }
 
-        let mut hunger_system = hunger_system::HungerSystem;
-        hunger_system.run_now(&self.ecs);
+    /// Runs all initiative cycles until it's the player's turn, or until a system requests a
+    /// state that needs handling outside of the systems loop.
+    fn run_ticking(&mut self) -> RunState {
+        let mut newrunstate = RunState::Ticking;
+        let mut should_change_target = false;
+
+        while newrunstate == RunState::Ticking {
+            self.run_systems();
+            self.ecs.maintain();
+            match *self.ecs.fetch::<RunState>() {
+                RunState::AwaitingInput => {
+                    newrunstate = RunState::AwaitingInput;
+                    should_change_target = true;
+                }
+                RunState::TownPortal => newrunstate = RunState::TownPortal,
+                RunState::ShowRemoveCurse => newrunstate = RunState::ShowRemoveCurse,
+                RunState::ShowIdentify => newrunstate = RunState::ShowIdentify,
+                RunState::TeleportingToOtherLevel { x, y, depth } => {
+                    newrunstate = RunState::TeleportingToOtherLevel { x, y, depth }
+                }
+                RunState::MagicMapReveal { .. } => {
+                    newrunstate = RunState::MagicMapReveal { row: 0 }
+                }
+                _ => newrunstate = RunState::Ticking,
+            }
+        }
+        if should_change_target {
+            player::end_turn_targeting(&mut self.ecs);
+        }
+        newrunstate
+    }
 
-        effects::run_effects_queue(&mut self.ecs);
+    /// Performs the next recorded command, or hands control back to the player once the replay
+    /// runs out.
+    fn step_replay(&mut self, runstate: RunState) -> RunState {
+        let command = self.replay.as_mut().unwrap().next_command(&self.ecs);
+        match command {
+            Some(command) => perform_command(&mut self.ecs, command),
+            None => {
+                self.replay = None;
+                Logger::new()
+                    .yellow("Replay finished.")
+                    .log(&mut self.ecs.fetch_mut::<GameLog>());
+                runstate
+            }
+        }
+    }
 
-        let mut particle_system = particle_system::ParticleSpawnSystem;
-        particle_system.run_now(&self.ecs);
+    fn town_portal(&mut self) {
+        // Spawn the portal
+        spawner::spawn_town_portal(&mut self.ecs);
 
-        let mut lighting_system = LightingSystem;
-        lighting_system.run_now(&self.ecs);
+        // Transition
+        let map_depth = self.ecs.fetch::<Map>().depth;
+        let destination_offset = 0 - (map_depth - 1);
+        self.goto_level(destination_offset);
+    }
 
-        self.ecs.maintain();
+    fn teleport_to_other_level(&mut self, x: i32, y: i32, depth: i32) {
+        self.goto_level(depth - 1);
+        let player_entity = self.ecs.fetch::<Entity>();
+        if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(*player_entity) {
+            pos.x = x;
+            pos.y = y;
+        }
+        let mut ppos = self.ecs.fetch_mut::<rltk::Point>();
+        ppos.x = x;
+        ppos.y = y;
     }
 }
 
@@ -263,11 +289,12 @@ impl GameState for State {
         ctx.cls();
         ctx.set_active_console(0);
         ctx.cls();
-        particle_system::update_particles(&mut self.ecs, ctx);
+        particle_system::update_particles(&mut self.ecs, ctx.frame_time_ms);
 
         // Either draw Main Menu or draw map
         match newrunstate {
             RunState::MainMenu { .. } => {}
+            RunState::SaveSlotMenu { .. } => {}
             RunState::GameOver => {}
             _ => {
                 camera::render_camera(&self.ecs, ctx);
@@ -280,41 +307,19 @@ impl GameState for State {
             RunState::PreRun => {
                 self.run_systems();
                 self.ecs.maintain();
+                // Every level transition, and the start of a run, passes through here
+                self.autosave();
                 newrunstate = RunState::AwaitingInput;
             }
+            // While replaying, recorded commands stand in for the player's input and menu picks
+            _ if self.replay.is_some() && newrunstate.awaits_player() => {
+                newrunstate = self.step_replay(newrunstate);
+            }
             RunState::AwaitingInput => {
                 newrunstate = player_input(self, ctx);
-                if newrunstate != RunState::AwaitingInput {
-                    gamelog::events::record_event("Turn", 1);
-                }
             }
             RunState::Ticking => {
-                let mut should_change_target = false;
-
-                // runs all initiative cycles until it's the player's turn
-                while newrunstate == RunState::Ticking {
-                    self.run_systems();
-                    self.ecs.maintain();
-                    match *self.ecs.fetch::<RunState>() {
-                        RunState::AwaitingInput => {
-                            newrunstate = RunState::AwaitingInput;
-                            should_change_target = true;
-                        }
-                        RunState::TownPortal => newrunstate = RunState::TownPortal,
-                        RunState::ShowRemoveCurse => newrunstate = RunState::ShowRemoveCurse,
-                        RunState::ShowIdentify => newrunstate = RunState::ShowIdentify,
-                        RunState::TeleportingToOtherLevel { x, y, depth } => {
-                            newrunstate = RunState::TeleportingToOtherLevel { x, y, depth }
-                        }
-                        RunState::MagicMapReveal { .. } => {
-                            newrunstate = RunState::MagicMapReveal { row: 0 }
-                        }
-                        _ => newrunstate = RunState::Ticking,
-                    }
-                }
-                if should_change_target {
-                    player::end_turn_targeting(&mut self.ecs);
-                }
+                newrunstate = self.run_ticking();
             }
             RunState::ShowDropItem => {
                 let (item_menu_result, item_entity) = gui::menu::drop_item_menu(self, ctx);
@@ -322,15 +327,12 @@ impl GameState for State {
                     ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
                     ItemMenuResult::NoResponse => {}
                     ItemMenuResult::Selected => {
-                        let item_entity = item_entity.unwrap();
-                        let mut intent = self.ecs.write_storage::<WantsToDropItem>();
-                        intent
-                            .insert(
-                                *self.ecs.fetch::<Entity>(),
-                                WantsToDropItem { item: item_entity },
-                            )
-                            .expect("Unable to insert intent");
-                        newrunstate = RunState::Ticking;
+                        newrunstate = perform_command(
+                            &mut self.ecs,
+                            PlayerCommand::DropItem {
+                                item: item_entity.unwrap(),
+                            },
+                        );
                     }
                 }
             }
@@ -340,15 +342,12 @@ impl GameState for State {
                     ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
                     ItemMenuResult::NoResponse => {}
                     ItemMenuResult::Selected => {
-                        let item_entity = item_entity.unwrap();
-                        let mut intent = self.ecs.write_storage::<WantsToRemoveItem>();
-                        intent
-                            .insert(
-                                *self.ecs.fetch::<Entity>(),
-                                WantsToRemoveItem { item: item_entity },
-                            )
-                            .expect("Unable to insert intent");
-                        newrunstate = RunState::Ticking;
+                        newrunstate = perform_command(
+                            &mut self.ecs,
+                            PlayerCommand::RemoveItem {
+                                item: item_entity.unwrap(),
+                            },
+                        );
                     }
                 }
             }
@@ -367,17 +366,13 @@ impl GameState for State {
                                 item: item_entity,
                             }
                         } else {
-                            let mut intent = self.ecs.write_storage::<WantsToUseItem>();
-                            intent
-                                .insert(
-                                    *self.ecs.fetch::<Entity>(),
-                                    WantsToUseItem {
-                                        item: item_entity,
-                                        target: None,
-                                    },
-                                )
-                                .expect("Unable to insert intent");
-                            newrunstate = RunState::Ticking;
+                            newrunstate = perform_command(
+                                &mut self.ecs,
+                                PlayerCommand::UseItem {
+                                    item: item_entity,
+                                    target: None,
+                                },
+                            );
                         }
                     }
                 }
@@ -389,31 +384,19 @@ impl GameState for State {
                     ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
                     ItemMenuResult::NoResponse => {}
                     ItemMenuResult::Selected => {
-                        if self.ecs.read_storage::<SpellTemplate>().get(item).is_some() {
-                            let mut intent = self.ecs.write_storage::<WantsToCastSpell>();
-                            intent
-                                .insert(
-                                    *self.ecs.fetch::<Entity>(),
-                                    WantsToCastSpell {
-                                        spell: item,
-                                        target: item_entity,
-                                    },
-                                )
-                                .expect("Unable to insert intent");
-                            newrunstate = RunState::Ticking;
+                        let is_spell = self.ecs.read_storage::<SpellTemplate>().get(item).is_some();
+                        let command = if is_spell {
+                            PlayerCommand::CastSpell {
+                                spell: item,
+                                target: item_entity,
+                            }
                         } else {
-                            let mut intent = self.ecs.write_storage::<WantsToUseItem>();
-                            intent
-                                .insert(
-                                    *self.ecs.fetch::<Entity>(),
-                                    WantsToUseItem {
-                                        item,
-                                        target: item_entity,
-                                    },
-                                )
-                                .expect("Unable to insert intent");
-                            newrunstate = RunState::Ticking;
-                        }
+                            PlayerCommand::UseItem {
+                                item,
+                                target: item_entity,
+                            }
+                        };
+                        newrunstate = perform_command(&mut self.ecs, command);
                     }
                 }
             }
@@ -426,11 +409,26 @@ impl GameState for State {
                         }
                     }
                     MainMenuResult::Selected { highlighted } => match highlighted {
-                        MainMenuSelection::NewGame => newrunstate = RunState::PreRun,
+                        MainMenuSelection::NewGame => {
+                            newrunstate = slot_menu(
+                                SlotMenuMode::NewGame {
+                                    ironman: false,
+                                    format: SaveFormat::default(),
+                                },
+                                &*self.storage,
+                            )
+                        }
+                        MainMenuSelection::NewIronmanGame => {
+                            newrunstate = slot_menu(
+                                SlotMenuMode::NewGame {
+                                    ironman: true,
+                                    format: SaveFormat::default(),
+                                },
+                                &*self.storage,
+                            )
+                        }
                         MainMenuSelection::LoadGame => {
-                            saveload_system::load_game(&mut self.ecs);
-                            newrunstate = RunState::AwaitingInput;
-                            saveload_system::delete_save();
+                            newrunstate = slot_menu(SlotMenuMode::LoadGame, &*self.storage)
                         }
                         MainMenuSelection::Quit => {
                             ::std::process::exit(0);
@@ -438,11 +436,82 @@ impl GameState for State {
                     },
                 }
             }
+            RunState::SaveSlotMenu { mode, slot } => {
+                // The player has moved on from the error
+                self.menu_error = None;
+                match save_slot_menu(ctx, &*self.storage, mode, slot) {
+                    SaveSlotMenuResult::NoSelection { highlighted } => {
+                        newrunstate = RunState::SaveSlotMenu {
+                            mode,
+                            slot: highlighted,
+                        }
+                    }
+                    SaveSlotMenuResult::ChangeMode { mode, highlighted } => {
+                        newrunstate = RunState::SaveSlotMenu {
+                            mode,
+                            slot: highlighted,
+                        }
+                    }
+                    SaveSlotMenuResult::Cancel => {
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: MainMenuSelection::NewGame,
+                        }
+                    }
+                    SaveSlotMenuResult::Selected { slot } => match (mode, slot) {
+                        (SlotMenuMode::NewGame { ironman, format }, SaveSlot::Manual(slot)) => {
+                            self.ecs.insert(SaveSettings {
+                                slot,
+                                ironman,
+                                format,
+                            });
+                            newrunstate = RunState::PreRun;
+                        }
+                        (SlotMenuMode::NewGame { .. }, SaveSlot::Autosave) => {}
+                        (SlotMenuMode::LoadGame, slot) => {
+                            match saveload_system::load_game(&mut self.ecs, &*self.storage, slot) {
+                                Ok(()) => {
+                                    // A loaded game can't be rebuilt from its seed alone
+                                    self.ecs.remove::<ReplayRecorder>();
+                                    newrunstate = RunState::AwaitingInput;
+                                    if self.ecs.fetch::<SaveSettings>().ironman {
+                                        if let Err(error) =
+                                            saveload_system::delete_save(&mut *self.storage, slot)
+                                        {
+                                            Logger::new()
+                                                .red(format!("Unable to delete {}:", slot.name()))
+                                                .white(error.to_string())
+                                                .log(&mut self.ecs.fetch_mut::<GameLog>());
+                                        }
+                                    }
+                                }
+                                Err(error) => {
+                                    // The failed load may have left a partial world behind
+                                    self.game_over_cleanup();
+                                    self.menu_error = Some(format!("{}: {}", slot.name(), error));
+                                    newrunstate = RunState::MainMenu {
+                                        menu_selection: MainMenuSelection::LoadGame,
+                                    };
+                                }
+                            }
+                        }
+                    },
+                }
+            }
             RunState::SaveGame => {
-                saveload_system::save_game(&mut self.ecs);
-                newrunstate = RunState::MainMenu {
-                    menu_selection: MainMenuSelection::Quit,
-                };
+                let slot = SaveSlot::Manual(self.ecs.fetch::<SaveSettings>().slot);
+                match saveload_system::save_game(&mut self.ecs, &mut *self.storage, slot) {
+                    Ok(()) => {
+                        self.save_replay();
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: MainMenuSelection::Quit,
+                        };
+                    }
+                    Err(error) => {
+                        // Keep playing rather than lose the run
+                        log_save_error(&self.ecs, slot, error);
+                        newrunstate = RunState::AwaitingInput;
+                    }
+                }
             }
             RunState::NextLevel => {
                 self.goto_level(1);
@@ -454,10 +523,22 @@ impl GameState for State {
                 newrunstate = RunState::MapGeneration;
             }
             RunState::GameOver => {
-                let game_over_result = game_over(ctx);
+                let game_over_result = game_over(&self.ecs, ctx);
                 match game_over_result {
                     GameOverResult::NoSelection => {}
                     GameOverResult::QuitToMenu => {
+                        self.save_replay();
+                        // An ironman run ends with its death
+                        let settings = *self.ecs.fetch::<SaveSettings>();
+                        if settings.ironman {
+                            let slot = SaveSlot::Manual(settings.slot);
+                            if let Err(error) =
+                                saveload_system::delete_save(&mut *self.storage, slot)
+                            {
+                                self.menu_error =
+                                    Some(format!("Unable to delete {}: {}", slot.name(), error));
+                            }
+                        }
                         self.game_over_cleanup();
                         newrunstate = RunState::MainMenu {
                             menu_selection: MainMenuSelection::NewGame,
@@ -483,7 +564,11 @@ impl GameState for State {
                 }
                 ctx.cls();
                 if self.mapgen_index < self.mapgen_history.len() {
-                    camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx);
+                    camera::render_debug_map(
+                        &self.mapgen_history[self.mapgen_index],
+                        ctx,
+                        &raws::get_raws(&self.ecs),
+                    );
                 }
 
                 self.mapgen_timer += ctx.frame_time_ms;
@@ -532,6 +617,10 @@ impl GameState for State {
                         player_pools.gold += 100_f32;
                         newrunstate = RunState::AwaitingInput;
                     }
+                    CheatMenuResult::ReloadRaws => {
+                        raws::reload_raws(&mut self.ecs);
+                        newrunstate = RunState::AwaitingInput;
+                    }
                 }
             }
             RunState::ShowVendor { vendor, mode } => {
@@ -540,67 +629,35 @@ impl GameState for State {
                 match vendor_result {
                     VendorResult::Cancel => newrunstate = RunState::AwaitingInput,
                     VendorResult::NoResponse => {}
+                    // The vendor menu stays open after trading
                     VendorResult::Sell => {
-                        let e = entity.unwrap();
-                        let price =
-                            self.ecs.read_storage::<Item>().get(e).unwrap().base_value * 0.8;
-                        // TODO(aalhendi): Clean this up
-                        self.ecs
-                            .write_storage::<Pools>()
-                            .get_mut(*self.ecs.fetch::<Entity>())
-                            .unwrap()
-                            .gold += price;
-                        self.ecs.delete_entity(e).expect("Unable to delete");
+                        perform_command(
+                            &mut self.ecs,
+                            PlayerCommand::SellItem {
+                                item: entity.unwrap(),
+                            },
+                        );
                     }
                     VendorResult::Buy => {
-                        let tag = tag.unwrap();
-                        let price = sell_price.unwrap();
-                        let mut pools = self.ecs.write_storage::<Pools>();
-                        let mut identified = self.ecs.write_storage::<IdentifiedItem>();
-                        let player_entity = self.ecs.fetch::<Entity>();
-                        identified
-                            .insert(*player_entity, IdentifiedItem { name: tag.clone() })
-                            .expect("Unable to insert");
-                        std::mem::drop(identified);
-                        let player_pools = pools.get_mut(*player_entity).unwrap();
-                        std::mem::drop(player_entity);
-                        if player_pools.gold >= price {
-                            player_pools.gold -= price;
-                            std::mem::drop(pools);
-                            let player_entity = *self.ecs.fetch::<Entity>();
-                            spawn_named_item(
-                                &RAWS.lock().unwrap(),
-                                &mut self.ecs,
-                                &tag,
-                                SpawnType::Carried { by: player_entity },
-                            );
-                        }
+                        perform_command(
+                            &mut self.ecs,
+                            PlayerCommand::BuyItem {
+                                tag: tag.unwrap(),
+                                price: sell_price.unwrap(),
+                            },
+                        );
                     }
                     VendorResult::BuyMode => newrunstate = RunState::buy_vendor(vendor),
                     VendorResult::SellMode => newrunstate = RunState::sell_vendor(vendor),
                 }
             }
             RunState::TownPortal => {
-                // Spawn the portal
-                spawner::spawn_town_portal(&mut self.ecs);
-
-                // Transition
-                let map_depth = self.ecs.fetch::<Map>().depth;
-                let destination_offset = 0 - (map_depth - 1);
-                self.goto_level(destination_offset);
+                self.town_portal();
                 self.mapgen_next_state = Some(RunState::PreRun);
                 newrunstate = RunState::MapGeneration;
             }
             RunState::TeleportingToOtherLevel { x, y, depth } => {
-                self.goto_level(depth - 1);
-                let player_entity = self.ecs.fetch::<Entity>();
-                if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(*player_entity) {
-                    pos.x = x;
-                    pos.y = y;
-                }
-                let mut ppos = self.ecs.fetch_mut::<rltk::Point>();
-                ppos.x = x;
-                ppos.y = y;
+                self.teleport_to_other_level(x, y, depth);
                 self.mapgen_next_state = Some(RunState::PreRun);
                 newrunstate = RunState::MapGeneration;
             }
@@ -610,9 +667,12 @@ impl GameState for State {
                     ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
                     ItemMenuResult::NoResponse => {}
                     ItemMenuResult::Selected => {
-                        let item_entity = maybe_entity.unwrap();
-                        self.ecs.write_storage::<CursedItem>().remove(item_entity);
-                        newrunstate = RunState::Ticking;
+                        newrunstate = perform_command(
+                            &mut self.ecs,
+                            PlayerCommand::RemoveCurse {
+                                item: maybe_entity.unwrap(),
+                            },
+                        );
                     }
                 }
             }
@@ -622,12 +682,12 @@ impl GameState for State {
                     ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
                     ItemMenuResult::NoResponse => {}
                     ItemMenuResult::Selected => {
-                        let item_entity = maybe_entity.unwrap();
-                        if let Some(name) = self.ecs.read_storage::<Name>().get(item_entity) {
-                            let mut dm = self.ecs.fetch_mut::<MasterDungeonMap>();
-                            dm.identified_items.insert(name.name.clone());
-                        }
-                        newrunstate = RunState::Ticking;
+                        newrunstate = perform_command(
+                            &mut self.ecs,
+                            PlayerCommand::IdentifyItem {
+                                item: maybe_entity.unwrap(),
+                            },
+                        );
                     }
                 }
             }
@@ -650,8 +710,75 @@ impl GameState for State {
 }
 // --- State End ---
 
+/// Inserts the resources a new game needs, loads the raws and spawns the player. Does not
+/// build a map; call `State::generate_world_map` afterwards. Every random roll of the run is
+/// derived from `seed`.
+pub fn init_game_world(ecs: &mut World, seed: u64) {
+    ecs.insert(SimpleMarkerAllocator::<IsSerialized>::new());
+    ecs.insert(effects::EffectQueue::default());
+    ecs.insert(GameLog::default());
+    ecs.insert(GameEvents::default());
+    ecs.insert(SaveSettings::default());
+    raws::load_raws(ecs);
+
+    // Resource Insertion
+    let raws = raws::get_raws(ecs);
+    ecs.insert(MasterDungeonMap::new(seed, &raws));
+    ecs.insert(Map::new(1, 64, 64, "New Map"));
+    ecs.insert(Point::new(0, 0));
+    ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
+    ecs.insert(ReplayRecorder::new(seed));
+    let player_entity = spawner::player(ecs, Position { x: 0, y: 0 });
+    ecs.insert(rex_assets::RexAssets::new());
+    ecs.insert(player_entity);
+    ecs.insert(RunState::PreRun);
+
+    Logger::new()
+        .white("Welcome to")
+        .cyan("Rusty Roguelike")
+        .log(&mut ecs.fetch_mut::<GameLog>());
+    log_run_seed(ecs, seed);
+
+    ecs.insert(particle_system::ParticleBuilder::new());
+}
+
+/// Opens the save slot picker with the first slot it offers highlighted.
+fn slot_menu(mode: SlotMenuMode, storage: &dyn SaveStorage) -> RunState {
+    let slot = menu_slots(mode, storage)
+        .first()
+        .copied()
+        .unwrap_or(SaveSlot::Manual(0));
+    RunState::SaveSlotMenu { mode, slot }
+}
+
+fn log_save_error(ecs: &World, slot: SaveSlot, error: std::io::Error) {
+    Logger::new()
+        .red(format!("Unable to save to {}:", slot.name()))
+        .white(error.to_string())
+        .log(&mut ecs.fetch_mut::<GameLog>());
+}
+
+fn log_run_seed(ecs: &World, seed: u64) {
+    Logger::new()
+        .white("Run seed:")
+        .cyan(seed)
+        .log(&mut ecs.fetch_mut::<GameLog>());
+}
+
 fn main() -> rltk::BError {
     use rltk::RltkBuilder;
+    if let Some(path) = raws::validation::validate_path_from_args() {
+        raws::validation::run_validator(&path);
+        return Ok(());
+    }
+    if let Some(turns) = headless::turns_from_args() {
+        match replay::replay_path_from_args() {
+            Some(path) => headless::run_replay(&path),
+            None => headless::run_balance_experiment(turns),
+        }
+        return Ok(());
+    }
+
     let context = RltkBuilder::simple(80, 60)
         .unwrap()
         .with_title("Rust Roguelike !")
@@ -660,110 +787,22 @@ fn main() -> rltk::BError {
         .with_sparse_console(80, 30, "vga8x16.png")
         .build()?;
 
-    let mut gs = State {
-        ecs: World::new(),
-        mapgen_next_state: Some(RunState::MainMenu {
-            menu_selection: MainMenuSelection::NewGame,
-        }),
-        mapgen_index: 0,
-        mapgen_history: Vec::new(),
-        mapgen_timer: 0.0,
-    };
-
-    // Component registration
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<InflictsDamage>();
-    gs.ecs.register::<Confusion>();
-    gs.ecs.register::<AreaOfEffect>();
-    gs.ecs.register::<Consumable>();
-    gs.ecs.register::<Ranged>();
-    gs.ecs.register::<Item>();
-    gs.ecs.register::<ProvidesHealing>();
-    gs.ecs.register::<InBackpack>();
-    gs.ecs.register::<WantsToPickupItem>();
-    gs.ecs.register::<WantsToUseItem>();
-    gs.ecs.register::<WantsToDropItem>();
-    gs.ecs.register::<WantsToRemoveItem>();
-    gs.ecs.register::<Equippable>();
-    gs.ecs.register::<Equipped>();
-    gs.ecs.register::<SimpleMarker<IsSerialized>>();
-    gs.ecs.register::<SerializationHelper>();
-    gs.ecs.register::<Weapon>();
-    gs.ecs.register::<Wearable>();
-    gs.ecs.register::<ParticleLifetime>();
-    gs.ecs.register::<HungerClock>();
-    gs.ecs.register::<ProvidesFood>();
-    gs.ecs.register::<MagicMapper>();
-    gs.ecs.register::<Hidden>();
-    gs.ecs.register::<EntryTrigger>();
-    gs.ecs.register::<EntityMoved>();
-    gs.ecs.register::<SingleActivation>();
-    gs.ecs.register::<BlocksVisibility>();
-    gs.ecs.register::<Door>();
-    gs.ecs.register::<Quips>();
-    gs.ecs.register::<Attributes>();
-    gs.ecs.register::<Skills>();
-    gs.ecs.register::<Pools>();
-    gs.ecs.register::<NaturalAttackDefense>();
-    gs.ecs.register::<LootTable>();
-    gs.ecs.register::<OtherLevelPosition>();
-    gs.ecs.register::<DMSerializationHelper>();
-    gs.ecs.register::<LightSource>();
-    gs.ecs.register::<Initiative>();
-    gs.ecs.register::<MyTurn>();
-    gs.ecs.register::<Faction>();
-    gs.ecs.register::<WantsToApproach>();
-    gs.ecs.register::<WantsToFlee>();
-    gs.ecs.register::<MoveMode>();
-    gs.ecs.register::<Chasing>();
-    gs.ecs.register::<EquipmentChanged>();
-    gs.ecs.register::<Vendor>();
-    gs.ecs.register::<TownPortal>();
-    gs.ecs.register::<TeleportTo>();
-    gs.ecs.register::<ApplyMove>();
-    gs.ecs.register::<ApplyTeleport>();
-    gs.ecs.register::<MagicItem>();
-    gs.ecs.register::<ObfuscatedName>();
-    gs.ecs.register::<IdentifiedItem>();
-    gs.ecs.register::<SpawnParticleBurst>();
-    gs.ecs.register::<SpawnParticleLine>();
-    gs.ecs.register::<CursedItem>();
-    gs.ecs.register::<ProvidesRemoveCurse>();
-    gs.ecs.register::<ProvidesIdentification>();
-    gs.ecs.register::<AttributeBonus>();
-    gs.ecs.register::<Duration>();
-    gs.ecs.register::<StatusEffect>();
-    gs.ecs.register::<KnownSpells>();
-    gs.ecs.register::<WantsToCastSpell>();
-    gs.ecs.register::<SpellTemplate>();
-    gs.ecs.register::<ProvidesMana>();
-    gs.ecs.register::<TeachesSpell>();
-    gs.ecs.register::<DamageOverTime>();
-    gs.ecs.register::<Slow>();
-    gs.ecs.register::<SpecialAbilities>();
-    gs.ecs.register::<TileSize>();
-    gs.ecs.register::<OnDeath>();
-    gs.ecs.register::<AlwaysTargetsSelf>();
-    gs.ecs.register::<Target>();
-    gs.ecs.register::<WantsToShoot>();
-
-    gs.ecs.insert(SimpleMarkerAllocator::<IsSerialized>::new());
-    raws::load_raws();
+    let mut gs = State::new(RunState::MainMenu {
+        menu_selection: MainMenuSelection::NewGame,
+    });
+    register_components(&mut gs.ecs);
 
-    // Resource Insertion
-    gs.ecs.insert(MasterDungeonMap::new());
-    gs.ecs.insert(Map::new(1, 64, 64, "New Map"));
-    gs.ecs.insert(Point::new(0, 0));
-    gs.ecs.insert(rltk::RandomNumberGenerator::new());
-    let player_entity = spawner::player(&mut gs.ecs, Position { x: 0, y: 0 });
-    gs.ecs.insert(rex_assets::RexAssets::new());
-    gs.ecs.insert(player_entity);
+    if let Some(path) = replay::replay_path_from_args() {
+        let replay = replay::load_replay(&path);
+        init_game_world(&mut gs.ecs, replay.seed);
+        gs.replay = Some(ReplayPlayer::new(replay));
+        gs.mapgen_next_state = Some(RunState::PreRun);
+        gs.ecs.insert(RunState::MapGeneration {});
+        gs.generate_world_map(1, 0);
+        return rltk::main_loop(context, gs);
+    }
+
+    init_game_world(&mut gs.ecs, run_seed::new_run_seed());
     if SHOW_MAPGEN_VISUALIZER {
         gs.ecs.insert(RunState::MapGeneration {});
     } else {
@@ -772,15 +811,6 @@ fn main() -> rltk::BError {
         });
     }
 
-    gamelog::clear_log();
-    gamelog::Logger::new()
-        .white("Welcome to")
-        .cyan("Rusty Roguelike")
-        .log();
-    gamelog::events::clear_events();
-
-    gs.ecs.insert(particle_system::ParticleBuilder::new());
-
     gs.generate_world_map(1, 0);
 
     rltk::main_loop(context, gs)
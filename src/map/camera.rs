@@ -1,4 +1,8 @@
-use crate::{map::themes::tile_glyph, Hidden, Position, Renderable, Target, TileSize};
+use crate::{
+    map::themes::tile_glyph,
+    raws::{get_raws, rawsmaster::RawMaster},
+    Hidden, Position, Renderable, Target, TileSize,
+};
 
 use super::Map;
 use rltk::{to_cp437, ColorPair, DrawBatch, Point, Rltk, RGB};
@@ -8,7 +12,7 @@ const SHOW_BOUNDARIES: bool = true;
 pub const PANE_WIDTH: i32 = 44;
 
 // TODO(aalhendi): batch drawing here?
-pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
+pub fn render_debug_map(map: &Map, ctx: &mut Rltk, raws: &RawMaster) {
     let player_pos = Point::new(map.width / 2, map.height / 2);
     let (x_chars, y_chars) = ctx.get_char_size();
 
@@ -28,7 +32,7 @@ pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
             if tx > 0 && tx < map_width && ty > 0 && ty < map_height {
                 let idx = map.xy_idx(tx, ty);
                 if map.revealed_tiles[idx] {
-                    let (glyph, fg, bg) = tile_glyph(idx, map);
+                    let (glyph, fg, bg) = tile_glyph(idx, map, raws);
                     ctx.set(x, y, fg, bg, glyph);
                 }
             } else if SHOW_BOUNDARIES {
@@ -47,6 +51,7 @@ pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
 pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
     let mut draw_batch = DrawBatch::new();
     let map = ecs.fetch::<Map>();
+    let raws = get_raws(ecs);
     let (min_x, max_x, min_y, max_y) = get_screen_bounds(ecs, ctx);
 
     let map_width = map.width - 1;
@@ -57,7 +62,7 @@ pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
             if tx > 0 && tx < map_width && ty > 0 && ty < map_height {
                 let idx = map.xy_idx(tx, ty);
                 if map.revealed_tiles[idx] {
-                    let (glyph, fg, bg) = tile_glyph(idx, &map);
+                    let (glyph, fg, bg) = tile_glyph(idx, &map, &raws);
                     draw_batch.set(Point::new(x, y), ColorPair::new(fg, bg), glyph);
                 }
             } else if SHOW_BOUNDARIES {
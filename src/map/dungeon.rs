@@ -1,6 +1,10 @@
 use crate::{
     map_builders::level_builder,
-    raws::rawsmaster::{get_potion_tags, get_scroll_tags},
+    raws::{
+        get_raws,
+        rawsmaster::{get_potion_tags, get_scroll_tags, RawMaster},
+    },
+    run_seed::{depth_seed, names_seed},
     OtherLevelPosition, Position, TileType, Viewshed,
 };
 
@@ -18,19 +22,22 @@ pub struct MasterDungeonMap {
     // maps actual name with randomized/obfuscated name
     pub scroll_mappings: HashMap<String, String>,
     pub potion_mappings: HashMap<String, String>,
+    // seed of the whole run; levels and name tables are derived from it
+    #[serde(default)]
+    pub seed: u64,
 }
 
 impl MasterDungeonMap {
-    pub fn new() -> MasterDungeonMap {
-        let mut rng = rltk::RandomNumberGenerator::new();
+    pub fn new(seed: u64, raws: &RawMaster) -> MasterDungeonMap {
+        let mut rng = rltk::RandomNumberGenerator::seeded(names_seed(seed));
 
-        let scroll_mappings = get_scroll_tags()
+        let scroll_mappings = get_scroll_tags(raws)
             .iter()
             .map(|scroll_tag| (scroll_tag.to_string(), make_scroll_name(&mut rng)))
             .collect();
 
         let mut used_pot_names = HashSet::new();
-        let potion_mappings = get_potion_tags()
+        let potion_mappings = get_potion_tags(raws)
             .iter()
             .map(|pot_tag| {
                 (
@@ -45,6 +52,28 @@ impl MasterDungeonMap {
             identified_items: HashSet::new(),
             scroll_mappings,
             potion_mappings,
+            seed,
+        }
+    }
+
+    /// Gives disguised names to scrolls and potions added to the raws since the run started.
+    /// Seeded from the run and the names already handed out, so a loaded game names them the
+    /// same way every time.
+    pub fn name_new_magic_items(&mut self, raws: &RawMaster) {
+        let named = (self.scroll_mappings.len() + self.potion_mappings.len()) as u64;
+        let mut rng =
+            rltk::RandomNumberGenerator::seeded(names_seed(self.seed).wrapping_add(named));
+        for scroll_tag in get_scroll_tags(raws) {
+            self.scroll_mappings
+                .entry(scroll_tag)
+                .or_insert_with(|| make_scroll_name(&mut rng));
+        }
+
+        let mut used_pot_names: HashSet<String> = self.potion_mappings.values().cloned().collect();
+        for pot_tag in get_potion_tags(raws) {
+            self.potion_mappings
+                .entry(pot_tag)
+                .or_insert_with(|| make_potion_name(&mut rng, &mut used_pot_names));
         }
     }
 
@@ -73,9 +102,11 @@ pub fn level_transition(ecs: &mut World, new_depth: i32, offset: i32) -> Option<
 }
 
 fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
-    let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
-    let mut builder = level_builder(new_depth, &mut rng, 80, 50);
-    builder.build_map(&mut rng);
+    let seed = depth_seed(ecs.fetch::<MasterDungeonMap>().seed, new_depth);
+    let mut rng = rltk::RandomNumberGenerator::seeded(seed);
+    let raws = get_raws(ecs);
+    let mut builder = level_builder(new_depth, &mut rng, &raws);
+    builder.build_map(&mut rng, &raws);
 
     // Set upstairs
     if new_depth > 1 {
@@ -92,7 +123,6 @@ fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
     };
 
     // Spawn bad guys
-    std::mem::drop(rng);
     builder.spawn_entities(ecs);
 
     // Place the player and update resources
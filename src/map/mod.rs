@@ -4,9 +4,9 @@ pub mod camera;
 pub mod dungeon;
 pub mod themes;
 pub mod tiletype;
-pub use tiletype::{tile_opaque, tile_walkable, TileType};
+pub use tiletype::{tile_opaque, tile_walkable, TileType, TILE_TYPES};
 
-use crate::spatial;
+use crate::spatial::SpatialMap;
 
 use self::tiletype::tile_cost;
 
@@ -22,7 +22,15 @@ pub struct Map {
     pub view_blocked: HashSet<usize>,
     pub name: String,
     pub outdoors: bool,
+    /// What kind of level this is, e.g. "cavern" or "fungal". Spawn table entries can require
+    /// or exclude tags. Saves from before tags existed load with none.
+    #[serde(default)]
+    pub tags: HashSet<String>,
+    /// How the map is drawn, the name of a theme in the raws.
+    pub theme: String,
     pub light_level_tiles: Vec<RGB>,
+    #[serde(skip)]
+    pub spatial: SpatialMap,
 }
 
 impl rltk::Algorithm2D for Map {
@@ -89,7 +97,8 @@ impl Map {
     /// Generates an empty map, consisting entirely of solid walls
     pub fn new<T: Into<String>>(new_depth: i32, width: i32, height: i32, name: T) -> Map {
         let map_tile_count = (width * height) as usize;
-        spatial::set_size(map_tile_count);
+        let mut spatial = SpatialMap::default();
+        spatial.set_size(map_tile_count);
         Map {
             tiles: vec![TileType::Wall; map_tile_count],
             width,
@@ -101,10 +110,21 @@ impl Map {
             view_blocked: HashSet::new(),
             name: name.into(),
             outdoors: true,
+            tags: HashSet::new(),
+            theme: themes::DEFAULT_THEME.to_string(),
             light_level_tiles: vec![RGB::named(rltk::BLACK); map_tile_count],
+            spatial,
         }
     }
 
+    /// A blank map with the same depth, size, name, tags and theme, for builders that start over.
+    pub fn cleared(&self) -> Map {
+        let mut map = Map::new(self.depth, self.width, self.height, &self.name);
+        map.tags = self.tags.clone();
+        map.theme = self.theme.clone();
+        map
+    }
+
     /// Returns index in 1D array via row-major indexing
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
         (y * self.width + x) as usize
@@ -117,7 +137,7 @@ impl Map {
 
     // Clears the contents of tile_content field
     pub fn clear_content_index(&mut self) {
-        spatial::clear();
+        self.spatial.clear();
     }
 
     /// Returns if a tile can be entered and is within bounds
@@ -127,16 +147,16 @@ impl Map {
             return false;
         }
         let idx = self.xy_idx(x, y);
-        !spatial::is_blocked(idx)
+        !self.spatial.is_blocked(idx)
     }
 
     /// Sets tile as blocked if Wall tile.
     pub fn populate_blocked(&mut self) {
-        spatial::populate_blocked_from_map(self);
+        self.spatial.populate_blocked_from_map(&self.tiles);
     }
 
     /// Returns a map with solid boundaries and 400 randomly placed wall tiles
-    pub fn new_map_test(&self) -> Vec<TileType> {
+    pub fn new_map_test(&self, rng: &mut RandomNumberGenerator) -> Vec<TileType> {
         let map_tile_count = (self.width * self.height) as usize;
         let mut map = vec![TileType::Floor; map_tile_count];
 
@@ -150,8 +170,7 @@ impl Map {
             map[self.xy_idx(self.width - 1, y)] = TileType::Wall;
         }
 
-        // Random Walls on ~10% of tiles via thread-local rng
-        let mut rng = RandomNumberGenerator::new();
+        // Random Walls on ~10% of tiles
         let spawn_idx = self.xy_idx(40, 25);
         for _ in 0..400 {
             let x = rng.roll_dice(1, self.width - 1);
@@ -165,9 +184,9 @@ impl Map {
         map
     }
 
-    fn is_revealed_and_wall(&self, x: i32, y: i32) -> bool {
+    fn is_revealed_tile(&self, x: i32, y: i32, tile: TileType) -> bool {
         let idx = self.xy_idx(x, y);
-        self.tiles[idx] == TileType::Wall && self.revealed_tiles[idx]
+        self.tiles[idx] == tile && self.revealed_tiles[idx]
     }
 
     pub fn populate_blocked_multi(&mut self, width: i32, height: i32) {
@@ -175,7 +194,7 @@ impl Map {
         for y in 1..self.height - 1 {
             for x in 1..self.width - 1 {
                 let idx = self.xy_idx(x, y);
-                if spatial::is_blocked(idx) {
+                if self.spatial.is_blocked(idx) {
                     continue;
                 }
 
@@ -185,11 +204,11 @@ impl Map {
                         let ty = y + cy;
                         if tx < self.width - 1 && ty < self.height - 1 {
                             let tidx = self.xy_idx(tx, ty);
-                            if spatial::is_blocked(tidx) {
-                                spatial::set_blocked(idx, true);
+                            if self.spatial.is_blocked(tidx) {
+                                self.spatial.set_blocked(idx, true);
                             }
                         } else {
-                            spatial::set_blocked(idx, true);
+                            self.spatial.set_blocked(idx, true);
                         }
                     }
                 }
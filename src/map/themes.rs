@@ -1,316 +1,187 @@
-use rltk::{to_cp437, FontCharType, RGB};
-
-use crate::{camera::PANE_WIDTH, Map, TileType};
+use std::collections::HashMap;
 
-pub fn tile_glyph(idx: usize, map: &Map) -> (FontCharType, RGB, RGB) {
-    let (glyph, mut fg, mut bg) = match map.depth {
-        8 | 9 => get_mushroom_glyph(idx, map),
-        7 => {
-            let (x, _y) = map.idx_xy(idx);
-            if x > map.width - (PANE_WIDTH as f32 / 2.75) as i32 {
-                get_tile_glyph_default(idx, map)
-            } else {
-                get_mushroom_glyph(idx, map)
-            }
-        }
-        5 => {
-            let (x, _y) = map.idx_xy(idx);
-            if x < map.width / 2 {
-                get_limestone_cavern_glyph(idx, map)
-            } else {
-                get_tile_glyph_default(idx, map)
-            }
-        }
-        4 => get_limestone_cavern_glyph(idx, map),
-        3 => get_limestone_cavern_glyph(idx, map),
-        2 => get_forest_glyph(idx, map),
-        _ => get_tile_glyph_default(idx, map),
-    };
-
-    if map.bloodstains.contains(&idx) {
-        bg = RGB::from_f32(0.75, 0., 0.);
-    }
+use rltk::{to_cp437, FontCharType, RGB};
 
-    // If can't tile, use greyscales
-    if !map.visible_tiles[idx] {
-        fg = fg.to_greyscale();
-        // Don't show stains out of visual range
-        bg = RGB::from_f32(0., 0., 0.);
-    // If can see tile && outdoors is false - multiply colors light intensity
-    } else if !map.outdoors {
-        fg = fg * map.light_level_tiles[idx];
-        bg = bg * map.light_level_tiles[idx];
-    }
+use crate::{
+    map::TILE_TYPES,
+    raws::{
+        rawsmaster::{get_theme, RawMaster},
+        theme_structs::{OutOfSight, Theme, TileLook},
+        Raws,
+    },
+    Map, TileType,
+};
+
+/// The theme new maps start with, and that maps whose theme is missing are drawn with.
+pub const DEFAULT_THEME: &str = "default";
+
+/// A theme from the raws with its glyphs and colors parsed and every tile type filled in.
+pub struct DrawTheme {
+    tiles: HashMap<TileType, ThemedTile>,
+    bloodstain: RGB,
+    greyscale_out_of_sight: bool,
+    out_of_sight_tint: Option<RGB>,
+    out_of_sight_bg: RGB,
+    regions: Vec<Region>,
+}
 
-    (glyph, fg, bg)
+struct ThemedTile {
+    /// The glyph first, then its variants.
+    glyphs: Vec<FontCharType>,
+    mask_glyphs: Option<Vec<FontCharType>>,
+    fg: RGB,
+    bg: RGB,
 }
 
-// TODO(aalhendi): Should this be part of impl Map?
-fn get_tile_glyph_default(idx: usize, map: &Map) -> (FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
+struct Region {
+    theme: String,
+    from_x: f32,
+    to_x: f32,
+}
 
-    match map.tiles[idx] {
-        TileType::Floor => {
-            glyph = to_cp437('.');
-            fg = RGB::from_f32(0.0, 0.5, 0.5);
-        }
-        TileType::WoodFloor => {
-            glyph = to_cp437('░');
-            fg = RGB::named(rltk::CHOCOLATE);
-        }
-        TileType::Wall => {
-            let (x, y) = map.idx_xy(idx);
-            glyph = get_wall_glyph(map, x, y);
-            fg = RGB::named(rltk::GREEN);
-        }
-        TileType::DownStairs => {
-            glyph = to_cp437('>');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::Bridge => {
-            glyph = to_cp437('.');
-            fg = RGB::named(rltk::CHOCOLATE);
-        }
-        TileType::Road => {
-            glyph = to_cp437('≡');
-            fg = RGB::named(rltk::GRAY);
-        }
-        TileType::Grass => {
-            glyph = to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        }
-        TileType::ShallowWater => {
-            glyph = to_cp437('~');
-            fg = RGB::named(rltk::CYAN); // BLUE
-        }
-        TileType::DeepWater => {
-            glyph = to_cp437('≈');
-            fg = RGB::named(rltk::NAVYBLUE);
-        }
-        TileType::Gravel => {
-            glyph = to_cp437(';');
-            fg = RGB::named(rltk::WEBGRAY);
-        }
-        TileType::UpStairs => {
-            glyph = to_cp437('<');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::Stalactite => {
-            glyph = rltk::to_cp437('╨');
-            fg = RGB::named(rltk::WEBGRAY);
+impl DrawTheme {
+    /// Looks up everything `theme` leaves to its base themes. Expects raws that passed
+    /// `validate_raws`.
+    pub fn new(raws: &Raws, theme: &Theme) -> DrawTheme {
+        let mut lineage = vec![theme];
+        while let Some(base) = &lineage[lineage.len() - 1].base {
+            match raws.themes.iter().find(|t| t.name == *base) {
+                Some(base) if !lineage.iter().any(|t| t.name == base.name) => lineage.push(base),
+                _ => break,
+            }
         }
-        TileType::Stalagmite => {
-            glyph = rltk::to_cp437('╥');
-            fg = RGB::named(rltk::WEBGRAY);
+
+        let tiles = TILE_TYPES
+            .iter()
+            .filter_map(|tile| {
+                lineage
+                    .iter()
+                    .find_map(|t| t.tiles.get(tile).or(t.fallback.as_ref()))
+                    .map(|look| (*tile, ThemedTile::new(look)))
+            })
+            .collect();
+        let bloodstain = lineage
+            .iter()
+            .find_map(|t| t.bloodstain.as_deref())
+            .map_or_else(|| RGB::from_f32(0.75, 0., 0.), color);
+        let default_out_of_sight = OutOfSight::default();
+        let out_of_sight = lineage
+            .iter()
+            .find_map(|t| t.out_of_sight.as_ref())
+            .unwrap_or(&default_out_of_sight);
+
+        DrawTheme {
+            tiles,
+            bloodstain,
+            greyscale_out_of_sight: out_of_sight.greyscale,
+            out_of_sight_tint: out_of_sight.tint.as_deref().map(color),
+            out_of_sight_bg: color(&out_of_sight.bg),
+            regions: theme
+                .regions
+                .iter()
+                .map(|region| Region {
+                    theme: region.theme.clone(),
+                    from_x: region.from_x,
+                    to_x: region.to_x,
+                })
+                .collect(),
         }
     }
-
-    (glyph, fg, bg)
 }
 
-// TODO(aalhendi): Should this be part of impl Map?
-fn get_forest_glyph(idx: usize, map: &Map) -> (FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::named(rltk::BLACK);
-
-    match map.tiles[idx] {
-        TileType::Wall => {
-            glyph = to_cp437('♣');
-            fg = RGB::from_f32(0.0, 0.6, 0.0);
-        }
-        TileType::Bridge => {
-            glyph = to_cp437('.');
-            fg = RGB::named(rltk::CHOCOLATE);
-        }
-        TileType::Road => {
-            glyph = to_cp437('≡');
-            fg = RGB::named(rltk::YELLOW);
-        }
-        TileType::Grass => {
-            glyph = to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        }
-        TileType::ShallowWater => {
-            glyph = to_cp437('~');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::DeepWater => {
-            glyph = to_cp437('≈');
-            fg = RGB::named(rltk::NAVYBLUE);
-        }
-        TileType::Gravel => {
-            glyph = to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        }
-        TileType::DownStairs => {
-            glyph = to_cp437('>');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::UpStairs => {
-            glyph = to_cp437('<');
-            fg = RGB::named(rltk::CYAN);
-        }
-        _ => {
-            glyph = to_cp437('"');
-            fg = RGB::from_f32(0.0, 0.6, 0.0);
+impl ThemedTile {
+    fn new(look: &TileLook) -> ThemedTile {
+        ThemedTile {
+            glyphs: std::iter::once(&look.glyph)
+                .chain(look.variants.iter())
+                .map(|glyph| glyph_of(glyph))
+                .collect(),
+            mask_glyphs: look
+                .mask_glyphs
+                .as_ref()
+                .map(|glyphs| glyphs.chars().map(to_cp437).collect()),
+            fg: color(&look.fg),
+            bg: color(&look.bg),
         }
     }
 
-    (glyph, fg, bg)
-}
-
-fn get_limestone_cavern_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
-
-    match map.tiles[idx] {
-        TileType::Wall => {
-            glyph = to_cp437('▒');
-            fg = RGB::from_f32(0.7, 0.7, 0.7);
-        }
-        TileType::Bridge => {
-            glyph = to_cp437('.');
-            fg = RGB::named(rltk::CHOCOLATE);
-        }
-        TileType::Road => {
-            glyph = to_cp437('≡');
-            fg = RGB::named(rltk::YELLOW);
-        }
-        TileType::Grass => {
-            glyph = to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        }
-        TileType::ShallowWater => {
-            glyph = to_cp437('░');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::DeepWater => {
-            glyph = to_cp437('▓');
-            // fg = RGB::named(rltk::BLUE);
-            fg = RGB::from_f32(0.2, 0.2, 1.0) // hint of green to see it in greyscale
-        }
-        TileType::Gravel => {
-            glyph = to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        }
-        TileType::DownStairs => {
-            glyph = to_cp437('>');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::UpStairs => {
-            glyph = to_cp437('<');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::Stalactite => {
-            glyph = rltk::to_cp437('╨');
-            fg = RGB::named(rltk::WEBGRAY);
-        }
-        TileType::Stalagmite => {
-            glyph = rltk::to_cp437('╥');
-            fg = RGB::named(rltk::WEBGRAY);
-        }
-        _ => {
-            glyph = to_cp437('░');
-            fg = RGB::from_f32(0.4, 0.4, 0.4);
+    fn glyph(&self, idx: usize, map: &Map) -> FontCharType {
+        let (x, y) = map.idx_xy(idx);
+        if let Some(mask_glyphs) = &self.mask_glyphs {
+            if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
+                return mask_glyphs[neighbour_mask(map, x, y)];
+            }
         }
+
+        // Scatter the variants, but draw each tile the same way every frame
+        let hash = (idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+        self.glyphs[hash as usize % self.glyphs.len()]
     }
+}
 
-    (glyph, fg, bg)
+fn glyph_of(glyph: &str) -> FontCharType {
+    to_cp437(glyph.chars().next().expect("Theme glyph is empty"))
 }
 
-fn get_mushroom_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
+fn color(hex: &str) -> RGB {
+    RGB::from_hex(hex).expect("Theme color isn't valid")
+}
 
-    match map.tiles[idx] {
-        TileType::Wall => {
-            glyph = rltk::to_cp437('♠');
-            fg = RGB::from_f32(1.0, 0.0, 1.0);
-        }
-        TileType::Bridge => {
-            glyph = rltk::to_cp437('.');
-            fg = RGB::named(rltk::GREEN);
-        }
-        TileType::Road => {
-            glyph = rltk::to_cp437('≡');
-            fg = RGB::named(rltk::CHOCOLATE);
-        }
-        TileType::Grass => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        }
-        TileType::ShallowWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::CYAN);
-        }
-        TileType::DeepWater => {
-            glyph = rltk::to_cp437('≈');
-            fg = RGB::named(rltk::BLUE);
-        }
-        TileType::Gravel => {
-            glyph = rltk::to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        }
-        TileType::DownStairs => {
-            glyph = rltk::to_cp437('>');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        }
-        TileType::UpStairs => {
-            glyph = rltk::to_cp437('<');
-            fg = RGB::from_f32(0., 1.0, 1.0);
+pub fn tile_glyph(idx: usize, map: &Map, raws: &RawMaster) -> (FontCharType, RGB, RGB) {
+    let theme = get_theme(raws, &map.theme);
+    let (x, _y) = map.idx_xy(idx);
+    let across = x as f32 / map.width as f32;
+    let tiles = theme
+        .regions
+        .iter()
+        .find(|region| across >= region.from_x && across < region.to_x)
+        .map_or(theme, |region| get_theme(raws, &region.theme));
+
+    let tile = &tiles.tiles[&map.tiles[idx]];
+    let glyph = tile.glyph(idx, map);
+    let mut fg = tile.fg;
+    let mut bg = tile.bg;
+
+    if map.bloodstains.contains(&idx) {
+        bg = theme.bloodstain;
+    }
+
+    // Tiles out of sight are drawn as remembered
+    if !map.visible_tiles[idx] {
+        if theme.greyscale_out_of_sight {
+            fg = fg.to_greyscale();
         }
-        _ => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::from_f32(0.0, 0.6, 0.0);
+        if let Some(tint) = theme.out_of_sight_tint {
+            fg = fg * tint;
         }
+        // Don't show stains out of visual range
+        bg = theme.out_of_sight_bg;
+    // If can see tile && outdoors is false - multiply colors light intensity
+    } else if !map.outdoors {
+        fg = fg * map.light_level_tiles[idx];
+        bg = bg * map.light_level_tiles[idx];
     }
 
     (glyph, fg, bg)
 }
 
-// TODO(aalhendi): Should this be part of impl Map?
-pub fn get_wall_glyph(map: &Map, x: i32, y: i32) -> FontCharType {
-    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 {
-        return 35;
-    }
-    let mut mask: u8 = 0;
+/// Which neighbours of the tile at `x`, `y` are revealed tiles of the same type, as an index
+/// into a theme's mask glyphs.
+fn neighbour_mask(map: &Map, x: i32, y: i32) -> usize {
+    let tile = map.tiles[map.xy_idx(x, y)];
+    let mut mask = 0;
 
-    if map.is_revealed_and_wall(x, y - 1) {
+    if map.is_revealed_tile(x, y - 1, tile) {
         mask += 1;
     }
-    if map.is_revealed_and_wall(x, y + 1) {
+    if map.is_revealed_tile(x, y + 1, tile) {
         mask += 2;
     }
-    if map.is_revealed_and_wall(x - 1, y) {
+    if map.is_revealed_tile(x - 1, y, tile) {
         mask += 4;
     }
-    if map.is_revealed_and_wall(x + 1, y) {
+    if map.is_revealed_tile(x + 1, y, tile) {
         mask += 8;
     }
-
-    // Uses <http://dwarffortresswiki.org/index.php/Character_table>
-    match mask {
-        0 => 9,    // Pillar because we can't see neighbors
-        1 => 186,  // Wall only to the north
-        2 => 186,  // Wall only to the south
-        3 => 186,  // Wall to the north and south
-        4 => 205,  // Wall only to the west
-        5 => 188,  // Wall to the north and west
-        6 => 187,  // Wall to the south and west
-        7 => 185,  // Wall to the north, south and west
-        8 => 205,  // Wall only to the east
-        9 => 200,  // Wall to the north and east
-        10 => 201, // Wall to the south and east
-        11 => 204, // Wall to the north, south and east
-        12 => 205, // Wall to the east and west
-        13 => 202, // Wall to the east, west, and south
-        14 => 203, // Wall to the east, west, and north
-        15 => 206, // ╬ Wall on all sides
-        _ => 35,   // Fallthrough
-    }
+    mask
 }
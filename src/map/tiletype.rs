@@ -1,4 +1,4 @@
-#[derive(PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum TileType {
     Wall,
     Stalactite,
@@ -15,6 +15,23 @@ pub enum TileType {
     UpStairs,
 }
 
+/// Every tile type, for themes to be checked against.
+pub const TILE_TYPES: [TileType; 13] = [
+    TileType::Wall,
+    TileType::Stalactite,
+    TileType::Stalagmite,
+    TileType::Floor,
+    TileType::DownStairs,
+    TileType::Road,
+    TileType::Grass,
+    TileType::ShallowWater,
+    TileType::DeepWater,
+    TileType::WoodFloor,
+    TileType::Bridge,
+    TileType::Gravel,
+    TileType::UpStairs,
+];
+
 // TODO(aalhendi): Refactor into impl
 pub fn tile_walkable(tt: TileType) -> bool {
     match tt {
@@ -1,15 +1,18 @@
 use crate::{map, TileType};
+use serde::Deserialize;
 
 use super::{BuilderMap, MetaMapBuilder};
 
-#[allow(dead_code)]
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
 pub enum XEnd {
     Left,
     Center,
     Right,
 }
 
-#[allow(dead_code)]
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
 pub enum YEnd {
     Top,
     Center,
@@ -1,14 +1,19 @@
 use crate::map::TileType;
+use serde::Deserialize;
 
 use super::{BuilderMap, MetaMapBuilder, Position};
 use rltk::RandomNumberGenerator;
 
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
 pub enum XStart {
     Left,
     Center,
     Right,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
 pub enum YStart {
     Top,
     Center,
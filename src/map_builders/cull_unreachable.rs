@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use crate::map::TileType;
 
 use super::{BuilderMap, MetaMapBuilder};
@@ -29,14 +31,17 @@ impl CullUnreachable {
             &build_data.map,
             1000.0,
         );
+        let mut culled = HashSet::new();
         for (i, tile) in build_data.map.tiles.iter_mut().enumerate() {
             if *tile == TileType::Floor {
                 let distance_to_start = dijkstra_map.map[i];
                 // Unreachable tile -> Wall
                 if distance_to_start == f32::MAX {
                     *tile = TileType::Wall;
+                    culled.insert(i);
                 }
             }
         }
+        build_data.retain_spawns(|_map, idx| !culled.contains(&idx));
     }
 }
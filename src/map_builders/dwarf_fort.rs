@@ -2,43 +2,8 @@ use rltk::RandomNumberGenerator;
 
 use crate::{tile_walkable, TileType};
 
-use super::{
-    area_ending_point::{AreaEndingPosition, XEnd, YEnd},
-    area_starting_points::{AreaStartingPosition, XStart, YStart},
-    bsp_dungeon::BspDungeonBuilder,
-    cull_unreachable::CullUnreachable,
-    distant_exit::DistantExit,
-    dla::DLABuilder,
-    room_corridor_spawner::CorridorSpawner,
-    room_draw::RoomDrawer,
-    room_sorter::{RoomSort, RoomSorter},
-    rooms_corridors_bsp::BspCorridors,
-    voronoi_spawning::VoronoiSpawning,
-    BuilderChain, BuilderMap, MetaMapBuilder,
-};
-
-pub fn dwarf_fort_builder(
-    new_depth: i32,
-    _rng: &mut rltk::RandomNumberGenerator,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Dwarven Fortress");
-    chain.start_with(BspDungeonBuilder::new());
-    chain.with(RoomSorter::new(RoomSort::Central));
-    chain.with(RoomDrawer::new());
-    chain.with(BspCorridors::new());
-    chain.with(CorridorSpawner::new());
-    chain.with(DragonsLair::new());
-
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Top));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaEndingPosition::new(XEnd::Right, YEnd::Bottom));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(DragonSpawner::new());
-    chain
-}
+use super::{dla::DLABuilder, BuilderChain, BuilderMap, MetaMapBuilder};
+
 pub struct DragonsLair;
 
 impl MetaMapBuilder for DragonsLair {
@@ -53,12 +18,16 @@ impl DragonsLair {
     }
 
     fn build(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
-        build_data.map.depth = 7;
         build_data.take_snapshot();
 
-        let mut builder = BuilderChain::new(6, build_data.width, build_data.height, "New Map");
+        let mut builder = BuilderChain::new(
+            build_data.map.depth,
+            build_data.width,
+            build_data.height,
+            "New Map",
+        );
         builder.start_with(DLABuilder::insectoid());
-        builder.build_map(rng);
+        builder.build_map(rng, &build_data.raws);
 
         // Add the history to our history
         for h in builder.build_data.history.iter() {
@@ -116,8 +85,8 @@ impl DragonSpawner {
         let dragon_pt = rltk::Point::new(start_x, start_y);
 
         // Remove all spawns within 25 tiles of the drake
-        build_data.spawn_list.retain(|spawn| {
-            let (spawn_x, spawn_y) = build_data.map.idx_xy(spawn.0);
+        build_data.retain_spawns(|map, idx| {
+            let (spawn_x, spawn_y) = map.idx_xy(idx);
             let spawn_pt = rltk::Point::new(spawn_x, spawn_y);
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(dragon_pt, spawn_pt);
             distance > 25.0
@@ -1,55 +1,34 @@
-use crate::{rect::Rect, spawner};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
-use self::{
-    area_starting_points::{AreaStartingPosition, XStart, YStart},
-    cull_unreachable::CullUnreachable,
-    distant_exit::DistantExit,
-    door_placement::DoorPlacement,
-    dwarf_fort::dwarf_fort_builder,
-    limestone_cavern::{limestone_deep_cavern_builder, limestone_transition_builder},
-    mushroom_forest::{mushroom_builder, mushroom_entrance, mushroom_exit},
-    room_based_spawner::RoomBasedSpawner,
-    room_based_stairs::RoomBasedStairs,
-    room_based_starting_position::RoomBasedStartingPosition,
-    room_corner_rounding::RoomCornerRounder,
-    room_corridor_spawner::CorridorSpawner,
-    room_draw::RoomDrawer,
-    room_exploder::RoomExploder,
-    room_sorter::{RoomSort, RoomSorter},
-    rooms_corridors_bsp::BspCorridors,
-    rooms_corridors_dogleg::DoglegCorridors,
-    rooms_corridors_lines::StraightLineCorridors,
-    rooms_corridors_nearest::NearestCorridors,
-    voronoi_spawning::VoronoiSpawning,
-    waveform_collapse::WaveformCollapseBuilder,
+use crate::{
+    raws::rawsmaster::{get_level, RawMaster},
+    rect::Rect,
+    spawner, Follower, Initiative,
 };
 
+use self::recipes::apply_recipe;
+
 use super::{Map, Position};
-mod simple_map;
-use simple_map::SimpleMapBuilder;
 mod bsp_dungeon;
-use bsp_dungeon::BspDungeonBuilder;
 mod bsp_interior;
-use bsp_interior::BspInteriorBuilder;
 mod cellular_automata;
-use cellular_automata::CellularAutomataBuilder;
+mod common;
+mod dla;
 mod drunkard;
-use drunkard::DrunkardsWalkBuilder;
 mod maze;
-use maze::MazeBuilder;
-mod dla;
-use dla::DLABuilder;
-mod common;
+mod simple_map;
 mod voronoi;
-use specs::World;
-use voronoi::VoronoiCellBuilder;
-mod prefab_builder;
-mod waveform_collapse;
-use prefab_builder::PrefabBuilder;
+use specs::{World, WorldExt};
+mod area_ending_point;
 mod area_starting_points;
 mod cull_unreachable;
 mod distant_exit;
 mod door_placement;
+mod dwarf_fort;
+mod limestone_cavern;
+mod prefab_builder;
+pub mod recipes;
 mod room_based_spawner;
 mod room_based_stairs;
 mod room_based_starting_position;
@@ -64,18 +43,19 @@ mod rooms_corridors_lines;
 mod rooms_corridors_nearest;
 mod town;
 mod voronoi_spawning;
-use town::town_builder;
-mod forest;
-use forest::forest_builder;
-mod limestone_cavern;
+mod waveform_collapse;
 mod yellow_brick_road;
-use limestone_cavern::limestone_cavern_builder;
-mod area_ending_point;
-mod dwarf_fort;
-mod mushroom_forest;
+
+pub use area_ending_point::{XEnd, YEnd};
+pub use area_starting_points::{XStart, YStart};
+pub use prefab_builder::{HorizontalPlacement, VerticalPlacement};
+pub use room_sorter::RoomSort;
 
 pub struct BuilderMap {
     pub spawn_list: Vec<(usize, String)>,
+    /// For spawns in a group with a leader: the tile of each member, and the tile its leader
+    /// spawns on.
+    pub spawn_leaders: HashMap<usize, usize>,
     pub map: Map,
     pub starting_position: Option<Position>,
     pub rooms: Option<Vec<Rect>>,
@@ -83,6 +63,7 @@ pub struct BuilderMap {
     pub corridors: Option<Vec<Vec<usize>>>,
     pub width: i32,
     pub height: i32,
+    pub raws: Arc<RawMaster>,
 }
 
 pub struct BuilderChain {
@@ -98,6 +79,7 @@ impl BuilderChain {
             builders: Vec::new(),
             build_data: BuilderMap {
                 spawn_list: Vec::new(),
+                spawn_leaders: HashMap::new(),
                 map: Map::new(new_depth, width, height, name),
                 starting_position: None,
                 rooms: None,
@@ -105,6 +87,7 @@ impl BuilderChain {
                 corridors: None,
                 width,
                 height,
+                raws: Arc::default(),
             },
         }
     }
@@ -120,7 +103,14 @@ impl BuilderChain {
         self.builders.push(metabuilder);
     }
 
-    pub fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator) {
+    /// Tags the level being built. Spawners only use the entries of the spawn table that the
+    /// level's tags allow, so tag the chain before it spawns anything.
+    pub fn with_tags(&mut self, tags: &[String]) {
+        self.build_data.map.tags.extend(tags.iter().cloned());
+    }
+
+    pub fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, raws: &Arc<RawMaster>) {
+        self.build_data.raws = raws.clone();
         match &mut self.starter {
             None => panic!("Cannot run a map builder chain without a starting build system"),
             Some(starter) => {
@@ -136,13 +126,49 @@ impl BuilderChain {
     }
 
     pub fn spawn_entities(&mut self, ecs: &mut World) {
+        // Only creatures join groups, so items or doors sharing their tile aren't linked
+        let mut spawned = HashMap::new();
         for entity in self.build_data.spawn_list.iter() {
-            spawner::spawn_entity(ecs, &(&entity.0, &entity.1));
+            if let Some(spawn) = spawner::spawn_entity(ecs, &(&entity.0, &entity.1)) {
+                if ecs.read_storage::<Initiative>().contains(spawn) {
+                    spawned.entry(entity.0).or_insert(spawn);
+                }
+            }
+        }
+
+        // Members can spawn before their leader, so they are linked once everyone exists
+        let mut followers = ecs.write_storage::<Follower>();
+        for (member_idx, leader_idx) in self.build_data.spawn_leaders.iter() {
+            if let (Some(member), Some(leader)) = (spawned.get(member_idx), spawned.get(leader_idx))
+            {
+                followers
+                    .insert(*member, Follower { leader: *leader })
+                    .expect("Unable to insert");
+            }
         }
     }
 }
 
 impl BuilderMap {
+    /// Keeps the spawns whose tile `keep` returns true for. Groups that lose their leader or a
+    /// member's spawn are broken up, so no link is left for whatever spawns on those tiles later.
+    fn retain_spawns<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Map, usize) -> bool,
+    {
+        let map = &self.map;
+        let mut removed = HashSet::new();
+        self.spawn_list.retain(|(idx, _name)| {
+            let kept = keep(map, *idx);
+            if !kept {
+                removed.insert(*idx);
+            }
+            kept
+        });
+        self.spawn_leaders
+            .retain(|member, leader| !removed.contains(member) && !removed.contains(leader));
+    }
+
     fn take_snapshot(&mut self) {
         if crate::SHOW_MAPGEN_VISUALIZER {
             let mut snapshot = self.map.clone();
@@ -162,176 +188,23 @@ pub trait MetaMapBuilder {
     fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap);
 }
 
-pub fn random_builder(
-    new_depth: i32,
-    rng: &mut rltk::RandomNumberGenerator,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut builder = BuilderChain::new(new_depth, width, height, "New Map");
-    let type_roll = rng.roll_dice(1, 2);
-    match type_roll {
-        1 => random_room_builder(rng, &mut builder),
-        _ => random_shape_builder(rng, &mut builder),
-    }
-
-    if rng.roll_dice(1, 3) == 1 {
-        builder.with(WaveformCollapseBuilder::new());
-
-        // Now set the start to a random starting area
-        let (start_x, start_y) = random_start_position(rng);
-        builder.with(AreaStartingPosition::new(start_x, start_y));
-
-        // Setup an exit and spawn mobs
-        builder.with(VoronoiSpawning::new());
-        builder.with(DistantExit::new());
-    }
-
-    if rng.roll_dice(1, 20) == 1 {
-        builder.with(PrefabBuilder::sectional(
-            prefab_builder::prefab_sections::UNDERGROUND_FORT,
-        ));
-    }
-
-    builder.with(DoorPlacement::new());
-    builder.with(PrefabBuilder::vaults());
-
-    builder
-}
-
-fn random_start_position(rng: &mut rltk::RandomNumberGenerator) -> (XStart, YStart) {
-    match (rng.roll_dice(1, 3), rng.roll_dice(1, 3)) {
-        (1, 1) => (XStart::Left, YStart::Top),
-        (1, 2) => (XStart::Left, YStart::Center),
-        (1, _) => (XStart::Left, YStart::Bottom),
-        (2, 1) => (XStart::Center, YStart::Top),
-        (2, 2) => (XStart::Center, YStart::Center),
-        (2, _) => (XStart::Center, YStart::Bottom),
-        (_, 1) => (XStart::Right, YStart::Top),
-        (_, 2) => (XStart::Right, YStart::Center),
-        (_, _) => (XStart::Right, YStart::Bottom),
-    }
-}
-
-fn random_room_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut BuilderChain) {
-    let build_roll = rng.roll_dice(1, 3);
-    match build_roll {
-        1 => builder.start_with(SimpleMapBuilder::new()),
-        2 => builder.start_with(BspDungeonBuilder::new()),
-        _ => builder.start_with(BspInteriorBuilder::new()),
-    }
-
-    // BSP Interior still makes holes in the walls
-    if build_roll != 3 {
-        // Sort by one of the 5 available algorithms
-        let sort_roll = rng.roll_dice(1, 5);
-        match sort_roll {
-            1 => builder.with(RoomSorter::new(RoomSort::Leftmost)),
-            2 => builder.with(RoomSorter::new(RoomSort::Rightmost)),
-            3 => builder.with(RoomSorter::new(RoomSort::Topmost)),
-            4 => builder.with(RoomSorter::new(RoomSort::Bottommost)),
-            _ => builder.with(RoomSorter::new(RoomSort::Central)),
-        }
-
-        // Apply floor tiles to map at rooms locs
-        builder.with(RoomDrawer::new());
-
-        let corridor_roll = rng.roll_dice(1, 4);
-        match corridor_roll {
-            1 => builder.with(DoglegCorridors::new()),
-            2 => builder.with(NearestCorridors::new()),
-            3 => builder.with(StraightLineCorridors::new()),
-            _ => builder.with(BspCorridors::new()),
-        }
-
-        let corridor_spawn_roll = rng.roll_dice(1, 2);
-        if corridor_spawn_roll == 1 {
-            builder.with(CorridorSpawner::new());
-        }
-
-        let modifier_roll = rng.roll_dice(1, 6);
-        match modifier_roll {
-            1 => builder.with(RoomExploder::new()),
-            2 => builder.with(RoomCornerRounder::new()),
-            _ => {}
-        }
-    }
-
-    let start_roll = rng.roll_dice(1, 2);
-    match start_roll {
-        1 => builder.with(RoomBasedStartingPosition::new()),
-        _ => {
-            let (start_x, start_y) = random_start_position(rng);
-            builder.with(AreaStartingPosition::new(start_x, start_y));
-        }
-    }
-
-    let exit_roll = rng.roll_dice(1, 2);
-    match exit_roll {
-        1 => builder.with(RoomBasedStairs::new()),
-        _ => builder.with(DistantExit::new()),
-    }
-
-    let spawn_roll = rng.roll_dice(1, 2);
-    match spawn_roll {
-        1 => builder.with(RoomBasedSpawner::new()),
-        _ => builder.with(VoronoiSpawning::new()),
-    }
-}
-
-fn random_shape_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut BuilderChain) {
-    let builder_roll = rng.roll_dice(1, 18);
-    match builder_roll {
-        1 => builder.start_with(CellularAutomataBuilder::new()),
-        2 => builder.start_with(DrunkardsWalkBuilder::open_area()),
-        3 => builder.start_with(DrunkardsWalkBuilder::open_halls()),
-        4 => builder.start_with(DrunkardsWalkBuilder::winding_passages()),
-        5 => builder.start_with(DrunkardsWalkBuilder::fat_passages()),
-        6 => builder.start_with(DrunkardsWalkBuilder::fearful_symmetry()),
-        7 => builder.start_with(MazeBuilder::new()),
-        8 => builder.start_with(DLABuilder::walk_inwards()),
-        9 => builder.start_with(DLABuilder::walk_outwards()),
-        10 => builder.start_with(DLABuilder::central_attractor()),
-        11 => builder.start_with(DLABuilder::insectoid()),
-        12 => builder.start_with(DLABuilder::heavy_erosion()),
-        13 => builder.start_with(VoronoiCellBuilder::pythagoras()),
-        14 => builder.start_with(VoronoiCellBuilder::manhattan()),
-        15 => builder.start_with(VoronoiCellBuilder::chebyshev()),
-        _ => builder.start_with(PrefabBuilder::constant(
-            prefab_builder::prefab_levels::WFC_POPULATED,
-        )),
-    }
-
-    // Set the start to the center and cull
-    builder.with(AreaStartingPosition::new(XStart::Center, YStart::Center));
-    builder.with(CullUnreachable::new());
-
-    // Now set the start to a random starting area
-    let (start_x, start_y) = random_start_position(rng);
-    builder.with(AreaStartingPosition::new(start_x, start_y));
-
-    // Setup an exit and spawn mobs
-    builder.with(VoronoiSpawning::new());
-    builder.with(DistantExit::new());
-}
-
+/// Sets up the chain that builds the level at `new_depth`, as the raws describe it.
 pub fn level_builder(
     new_depth: i32,
     rng: &mut rltk::RandomNumberGenerator,
-    width: i32,
-    height: i32,
+    raws: &RawMaster,
 ) -> BuilderChain {
     rltk::console::log(format!("Depth: {new_depth}"));
-    match new_depth {
-        1 => town_builder(new_depth, rng, width, height),
-        2 => forest_builder(new_depth, rng, width, height),
-        3 => limestone_cavern_builder(new_depth, rng, width, height),
-        4 => limestone_deep_cavern_builder(new_depth, rng, width, height),
-        5 => limestone_transition_builder(new_depth, rng, width, height),
-        6 => dwarf_fort_builder(new_depth, rng, width, height),
-        7 => mushroom_entrance(new_depth, rng, width, height),
-        8 => mushroom_builder(new_depth, rng, width, height),
-        9 => mushroom_exit(new_depth, rng, width, height),
-        _ => random_builder(new_depth, rng, width, height),
-    }
+    let level = get_level(raws, new_depth);
+    let mut chain = BuilderChain::new(
+        new_depth,
+        level.width,
+        level.height,
+        level.map_name.as_deref().unwrap_or(&level.name),
+    );
+    chain.with_tags(&level.tags);
+    chain.build_data.map.theme = level.theme.clone();
+
+    apply_recipe(raws, &level.builder, rng, &mut chain);
+    chain
 }
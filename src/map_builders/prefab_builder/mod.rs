@@ -1,23 +1,32 @@
 use rltk::RandomNumberGenerator;
+use serde::Deserialize;
 
 use super::{BuilderMap, InitialMapBuilder, MetaMapBuilder};
-use crate::{Position, TileType};
-use std::collections::HashSet;
-pub mod prefab_levels;
-pub mod prefab_rooms;
-pub mod prefab_sections;
+use crate::{map::tile_walkable, raws::prefab_structs::Prefab, Position, TileType};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HorizontalPlacement {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalPlacement {
+    Top,
+    #[default]
+    Center,
+    Bottom,
+}
 
 pub enum PrefabMode {
-    RexLevel {
-        template: &'static str,
-    },
-    Constant {
-        level: prefab_levels::PrefabLevel,
-    },
-    Sectional {
-        section: prefab_sections::PrefabSection,
-    },
-    RoomVaults,
+    Constant { level: Prefab },
+    Sectional { section: Prefab },
+    RoomVaults { vaults: Vec<Prefab> },
 }
 
 pub struct PrefabBuilder {
@@ -37,117 +46,67 @@ impl InitialMapBuilder for PrefabBuilder {
 }
 
 impl PrefabBuilder {
-    #[allow(dead_code)]
-    pub fn rex_level(template: &'static str) -> Box<PrefabBuilder> {
-        Box::new(PrefabBuilder {
-            mode: PrefabMode::RexLevel { template },
-        })
-    }
-
-    pub fn constant(level: prefab_levels::PrefabLevel) -> Box<PrefabBuilder> {
+    pub fn constant(level: Prefab) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
             mode: PrefabMode::Constant { level },
         })
     }
 
-    pub fn sectional(section: prefab_sections::PrefabSection) -> Box<PrefabBuilder> {
+    pub fn sectional(section: Prefab) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
             mode: PrefabMode::Sectional { section },
         })
     }
 
-    pub fn vaults() -> Box<PrefabBuilder> {
+    /// Picks among `vaults` for the ones that fit the level's depth.
+    pub fn vaults(vaults: Vec<Prefab>) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
-            mode: PrefabMode::RoomVaults,
+            mode: PrefabMode::RoomVaults { vaults },
         })
     }
 
     fn build(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
-        match self.mode {
-            PrefabMode::RexLevel { template } => self.load_rex_map(template, build_data),
-            PrefabMode::Constant { level } => self.load_ascii_map(&level, build_data),
-            PrefabMode::Sectional { section } => self.apply_sectional(&section, rng, build_data),
-            PrefabMode::RoomVaults => self.apply_room_vaults(rng, build_data),
-        }
-        build_data.take_snapshot();
-    }
-
-    // MIT LICENSE: @Smokku - Copyright (c) 2020 Tomasz Sterna
-    fn read_ascii_to_vec(template: &str, width: usize, height: usize) -> Vec<char> {
-        let vec: Vec<char> = template
-            .lines()
-            .map(|line| format!("{line: <width$}"))
-            .collect::<Vec<_>>()
-            .concat()
-            .chars()
-            .collect();
-        if vec.len() != width * height {
-            panic!("Loaded template did not yield the expected number of characters. Got {cur_chars}, expected {total_chars}.\n{template:?}", cur_chars = vec.len(), total_chars = width * height);
-        }
-
-        vec
-    }
-
-    fn load_rex_map(&mut self, path: &str, build_data: &mut BuilderMap) {
-        let xp_file = rltk::rex::XpFile::from_resource(path).unwrap();
-
-        for layer in &xp_file.layers {
-            for y in 0..layer.height {
-                for x in 0..layer.width {
-                    let cell = layer.get(x, y).unwrap();
-                    if x < build_data.map.width as usize && y < build_data.map.height as usize {
-                        let idx = build_data.map.xy_idx(x as i32, y as i32);
-                        // NOTE: nasty casting.
-                        self.set_tile_from_char(cell.ch as u8 as char, idx, build_data);
-                    }
-                }
+        match &self.mode {
+            PrefabMode::Constant { level } => PrefabBuilder::load_ascii_map(level, build_data),
+            PrefabMode::Sectional { section } => {
+                PrefabBuilder::apply_sectional(section, rng, build_data)
+            }
+            PrefabMode::RoomVaults { vaults } => {
+                PrefabBuilder::apply_room_vaults(vaults, rng, build_data)
             }
         }
+        build_data.take_snapshot();
     }
 
-    fn load_ascii_map(&mut self, level: &prefab_levels::PrefabLevel, build_data: &mut BuilderMap) {
-        // Start by converting to a vector, with newlines removed
-        let string_vec =
-            PrefabBuilder::read_ascii_to_vec(level.template, level.width, level.height);
-
+    fn load_ascii_map(level: &Prefab, build_data: &mut BuilderMap) {
         let mut i = 0;
         for ty in 0..level.height {
             for tx in 0..level.width {
                 if tx < build_data.map.width as usize && ty < build_data.map.height as usize {
                     let idx = build_data.map.xy_idx(tx as i32, ty as i32);
-                    self.set_tile_from_char(string_vec[i], idx, build_data);
+                    PrefabBuilder::set_tile_from_char(level, level.cells[i], idx, build_data);
                 }
                 i += 1;
             }
         }
     }
 
-    // TODO(aalhendi): Refactor
-    fn set_tile_from_char(&mut self, ch: char, idx: usize, build_data: &mut BuilderMap) {
+    /// Sets the tile at `idx` for the character `ch`, using `prefab`'s legend for spawns.
+    fn set_tile_from_char(prefab: &Prefab, ch: char, idx: usize, build_data: &mut BuilderMap) {
+        let spawn = prefab.spawn_for(ch);
         let tile = match ch {
-            ' ' | '@' | 'g' | '^' | '!' | '%' | 'O' | '☼' | 'o' | 'e' => Some(TileType::Floor),
+            ' ' | '@' => Some(TileType::Floor),
             '#' => Some(TileType::Wall),
             '>' => Some(TileType::DownStairs),
+            _ if spawn.is_some() => Some(TileType::Floor),
             _ => None,
         };
         if let Some(tile) = tile {
             build_data.map.tiles[idx] = tile;
         }
 
-        let spawn = match ch {
-            'g' => Some("Goblin".to_string()),
-            'e' => Some("Dark Elf".to_string()),
-            //'o' => Some(Monster::Orc), // TODO: Use enum for spawns. to_string() allocs to heap
-            'o' => Some("Orc".to_string()),
-            'O' => Some("Orc Leader".to_string()),
-            '^' => Some("Bear Trap".to_string()),
-            '%' => Some("Rations".to_string()),
-            '!' => Some("Health Potion".to_string()),
-            '☼' => Some("Watch Fire".to_string()),
-            _ => None,
-        };
         if let Some(name) = spawn {
-            build_data.spawn_list.push((idx, name));
+            build_data.spawn_list.push((idx, name.clone()));
         }
 
         if ch == '@' {
@@ -157,81 +116,59 @@ impl PrefabBuilder {
         }
     }
 
-    fn apply_previous_iteration<F>(
-        &mut self,
-        mut filter: F,
-        _rng: &mut RandomNumberGenerator,
-        build_data: &mut BuilderMap,
-    ) where
+    fn apply_previous_iteration<F>(mut filter: F, build_data: &mut BuilderMap)
+    where
         F: FnMut(i32, i32) -> bool,
     {
-        build_data.spawn_list.retain(|(idx, _name)| {
-            let (x, y) = build_data.map.idx_xy(*idx);
+        build_data.retain_spawns(|map, idx| {
+            let (x, y) = map.idx_xy(idx);
             filter(x, y)
         });
         build_data.take_snapshot();
     }
 
+    /// Stamps `section` at its placement, turned and flipped any way it allows that keeps the
+    /// ways down reachable. Sections that can't be placed that way are left out.
     fn apply_sectional(
-        &mut self,
-        section: &prefab_sections::PrefabSection,
+        section: &Prefab,
         rng: &mut RandomNumberGenerator,
         build_data: &mut BuilderMap,
     ) {
-        use prefab_sections::*;
-
-        let string_vec =
-            PrefabBuilder::read_ascii_to_vec(section.template, section.width, section.height);
-
-        // Place the new section
-        let chunk_x = match section.placement.0 {
-            HorizontalPlacement::Left => 0,
-            HorizontalPlacement::Center => (build_data.map.width / 2) - (section.width as i32 / 2),
-            HorizontalPlacement::Right => (build_data.map.width - 1) - section.width as i32,
-        };
-
-        let chunk_y = match section.placement.1 {
-            VerticalPlacement::Top => 0,
-            VerticalPlacement::Center => (build_data.map.height / 2) - (section.height as i32 / 2),
-            VerticalPlacement::Bottom => (build_data.map.height - 1) - section.height as i32,
-        };
-
-        // Build the map
-        self.apply_previous_iteration(
-            |x, y| {
-                x < chunk_x
-                    || x > (chunk_x + section.width as i32)
-                    || y < chunk_y
-                    || y > (chunk_y + section.height as i32)
-            },
-            rng,
-            build_data,
-        );
+        let mut orientations = PrefabBuilder::orientations(section, build_data);
+        while !orientations.is_empty() {
+            let oriented =
+                orientations.remove(PrefabBuilder::random_index(rng, orientations.len()));
+
+            // Place the new section
+            let chunk_x = match oriented.placement.x {
+                HorizontalPlacement::Left => 0,
+                HorizontalPlacement::Center => {
+                    (build_data.map.width / 2) - (oriented.width as i32 / 2)
+                }
+                HorizontalPlacement::Right => (build_data.map.width - 1) - oriented.width as i32,
+            };
 
-        let mut i = 0;
-        for ty in 0..section.height {
-            for tx in 0..section.width {
-                if tx > 0
-                    && tx < build_data.map.width as usize - 1
-                    && ty < build_data.map.height as usize - 1
-                    && ty > 0
-                {
-                    let idx = build_data
-                        .map
-                        .xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                    self.set_tile_from_char(string_vec[i], idx, build_data);
+            let chunk_y = match oriented.placement.y {
+                VerticalPlacement::Top => 0,
+                VerticalPlacement::Center => {
+                    (build_data.map.height / 2) - (oriented.height as i32 / 2)
                 }
-                i += 1;
+                VerticalPlacement::Bottom => (build_data.map.height - 1) - oriented.height as i32,
+            };
+
+            if PrefabBuilder::stamp_if_connected(&oriented, chunk_x, chunk_y, build_data) {
+                break;
             }
         }
-        build_data.take_snapshot();
     }
 
-    fn apply_room_vaults(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
-        use prefab_rooms::*;
-
+    fn apply_room_vaults(
+        vaults: &[Prefab],
+        rng: &mut RandomNumberGenerator,
+        build_data: &mut BuilderMap,
+    ) {
         // Apply the previous builder, and keep all entities it spawns (for now)
-        self.apply_previous_iteration(|_x, _y| true, rng, build_data);
+        PrefabBuilder::apply_previous_iteration(|_x, _y| true, build_data);
 
         // Vault spawns have 3/6 chance
         let vault_roll = rng.roll_dice(1, 6) + build_data.map.depth;
@@ -239,100 +176,210 @@ impl PrefabBuilder {
             return;
         };
 
-        // NOTE: place-holder, will be moved out of this function
-        let master_vault_list = [TOTALLY_NOT_A_TRAP, SILLY_SMILE, CHECKERBOARD];
-
         // Filter vault list by current depth
-        let mut possible_vaults: Vec<&PrefabRoom> = master_vault_list
+        let mut possible_vaults: Vec<&Prefab> = vaults
             .iter()
-            .filter(|v| {
-                build_data.map.depth >= v.first_depth && build_data.map.depth <= v.last_depth
-            })
+            .filter(|v| v.in_depth(build_data.map.depth))
             .collect();
-
-        // No vaults, return early
-        if possible_vaults.is_empty() {
-            return;
-        }
-
-        let n_vaults = i32::min(rng.roll_dice(1, 3), possible_vaults.len() as i32);
+        let mut times_placed: HashMap<&str, i32> = HashMap::new();
         let mut used_tiles = HashSet::new();
 
-        for _ in 0..n_vaults {
-            let vault_index = if possible_vaults.len() == 1 {
-                0
-            } else {
-                (rng.roll_dice(1, possible_vaults.len() as i32) - 1) as usize
-            };
+        for _ in 0..rng.roll_dice(1, 3) {
+            // No vaults left, return early
+            if possible_vaults.is_empty() {
+                return;
+            }
+
+            let vault_index = PrefabBuilder::pick_vault(rng, &possible_vaults);
             let vault = possible_vaults[vault_index];
+            let mut orientations = PrefabBuilder::orientations(vault, build_data);
+            if orientations.is_empty() {
+                continue;
+            }
+            let oriented =
+                orientations.remove(PrefabBuilder::random_index(rng, orientations.len()));
 
-            // List of positions vault can fit it
+            // Every position the vault fits, on floor no other vault has taken
             let mut vault_positions: Vec<Position> = Vec::new();
-
             for idx in 0..build_data.map.tiles.len() {
                 let (x, y) = build_data.map.idx_xy(idx);
 
                 // Check map bounds
                 if x > 1
-                    && (x + vault.width as i32) < build_data.map.width - 2
+                    && (x + oriented.width as i32) < build_data.map.width - 2
                     && y > 1
-                    && (y + vault.height as i32) < build_data.map.height - 2
+                    && (y + oriented.height as i32) < build_data.map.height - 2
                 {
-                    // TODO: REFACTOR
-                    let mut possible = true;
-                    'map: for ty in 0..vault.height as i32 {
-                        for tx in 0..vault.width as i32 {
+                    let fits = (0..oriented.height as i32).all(|ty| {
+                        (0..oriented.width as i32).all(|tx| {
                             let t_idx = build_data.map.xy_idx(tx + x, ty + y);
-                            if build_data.map.tiles[t_idx] != TileType::Floor
-                                || used_tiles.contains(&idx)
-                            {
-                                possible = false;
-                                break 'map;
-                            }
+                            build_data.map.tiles[t_idx] == TileType::Floor
+                                && !used_tiles.contains(&t_idx)
+                        })
+                    });
+                    if fits {
+                        vault_positions.push(Position { x, y });
+                    }
+                }
+            }
+
+            while !vault_positions.is_empty() {
+                let pos =
+                    vault_positions.remove(PrefabBuilder::random_index(rng, vault_positions.len()));
+                if PrefabBuilder::stamp_if_connected(&oriented, pos.x, pos.y, build_data) {
+                    for ty in 0..oriented.height as i32 {
+                        for tx in 0..oriented.width as i32 {
+                            used_tiles.insert(build_data.map.xy_idx(tx + pos.x, ty + pos.y));
                         }
                     }
+                    build_data.take_snapshot();
 
-                    if possible {
-                        vault_positions.push(Position { x, y });
-                        break;
+                    let placed = times_placed.entry(vault.name.as_str()).or_insert(0);
+                    *placed += 1;
+                    if *placed >= vault.max_per_level {
+                        possible_vaults.remove(vault_index);
                     }
+                    break;
                 }
             }
+        }
+    }
 
-            if vault_positions.is_empty() {
-                continue;
+    /// Rolls for one of `vaults`, weighted by how common each is.
+    fn pick_vault(rng: &mut RandomNumberGenerator, vaults: &[&Prefab]) -> usize {
+        let total_weight: i32 = vaults.iter().map(|vault| vault.weight).sum();
+        let mut roll = rng.roll_dice(1, total_weight) - 1;
+        for (i, vault) in vaults.iter().enumerate() {
+            if roll < vault.weight {
+                return i;
             }
+            roll -= vault.weight;
+        }
+        vaults.len() - 1
+    }
 
-            let pos_idx = if vault_positions.len() == 1 {
-                0
-            } else {
-                (rng.roll_dice(1, vault_positions.len() as i32) - 1) as usize
-            };
-            let pos = &vault_positions[pos_idx];
-
-            // Don't spawn things in the vault tiles.
-            build_data.spawn_list.retain(|e| {
-                let idx = e.0 as i32;
-                let (x, y) = build_data.map.idx_xy(idx as usize);
-                x < pos.x
-                    || x > pos.x + vault.width as i32
-                    || y < pos.y
-                    || y > pos.y + vault.height as i32
-            });
-
-            let string_vec =
-                PrefabBuilder::read_ascii_to_vec(vault.template, vault.width, vault.height);
-            let mut i = 0;
-            for ty in 0..vault.height {
-                for tx in 0..vault.width {
-                    let idx = build_data.map.xy_idx(tx as i32 + pos.x, ty as i32 + pos.y);
-                    self.set_tile_from_char(string_vec[i], idx, build_data);
-                    used_tiles.insert(idx);
-                    i += 1;
+    fn random_index(rng: &mut RandomNumberGenerator, len: usize) -> usize {
+        (rng.roll_dice(1, len as i32) - 1) as usize
+    }
+
+    /// The ways `prefab` allows itself to be turned and flipped, that fit on the map.
+    fn orientations(prefab: &Prefab, build_data: &BuilderMap) -> Vec<Prefab> {
+        let flips: &[(bool, bool)] = match (prefab.mirror, prefab.rotate) {
+            (false, _) => &[(false, false)],
+            // Flipping top to bottom is a flip left to right, turned twice
+            (true, true) => &[(false, false), (true, false)],
+            (true, false) => &[(false, false), (true, false), (false, true), (true, true)],
+        };
+        let turns = if prefab.rotate { 4 } else { 1 };
+
+        let mut orientations = Vec::new();
+        for &(flip_x, flip_y) in flips {
+            let mut oriented = PrefabBuilder::flipped(prefab, flip_x, flip_y);
+            for _ in 0..turns {
+                if oriented.width as i32 <= build_data.map.width
+                    && oriented.height as i32 <= build_data.map.height
+                {
+                    orientations.push(oriented.clone());
                 }
+                oriented = PrefabBuilder::turned(&oriented);
             }
-            build_data.take_snapshot();
-            possible_vaults.remove(vault_index);
         }
+        orientations
+    }
+
+    fn flipped(prefab: &Prefab, flip_x: bool, flip_y: bool) -> Prefab {
+        let mut flipped = prefab.clone();
+        for y in 0..prefab.height {
+            for x in 0..prefab.width {
+                let from_x = if flip_x { prefab.width - 1 - x } else { x };
+                let from_y = if flip_y { prefab.height - 1 - y } else { y };
+                flipped.cells[y * prefab.width + x] = prefab.cells[from_y * prefab.width + from_x];
+            }
+        }
+        flipped
+    }
+
+    /// `prefab` turned a quarter clockwise.
+    fn turned(prefab: &Prefab) -> Prefab {
+        let mut turned = prefab.clone();
+        turned.width = prefab.height;
+        turned.height = prefab.width;
+        for y in 0..turned.height {
+            for x in 0..turned.width {
+                let (from_x, from_y) = (y, prefab.height - 1 - x);
+                turned.cells[y * turned.width + x] = prefab.cells[from_y * prefab.width + from_x];
+            }
+        }
+        turned
+    }
+
+    /// Stamps `prefab` with its top left corner at `x`, `y`, inside the map's edge, unless that
+    /// would cut the starting position off from a way down it could reach before. Returns
+    /// whether it was stamped; if not, the map is left as it was.
+    fn stamp_if_connected(prefab: &Prefab, x: i32, y: i32, build_data: &mut BuilderMap) -> bool {
+        let reachable = PrefabBuilder::reachable_exits(build_data);
+        let tiles = build_data.map.tiles.clone();
+        let spawn_list = build_data.spawn_list.clone();
+        let spawn_leaders = build_data.spawn_leaders.clone();
+        let starting_position = build_data.starting_position;
+
+        // Don't spawn things in the prefab's tiles
+        let (width, height) = (prefab.width as i32, prefab.height as i32);
+        build_data.retain_spawns(|map, idx| {
+            let (sx, sy) = map.idx_xy(idx);
+            sx < x || sx >= x + width || sy < y || sy >= y + height
+        });
+
+        for ty in 0..height {
+            for tx in 0..width {
+                let (mx, my) = (tx + x, ty + y);
+                if mx > 0
+                    && mx < build_data.map.width - 1
+                    && my > 0
+                    && my < build_data.map.height - 1
+                {
+                    let idx = build_data.map.xy_idx(mx, my);
+                    let ch = prefab.cells[(ty * width + tx) as usize];
+                    PrefabBuilder::set_tile_from_char(prefab, ch, idx, build_data);
+                }
+            }
+        }
+
+        if reachable.is_subset(&PrefabBuilder::reachable_exits(build_data)) {
+            return true;
+        }
+        build_data.map.tiles = tiles;
+        build_data.spawn_list = spawn_list;
+        build_data.spawn_leaders = spawn_leaders;
+        build_data.starting_position = starting_position;
+        false
+    }
+
+    /// The down stairs the player can walk to from the starting position.
+    fn reachable_exits(build_data: &mut BuilderMap) -> HashSet<usize> {
+        let start_idx = match &build_data.starting_position {
+            Some(start) => build_data.map.xy_idx(start.x, start.y),
+            None => return HashSet::new(),
+        };
+        if !tile_walkable(build_data.map.tiles[start_idx]) {
+            return HashSet::new();
+        }
+
+        build_data.map.populate_blocked();
+        let dijkstra_map = rltk::DijkstraMap::new(
+            build_data.map.width as usize,
+            build_data.map.height as usize,
+            &[start_idx],
+            &build_data.map,
+            1000.0,
+        );
+        build_data
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(i, tile)| **tile == TileType::DownStairs && dijkstra_map.map[*i] != f32::MAX)
+            .map(|(i, _tile)| i)
+            .collect()
     }
 }
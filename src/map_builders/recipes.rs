@@ -0,0 +1,190 @@
+use crate::raws::{
+    builder_chain_structs::{
+        DlaPreset, DrunkardPreset, InitialBuilder, MetaBuilder, PrefabChoice, RecipeChoice,
+        RecipeStep, VoronoiPreset,
+    },
+    prefab_structs::PrefabKind,
+    rawsmaster::{get_builder_recipe, get_prefab, RawMaster},
+};
+
+use super::{
+    area_ending_point::AreaEndingPosition,
+    area_starting_points::AreaStartingPosition,
+    bsp_dungeon::BspDungeonBuilder,
+    bsp_interior::BspInteriorBuilder,
+    cellular_automata::CellularAutomataBuilder,
+    cull_unreachable::CullUnreachable,
+    distant_exit::DistantExit,
+    dla::DLABuilder,
+    door_placement::DoorPlacement,
+    drunkard::DrunkardsWalkBuilder,
+    dwarf_fort::{DragonSpawner, DragonsLair},
+    limestone_cavern::{CaveDecorator, CaveTransition},
+    maze::MazeBuilder,
+    prefab_builder::PrefabBuilder,
+    room_based_spawner::RoomBasedSpawner,
+    room_based_stairs::RoomBasedStairs,
+    room_based_starting_position::RoomBasedStartingPosition,
+    room_corner_rounding::RoomCornerRounder,
+    room_corridor_spawner::CorridorSpawner,
+    room_draw::RoomDrawer,
+    room_exploder::RoomExploder,
+    room_sorter::RoomSorter,
+    rooms_corridors_bsp::BspCorridors,
+    rooms_corridors_dogleg::DoglegCorridors,
+    rooms_corridors_lines::StraightLineCorridors,
+    rooms_corridors_nearest::NearestCorridors,
+    simple_map::SimpleMapBuilder,
+    town::TownBuilder,
+    voronoi::VoronoiCellBuilder,
+    voronoi_spawning::VoronoiSpawning,
+    waveform_collapse::WaveformCollapseBuilder,
+    yellow_brick_road::YellowBrickRoad,
+    BuilderChain, InitialMapBuilder, MetaMapBuilder,
+};
+
+/// Adds the builders of the recipe `name` to `chain`, rolling for its choices as it goes.
+/// Expects raws that passed `validate_raws`.
+pub fn apply_recipe(
+    raws: &RawMaster,
+    name: &str,
+    rng: &mut rltk::RandomNumberGenerator,
+    chain: &mut BuilderChain,
+) {
+    let recipe = get_builder_recipe(raws, name).expect("Unknown builder recipe");
+    apply_steps(raws, &recipe.steps, rng, chain);
+}
+
+fn apply_steps(
+    raws: &RawMaster,
+    steps: &[RecipeStep],
+    rng: &mut rltk::RandomNumberGenerator,
+    chain: &mut BuilderChain,
+) {
+    for step in steps {
+        match step {
+            RecipeStep::Start(builder) => chain.start_with(initial_builder(raws, builder)),
+            RecipeStep::With(builder) => chain.with(meta_builder(raws, builder)),
+            RecipeStep::OneOf(choices) => {
+                if let Some(choice) = pick_choice(rng, choices) {
+                    apply_steps(raws, &choice.steps, rng, chain);
+                }
+            }
+            RecipeStep::Recipe(name) => apply_recipe(raws, name, rng, chain),
+        }
+    }
+}
+
+fn pick_choice<'a>(
+    rng: &mut rltk::RandomNumberGenerator,
+    choices: &'a [RecipeChoice],
+) -> Option<&'a RecipeChoice> {
+    let total_weight: i32 = choices.iter().map(|choice| choice.weight).sum();
+    if total_weight < 1 {
+        return None;
+    }
+
+    let mut roll = rng.roll_dice(1, total_weight) - 1;
+    for choice in choices {
+        if roll < choice.weight {
+            return Some(choice);
+        }
+        roll -= choice.weight;
+    }
+    None
+}
+
+fn prefab_builder(raws: &RawMaster, choice: &PrefabChoice) -> Box<PrefabBuilder> {
+    match choice {
+        PrefabChoice::Level(name) => PrefabBuilder::constant(
+            get_prefab(raws, name)
+                .expect("Unknown prefab level")
+                .clone(),
+        ),
+        PrefabChoice::Section(name) => PrefabBuilder::sectional(
+            get_prefab(raws, name)
+                .expect("Unknown prefab section")
+                .clone(),
+        ),
+        PrefabChoice::Vaults => PrefabBuilder::vaults(
+            raws.raws
+                .prefabs
+                .iter()
+                .filter(|prefab| prefab.kind == PrefabKind::Vault)
+                .cloned()
+                .collect(),
+        ),
+    }
+}
+
+fn drunkards_walk(preset: &DrunkardPreset) -> Box<DrunkardsWalkBuilder> {
+    match preset {
+        DrunkardPreset::OpenArea => DrunkardsWalkBuilder::open_area(),
+        DrunkardPreset::OpenHalls => DrunkardsWalkBuilder::open_halls(),
+        DrunkardPreset::WindingPassages => DrunkardsWalkBuilder::winding_passages(),
+        DrunkardPreset::FatPassages => DrunkardsWalkBuilder::fat_passages(),
+        DrunkardPreset::FearfulSymmetry => DrunkardsWalkBuilder::fearful_symmetry(),
+    }
+}
+
+fn dla(preset: &DlaPreset) -> Box<DLABuilder> {
+    match preset {
+        DlaPreset::WalkInwards => DLABuilder::walk_inwards(),
+        DlaPreset::WalkOutwards => DLABuilder::walk_outwards(),
+        DlaPreset::CentralAttractor => DLABuilder::central_attractor(),
+        DlaPreset::Insectoid => DLABuilder::insectoid(),
+        DlaPreset::HeavyErosion => DLABuilder::heavy_erosion(),
+    }
+}
+
+fn initial_builder(raws: &RawMaster, builder: &InitialBuilder) -> Box<dyn InitialMapBuilder> {
+    match builder {
+        InitialBuilder::SimpleMap => SimpleMapBuilder::new(),
+        InitialBuilder::BspDungeon => BspDungeonBuilder::new(),
+        InitialBuilder::BspInterior => BspInteriorBuilder::new(),
+        InitialBuilder::CellularAutomata => CellularAutomataBuilder::new(),
+        InitialBuilder::DrunkardsWalk(preset) => drunkards_walk(preset),
+        InitialBuilder::Maze => MazeBuilder::new(),
+        InitialBuilder::Dla(preset) => dla(preset),
+        InitialBuilder::Voronoi(preset) => match preset {
+            VoronoiPreset::Pythagoras => VoronoiCellBuilder::pythagoras(),
+            VoronoiPreset::Manhattan => VoronoiCellBuilder::manhattan(),
+            VoronoiPreset::Chebyshev => VoronoiCellBuilder::chebyshev(),
+        },
+        InitialBuilder::Prefab(choice) => prefab_builder(raws, choice),
+        InitialBuilder::Town => TownBuilder::new(),
+    }
+}
+
+fn meta_builder(raws: &RawMaster, builder: &MetaBuilder) -> Box<dyn MetaMapBuilder> {
+    match builder {
+        MetaBuilder::CellularAutomata => CellularAutomataBuilder::new(),
+        MetaBuilder::DrunkardsWalk(preset) => drunkards_walk(preset),
+        MetaBuilder::Dla(preset) => dla(preset),
+        MetaBuilder::WaveformCollapse => WaveformCollapseBuilder::new(),
+        MetaBuilder::Prefab(choice) => prefab_builder(raws, choice),
+        MetaBuilder::RoomSorter(sort_by) => RoomSorter::new(*sort_by),
+        MetaBuilder::RoomDrawer => RoomDrawer::new(),
+        MetaBuilder::RoomExploder => RoomExploder::new(),
+        MetaBuilder::RoomCornerRounder => RoomCornerRounder::new(),
+        MetaBuilder::BspCorridors => BspCorridors::new(),
+        MetaBuilder::DoglegCorridors => DoglegCorridors::new(),
+        MetaBuilder::NearestCorridors => NearestCorridors::new(),
+        MetaBuilder::StraightLineCorridors => StraightLineCorridors::new(),
+        MetaBuilder::CorridorSpawner => CorridorSpawner::new(),
+        MetaBuilder::AreaStartingPosition { x, y } => AreaStartingPosition::new(*x, *y),
+        MetaBuilder::RoomBasedStartingPosition => RoomBasedStartingPosition::new(),
+        MetaBuilder::AreaEndingPosition { x, y } => AreaEndingPosition::new(*x, *y),
+        MetaBuilder::DistantExit => DistantExit::new(),
+        MetaBuilder::RoomBasedStairs => RoomBasedStairs::new(),
+        MetaBuilder::YellowBrickRoad => YellowBrickRoad::new(),
+        MetaBuilder::CullUnreachable => CullUnreachable::new(),
+        MetaBuilder::VoronoiSpawning => VoronoiSpawning::new(),
+        MetaBuilder::RoomBasedSpawner => RoomBasedSpawner::new(),
+        MetaBuilder::DoorPlacement => DoorPlacement::new(),
+        MetaBuilder::CaveDecorator => CaveDecorator::new(),
+        MetaBuilder::CaveTransition => CaveTransition::new(),
+        MetaBuilder::DragonsLair => DragonsLair::new(),
+        MetaBuilder::DragonSpawner => DragonSpawner::new(),
+    }
+}
@@ -18,11 +18,13 @@ impl RoomBasedSpawner {
         if let Some(rooms) = &build_data.rooms {
             for room in rooms.iter().skip(1) {
                 spawner::spawn_room(
+                    &build_data.raws,
                     &build_data.map,
                     rng,
                     room,
                     build_data.map.depth,
                     &mut build_data.spawn_list,
+                    &mut build_data.spawn_leaders,
                 );
             }
         } else {
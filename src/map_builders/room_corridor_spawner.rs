@@ -18,11 +18,13 @@ impl CorridorSpawner {
         if let Some(corridors) = &build_data.corridors {
             for c in corridors.iter() {
                 spawner::spawn_region(
+                    &build_data.raws,
                     &build_data.map,
                     rng,
                     c,
                     build_data.map.depth,
                     &mut build_data.spawn_list,
+                    &mut build_data.spawn_leaders,
                 );
             }
         } else {
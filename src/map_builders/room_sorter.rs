@@ -1,8 +1,11 @@
 use crate::rect::Rect;
+use serde::Deserialize;
 
 use super::{BuilderMap, MetaMapBuilder};
 use rltk::RandomNumberGenerator;
 
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
 pub enum RoomSort {
     Leftmost,
     Rightmost,
@@ -1,17 +1,6 @@
-use super::{BuilderChain, BuilderMap, InitialMapBuilder};
+use super::{BuilderMap, InitialMapBuilder};
 use crate::{components::Position, map::TileType};
-use std::collections::HashSet;
-
-pub fn town_builder(
-    new_depth: i32,
-    _rng: &mut rltk::RandomNumberGenerator,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut builder = BuilderChain::new(new_depth, width, height, "Port Town");
-    builder.start_with(TownBuilder::new());
-    builder
-}
+use std::collections::BTreeSet;
 
 enum BuildingTag {
     Pub,
@@ -152,8 +141,8 @@ impl TownBuilder {
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
         build_data: &mut BuilderMap,
-    ) -> (HashSet<usize>, i32) {
-        let mut available_building_tiles = HashSet::new();
+    ) -> (BTreeSet<usize>, i32) {
+        let mut available_building_tiles = BTreeSet::new();
         // Height of road going through town horizontally
         let wall_gap_y = rng.roll_dice(1, build_data.height - 9) + 5;
         for y in 1..build_data.height - 2 {
@@ -196,7 +185,7 @@ impl TownBuilder {
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
         build_data: &mut BuilderMap,
-        available_building_tiles: &mut HashSet<usize>,
+        available_building_tiles: &mut BTreeSet<usize>,
     ) -> Vec<RoomEdges> {
         let w = build_data.width as usize;
         let mut buildings = Vec::new();
@@ -497,7 +486,7 @@ impl TownBuilder {
         &mut self,
         build_data: &mut BuilderMap,
         rng: &mut rltk::RandomNumberGenerator,
-        available_building_tiles: &mut HashSet<usize>,
+        available_building_tiles: &mut BTreeSet<usize>,
     ) {
         for idx in available_building_tiles.iter() {
             if rng.roll_dice(1, 10) == 1 {
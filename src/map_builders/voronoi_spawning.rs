@@ -1,7 +1,7 @@
 use super::{spawner, BuilderMap, MetaMapBuilder};
 use crate::map::TileType;
 use rltk::RandomNumberGenerator;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// Generates a Voronoi/cellular noise map of a region, and divides it into spawn regions. <https://thebookofshaders.com/12/>
 pub struct VoronoiSpawning {}
@@ -18,7 +18,7 @@ impl VoronoiSpawning {
     }
 
     fn build(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
-        let mut noise_areas: HashMap<i32, Vec<usize>> = HashMap::new();
+        let mut noise_areas: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
         let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
         noise.set_noise_type(rltk::NoiseType::Cellular);
         noise.set_frequency(0.08);
@@ -41,11 +41,13 @@ impl VoronoiSpawning {
         // Spawn the entities
         for area in noise_areas.iter() {
             spawner::spawn_region(
+                &build_data.raws,
                 &build_data.map,
                 rng,
                 area.1,
                 build_data.map.depth,
                 &mut build_data.spawn_list,
+                &mut build_data.spawn_leaders,
             );
         }
     }
@@ -67,8 +67,9 @@ pub fn build_patterns(
     }
 
     if dedupe {
-        let set: HashSet<Vec<TileType>> = patterns.drain(..).collect(); // dedup
-        patterns.extend(set);
+        // Keep the first of each pattern, so their order doesn't depend on hashing
+        let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+        patterns.retain(|pattern| seen.insert(pattern.clone()));
     }
 
     patterns
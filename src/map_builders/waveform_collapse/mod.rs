@@ -2,7 +2,7 @@ mod common;
 mod constraints;
 mod solver;
 
-use crate::{Map, TileType};
+use crate::TileType;
 use rltk::RandomNumberGenerator;
 
 use self::{common::MapChunk, solver::Solver};
@@ -31,12 +31,7 @@ impl WaveformCollapseBuilder {
         let constraints = constraints::patterns_to_constraints(patterns, CHUNK_SIZE);
         self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
 
-        build_data.map = Map::new(
-            build_data.map.depth,
-            build_data.width,
-            build_data.height,
-            &build_data.map.name,
-        );
+        build_data.map = build_data.map.cleared();
         loop {
             let mut solver = Solver::new(constraints.clone(), CHUNK_SIZE, &build_data.map);
             while !solver.iteration(&mut build_data.map, rng) {
@@ -58,12 +53,7 @@ impl WaveformCollapseBuilder {
         chunk_size: i32,
         build_data: &mut BuilderMap,
     ) {
-        build_data.map = Map::new(
-            build_data.map.depth,
-            build_data.width,
-            build_data.height,
-            &build_data.map.name,
-        );
+        build_data.map = build_data.map.cleared();
         let mut counter = 0;
         let mut x = 1;
         let mut y = 1;
@@ -85,12 +75,7 @@ impl WaveformCollapseBuilder {
                 if y + chunk_size > build_data.map.height {
                     // Move to the next page
                     build_data.take_snapshot();
-                    build_data.map = Map::new(
-                        build_data.map.depth,
-                        build_data.width,
-                        build_data.height,
-                        &build_data.map.name,
-                    );
+                    build_data.map = build_data.map.cleared();
 
                     x = 1;
                     y = 1;
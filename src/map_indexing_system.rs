@@ -1,4 +1,4 @@
-use crate::{spatial, Pools, TileSize};
+use crate::{Pools, TileSize};
 
 use super::{BlocksTile, Map, Position};
 use specs::prelude::*;
@@ -7,7 +7,7 @@ pub struct MapIndexingSystem;
 
 impl<'a> System<'a> for MapIndexingSystem {
     type SystemData = (
-        ReadExpect<'a, Map>,
+        WriteExpect<'a, Map>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
         ReadStorage<'a, Pools>,
@@ -16,10 +16,10 @@ impl<'a> System<'a> for MapIndexingSystem {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (map, positions, blockers, pools, entities, sizes) = data;
+        let (mut map, positions, blockers, pools, entities, sizes) = data;
 
-        spatial::clear();
-        spatial::populate_blocked_from_map(&map);
+        map.spatial.clear();
+        map.populate_blocked();
         for (position, entity) in (&positions, &entities).join() {
             let alive = pools
                 .get(entity)
@@ -34,7 +34,7 @@ impl<'a> System<'a> for MapIndexingSystem {
                         for x in position.x..position.x + size.x {
                             if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
                                 let idx = map.xy_idx(x, y);
-                                spatial::index_entity(entity, idx, blocks_tile);
+                                map.spatial.index_entity(entity, idx, blocks_tile);
                             }
                         }
                     }
@@ -42,7 +42,7 @@ impl<'a> System<'a> for MapIndexingSystem {
                 }
                 // Single tile
                 let idx = map.xy_idx(position.x, position.y);
-                spatial::index_entity(entity, idx, blocks_tile);
+                map.spatial.index_entity(entity, idx, blocks_tile);
             }
         }
     }
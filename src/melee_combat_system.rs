@@ -1,8 +1,10 @@
 use crate::{
-    effects::{add_effect, EffectType, Targets},
-    gamelog::Logger,
+    dice::DiceExpr,
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, Logger},
     gamesystem::skill_bonus,
-    Attributes, EquipmentSlot, NaturalAttackDefense, Pools, Skill, Skills, WeaponAttribute,
+    Attributes, EquipmentSlot, ItemSetBonuses, NaturalAttackDefense, Pools, Skill, Skills,
+    WeaponAttribute,
 };
 
 use super::{Equipped, HungerClock, HungerState, Name, WantsToMelee, Weapon, Wearable};
@@ -25,6 +27,9 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, Pools>,
         ReadStorage<'a, NaturalAttackDefense>,
         WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, EffectQueue>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, ItemSetBonuses>,
     );
     fn run(&mut self, data: Self::SystemData) {
         let (
@@ -40,6 +45,9 @@ impl<'a> System<'a> for MeleeCombatSystem {
             pools,
             naturals,
             mut rng,
+            mut effects,
+            mut gamelog,
+            set_bonuses,
         ) = data;
 
         for (entity, wants_melee, name, attacker_attributes, attacker_skills, attacker_pools) in (
@@ -66,9 +74,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
             // TODO(aalhendi): Refactor into MeleeWeapon::default()?
             let mut weapon_info = Weapon {
                 attribute: WeaponAttribute::Might,
-                damage_n_dice: 1,
-                damage_die_type: 4,
-                damage_bonus: 0,
+                damage: DiceExpr::dice(1, 4),
                 hit_bonus: 0,
                 proc_chance: None,
                 proc_target: None,
@@ -83,9 +89,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     rng.roll_dice(1, nat.attacks.len() as i32) as usize - 1
                 };
                 weapon_info.hit_bonus = nat.attacks[attack_index].hit_bonus;
-                weapon_info.damage_n_dice = nat.attacks[attack_index].damage_n_dice;
-                weapon_info.damage_die_type = nat.attacks[attack_index].damage_die_type;
-                weapon_info.damage_bonus = nat.attacks[attack_index].damage_bonus;
+                weapon_info.damage = nat.attacks[attack_index].damage.clone();
             }
 
             // If melee weapon, update its data
@@ -102,7 +106,10 @@ impl<'a> System<'a> for MeleeCombatSystem {
                 WeaponAttribute::Might => attacker_attributes.might.bonus,
                 WeaponAttribute::Quickness => attacker_attributes.quickness.bonus,
             };
-            let skill_hit_bonus = skill_bonus(Skill::Melee, attacker_skills);
+            let attacker_set_skill = set_bonuses
+                .get(entity)
+                .map_or(0, |bonuses| bonuses.skill(Skill::Melee));
+            let skill_hit_bonus = skill_bonus(Skill::Melee, attacker_skills) + attacker_set_skill;
             let weapon_hit_bonus = weapon_info.hit_bonus;
             let mut status_hit_bonus = 0;
             if let Some(hc) = hunger_clock.get(entity) {
@@ -131,8 +138,11 @@ impl<'a> System<'a> for MeleeCombatSystem {
                 None => 10,
             };
             let armor_quickness_bonus = target_attributes.quickness.bonus;
-            let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills);
-            let armor_item_bonus = armor_item_bonus as i32;
+            let target_set_bonuses = set_bonuses.get(wants_melee.target);
+            let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills)
+                + target_set_bonuses.map_or(0, |bonuses| bonuses.skill(Skill::Defense));
+            let armor_item_bonus = armor_item_bonus as i32
+                + target_set_bonuses.map_or(0, |bonuses| bonuses.armor_class);
             let armor_class =
                 base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
 
@@ -144,8 +154,8 @@ impl<'a> System<'a> for MeleeCombatSystem {
                         .white("considers attacking")
                         .cyan(&target_name.name)
                         .white("but misjudges the timing!")
-                        .log();
-                    add_effect(
+                        .log(&mut gamelog);
+                    effects.add_effect(
                         None,
                         EffectType::Particle {
                             glyph: rltk::to_cp437('‼'),
@@ -161,21 +171,16 @@ impl<'a> System<'a> for MeleeCombatSystem {
 
                 // Target hit!
                 _ if natural_roll == 20 || modified_hit_roll > armor_class => {
-                    let base_damage =
-                        rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
+                    let base_damage = weapon_info.damage.roll(&mut rng);
                     let attr_damage_bonus = attacker_attributes.might.bonus;
-                    let skill_damage_bonus = skill_bonus(Skill::Melee, attacker_skills);
-                    let weapon_damage_bonus = weapon_info.damage_bonus;
+                    let skill_damage_bonus =
+                        skill_bonus(Skill::Melee, attacker_skills) + attacker_set_skill;
 
                     let damage = i32::max(
                         0,
-                        base_damage
-                            + attr_damage_bonus
-                            + skill_hit_bonus
-                            + skill_damage_bonus
-                            + weapon_damage_bonus,
+                        base_damage + attr_damage_bonus + skill_hit_bonus + skill_damage_bonus,
                     );
-                    add_effect(
+                    effects.add_effect(
                         Some(entity),
                         EffectType::Damage { amount: damage },
                         Targets::Single {
@@ -189,7 +194,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                         .white("for")
                         .red(format!("{}", damage))
                         .white("hp.")
-                        .log();
+                        .log(&mut gamelog);
 
                     // Proc effects
                     if weapon_info
@@ -203,7 +208,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                                 target: wants_melee.target,
                             }
                         };
-                        add_effect(
+                        effects.add_effect(
                             Some(entity),
                             EffectType::ItemUse {
                                 item: weapon_entity.unwrap(),
@@ -220,8 +225,8 @@ impl<'a> System<'a> for MeleeCombatSystem {
                         .white("attacks")
                         .cyan(&target_name.name)
                         .white("but can't connect.")
-                        .log();
-                    add_effect(
+                        .log(&mut gamelog);
+                    effects.add_effect(
                         None,
                         EffectType::Particle {
                             glyph: rltk::to_cp437('‼'),
@@ -1,15 +1,15 @@
 use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
 use crate::{
-    spatial, ApplyMove, ApplyTeleport, BlocksTile, EntityMoved, Map, OtherLevelPosition, Position,
-    RunState, Viewshed,
+    ApplyMove, ApplyTeleport, BlocksTile, EntityMoved, Map, OtherLevelPosition, Position, RunState,
+    Viewshed,
 };
 
 pub struct MovementSystem;
 
 impl<'a> System<'a> for MovementSystem {
     type SystemData = (
-        ReadExpect<'a, Map>,
+        WriteExpect<'a, Map>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
         Entities<'a>,
@@ -24,7 +24,7 @@ impl<'a> System<'a> for MovementSystem {
 
     fn run(&mut self, data: Self::SystemData) {
         let (
-            map,
+            mut map,
             mut position,
             _blockers,
             entities,
@@ -53,7 +53,7 @@ impl<'a> System<'a> for MovementSystem {
                 };
             } else if let Some(pos) = position.get(entity) {
                 let idx = map.xy_idx(pos.x, pos.y);
-                spatial::remove_entity(entity, idx);
+                map.spatial.remove_entity(entity, idx);
                 other_level
                     .insert(
                         entity,
@@ -72,7 +72,8 @@ impl<'a> System<'a> for MovementSystem {
         // Apply broad movement
         for (entity, movement, pos) in (&entities, &apply_move, &mut position).join() {
             let start_idx = map.xy_idx(pos.x, pos.y);
-            spatial::move_entity(entity, start_idx, movement.dest_idx);
+            map.spatial
+                .move_entity(entity, start_idx, movement.dest_idx);
             let (x, y) = map.idx_xy(movement.dest_idx);
             pos.x = x;
             pos.y = y;
@@ -1,7 +1,7 @@
-use super::{ParticleLifetime, Position, Renderable, Rltk};
+use super::{ParticleLifetime, Position, Renderable};
 use specs::prelude::*;
 
-pub fn update_particles(ecs: &mut World, ctx: &Rltk) {
+pub fn update_particles(ecs: &mut World, frame_time_ms: f32) {
     let mut dead_particles = Vec::new();
     {
         // Age out particles
@@ -9,7 +9,7 @@ pub fn update_particles(ecs: &mut World, ctx: &Rltk) {
         let entities = ecs.entities();
         for (entity, particle) in (&entities, &mut particles).join() {
             if let Some(animation) = &mut particle.animation {
-                animation.timer += ctx.frame_time_ms;
+                animation.timer += frame_time_ms;
                 if animation.timer > animation.step_time
                     && animation.current_step < animation.path.len() - 2
                 {
@@ -22,7 +22,7 @@ pub fn update_particles(ecs: &mut World, ctx: &Rltk) {
                 }
             }
 
-            particle.lifetime_ms -= ctx.frame_time_ms;
+            particle.lifetime_ms -= frame_time_ms;
             if particle.lifetime_ms < 0.0 {
                 dead_particles.push(entity);
             }
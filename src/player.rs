@@ -3,16 +3,20 @@ use super::{
     Position, Renderable, RunState, State, Viewshed, WantsToPickupItem,
 };
 use crate::components::WantsToMelee;
-use crate::gamelog::Logger;
+use crate::gamelog::{GameEvents, GameLog, Logger};
+use crate::map::dungeon::MasterDungeonMap;
 use crate::map::TileType;
 use crate::raws::faction_structs::Reaction;
+use crate::raws::get_raws;
 use crate::raws::rawsmaster::faction_reaction;
 use crate::raws::rawsmaster::find_spell_entity;
-use crate::raws::RAWS;
-use crate::spatial;
+use crate::raws::rawsmaster::{spawn_named_item, SpawnType};
+use crate::replay;
 use crate::Consumable;
+use crate::CursedItem;
 use crate::Equipped;
 use crate::Faction;
+use crate::IdentifiedItem;
 use crate::InBackpack;
 use crate::KnownSpells;
 use crate::Name;
@@ -22,6 +26,8 @@ use crate::Target;
 use crate::Vendor;
 use crate::VendorMode;
 use crate::WantsToCastSpell;
+use crate::WantsToDropItem;
+use crate::WantsToRemoveItem;
 use crate::WantsToShoot;
 use crate::WantsToUseItem;
 use crate::Weapon;
@@ -34,7 +40,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     let mut players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
     let pools = ecs.read_storage::<Pools>();
-    let map = ecs.fetch::<Map>();
+    let mut map = ecs.fetch_mut::<Map>();
     let entities = ecs.entities();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
@@ -46,6 +52,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     let mut swap_entities = Vec::new();
     let mut result = RunState::AwaitingInput;
     let vendors = ecs.read_storage::<Vendor>();
+    let raws = get_raws(ecs);
 
     for (_player, pos, viewshed, entity) in
         (&mut players, &mut positions, &mut viewsheds, &entities).join()
@@ -60,66 +67,68 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
         }
         let dest_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
-        result = spatial::for_each_tile_content_with_gamemode(dest_idx, |potential_target| {
-            // TODO(aalhendi): this returns early and so vendors cannot be hostile
-            if vendors.get(potential_target).is_some() {
-                return Some(RunState::ShowVendor {
-                    vendor: potential_target,
-                    mode: VendorMode::Sell,
-                });
-            }
+        result = map
+            .spatial
+            .for_each_tile_content_with_gamemode(dest_idx, |potential_target| {
+                // TODO(aalhendi): this returns early and so vendors cannot be hostile
+                if vendors.get(potential_target).is_some() {
+                    return Some(RunState::ShowVendor {
+                        vendor: potential_target,
+                        mode: VendorMode::Sell,
+                    });
+                }
 
-            let is_hostile = if pools.get(potential_target).is_some() {
-                if let Some(faction) = factions.get(potential_target) {
-                    let reaction = faction_reaction(&faction.name, "Player", &RAWS.lock().unwrap());
-                    reaction == Reaction::Attack
+                let is_hostile = if pools.get(potential_target).is_some() {
+                    if let Some(faction) = factions.get(potential_target) {
+                        let reaction = faction_reaction(&faction.name, "Player", &raws);
+                        reaction == Reaction::Attack
+                    } else {
+                        true
+                    }
                 } else {
                     true
-                }
-            } else {
-                true
-            };
+                };
 
-            if !is_hostile {
-                // Note that we want to move the bystander
-                swap_entities.push((potential_target, pos.x, pos.y));
-
-                // Move the player
-                pos.x = (pos.x + delta_x).clamp(0, map.width - 1);
-                pos.y = (pos.y + delta_y).clamp(0, map.height - 1);
-                entity_moved
-                    .insert(entity, EntityMoved {})
-                    .expect("Unable to insert marker");
-
-                viewshed.dirty = true;
-                ppos.x = pos.x;
-                ppos.y = pos.y;
-                return Some(RunState::Ticking);
-            } else if let Some(_tgt) = pools.get(potential_target) {
-                wants_to_melee
-                    .insert(
-                        entity,
-                        WantsToMelee {
-                            target: potential_target,
-                        },
-                    )
-                    .expect("Add target failed");
-                return Some(RunState::Ticking); // So we don't move after attacking
-            }
+                if !is_hostile {
+                    // Note that we want to move the bystander
+                    swap_entities.push((potential_target, pos.x, pos.y));
+
+                    // Move the player
+                    pos.x = (pos.x + delta_x).clamp(0, map.width - 1);
+                    pos.y = (pos.y + delta_y).clamp(0, map.height - 1);
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+
+                    viewshed.dirty = true;
+                    ppos.x = pos.x;
+                    ppos.y = pos.y;
+                    return Some(RunState::Ticking);
+                } else if let Some(_tgt) = pools.get(potential_target) {
+                    wants_to_melee
+                        .insert(
+                            entity,
+                            WantsToMelee {
+                                target: potential_target,
+                            },
+                        )
+                        .expect("Add target failed");
+                    return Some(RunState::Ticking); // So we don't move after attacking
+                }
 
-            if let Some(door) = doors.get_mut(potential_target) {
-                door.open = true;
-                blocks_visibility.remove(potential_target);
-                blocks_movement.remove(potential_target);
-                let door_renderable = renderables.get_mut(potential_target).unwrap();
-                door_renderable.glyph = rltk::to_cp437('/');
-                viewshed.dirty = true;
-                return Some(RunState::Ticking);
-            }
-            None
-        });
+                if let Some(door) = doors.get_mut(potential_target) {
+                    door.open = true;
+                    blocks_visibility.remove(potential_target);
+                    blocks_movement.remove(potential_target);
+                    let door_renderable = renderables.get_mut(potential_target).unwrap();
+                    door_renderable.glyph = rltk::to_cp437('/');
+                    viewshed.dirty = true;
+                    return Some(RunState::Ticking);
+                }
+                None
+            });
 
-        if !spatial::is_blocked(dest_idx) {
+        if !map.spatial.is_blocked(dest_idx) {
             pos.x = (pos.x + delta_x).clamp(0, map.width - 1);
             pos.y = (pos.y + delta_y).clamp(0, map.height - 1);
             entity_moved
@@ -144,116 +153,286 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
             let new_idx = map.xy_idx(swappable_pos_x, swappable_pos_y);
             e_pos.x = swappable_pos_x;
             e_pos.y = swappable_pos_y;
-            spatial::move_entity(swappable_entity, old_idx, new_idx);
+            map.spatial.move_entity(swappable_entity, old_idx, new_idx);
             result = RunState::Ticking;
         }
     }
     result
 }
 
+/// A single player action, decoupled from the key or menu that produced it. Used by the
+/// keyboard handler, the menus, the headless driver and replays.
+#[derive(PartialEq, Clone, Debug)]
+pub enum PlayerCommand {
+    Move {
+        dx: i32,
+        dy: i32,
+    },
+    SkipTurn,
+    CycleTarget,
+    Fire,
+    PickUp,
+    ConsumableHotkey {
+        slot: usize,
+    },
+    SpellHotkey {
+        slot: usize,
+    },
+    UseItem {
+        item: Entity,
+        target: Option<Point>,
+    },
+    CastSpell {
+        spell: Entity,
+        target: Option<Point>,
+    },
+    DropItem {
+        item: Entity,
+    },
+    RemoveItem {
+        item: Entity,
+    },
+    SellItem {
+        item: Entity,
+    },
+    BuyItem {
+        tag: String,
+        price: f32,
+    },
+    IdentifyItem {
+        item: Entity,
+    },
+    RemoveCurse {
+        item: Entity,
+    },
+    DescendStairs,
+    AscendStairs,
+    ShowInventory,
+    ShowDropItem,
+    ShowRemoveItem,
+}
+
+impl PlayerCommand {
+    /// Whether the command is one of the player's turns, rather than a choice made in a menu
+    /// the turn opened.
+    fn takes_turn(&self) -> bool {
+        !matches!(
+            self,
+            PlayerCommand::UseItem { .. }
+                | PlayerCommand::CastSpell { .. }
+                | PlayerCommand::DropItem { .. }
+                | PlayerCommand::RemoveItem { .. }
+                | PlayerCommand::SellItem { .. }
+                | PlayerCommand::BuyItem { .. }
+                | PlayerCommand::IdentifyItem { .. }
+                | PlayerCommand::RemoveCurse { .. }
+        )
+    }
+}
+
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
     if let Some(key) = ctx.key {
         use VirtualKeyCode::*;
         // Hotkeys (Shift held down)
         if ctx.shift {
-            let key_val = match key {
-                Key1 => Some(1),
-                Key2 => Some(2),
-                Key3 => Some(3),
-                Key4 => Some(4),
-                Key5 => Some(5),
-                Key6 => Some(6),
-                Key7 => Some(7),
-                Key8 => Some(8),
-                Key9 => Some(9),
-                _ => None,
-            };
-            if let Some(key_val) = key_val {
-                return use_consumable_hotkey(gs, key_val - 1);
+            if let Some(key_val) = number_key(key) {
+                return perform_command(
+                    &mut gs.ecs,
+                    PlayerCommand::ConsumableHotkey { slot: key_val - 1 },
+                );
             }
         }
 
         if ctx.control {
-            let key_val = match key {
-                Key1 => Some(1),
-                Key2 => Some(2),
-                Key3 => Some(3),
-                Key4 => Some(4),
-                Key5 => Some(5),
-                Key6 => Some(6),
-                Key7 => Some(7),
-                Key8 => Some(8),
-                Key9 => Some(9),
-                _ => None,
-            };
-            if let Some(key_val) = key_val {
-                return use_spell_hotkey(gs, key_val - 1);
+            if let Some(key_val) = number_key(key) {
+                return perform_command(
+                    &mut gs.ecs,
+                    PlayerCommand::SpellHotkey { slot: key_val - 1 },
+                );
             }
         }
 
-        match key {
+        let command = match key {
             // Skip turn
-            Space | Numpad5 => skip_turn(&mut gs.ecs),
+            Space | Numpad5 => PlayerCommand::SkipTurn,
 
             // Cardinal
-            Left | Numpad4 | H => try_move_player(-1, 0, &mut gs.ecs),
-            Right | Numpad6 | L => try_move_player(1, 0, &mut gs.ecs),
-            Up | Numpad8 | K => try_move_player(0, -1, &mut gs.ecs),
-            Down | Numpad2 | J => try_move_player(0, 1, &mut gs.ecs),
+            Left | Numpad4 | H => PlayerCommand::Move { dx: -1, dy: 0 },
+            Right | Numpad6 | L => PlayerCommand::Move { dx: 1, dy: 0 },
+            Up | Numpad8 | K => PlayerCommand::Move { dx: 0, dy: -1 },
+            Down | Numpad2 | J => PlayerCommand::Move { dx: 0, dy: 1 },
 
             //Diagonal
-            Numpad7 | Y => try_move_player(-1, -1, &mut gs.ecs),
-            Numpad3 | N => try_move_player(1, 1, &mut gs.ecs),
-            Numpad1 | B => try_move_player(-1, 1, &mut gs.ecs),
-            Numpad9 | U => try_move_player(1, -1, &mut gs.ecs),
+            Numpad7 | Y => PlayerCommand::Move { dx: -1, dy: -1 },
+            Numpad3 | N => PlayerCommand::Move { dx: 1, dy: 1 },
+            Numpad1 | B => PlayerCommand::Move { dx: -1, dy: 1 },
+            Numpad9 | U => PlayerCommand::Move { dx: 1, dy: -1 },
 
             // Ranged
-            V => {
-                cycle_target(&mut gs.ecs);
-                RunState::AwaitingInput
-            }
-            F => fire_on_target(&mut gs.ecs),
+            V => PlayerCommand::CycleTarget,
+            F => PlayerCommand::Fire,
 
             // Item
-            G => {
-                get_item(&mut gs.ecs);
-                RunState::Ticking
-            }
-            I => RunState::ShowInventory,
-            D => RunState::ShowDropItem,
-            R => RunState::ShowRemoveItem,
+            G => PlayerCommand::PickUp,
+            I => PlayerCommand::ShowInventory,
+            D => PlayerCommand::ShowDropItem,
+            R => PlayerCommand::ShowRemoveItem,
 
             // Main Menu
-            Escape => RunState::SaveGame,
+            Escape => return RunState::SaveGame,
             // Cheating!
-            Backslash => RunState::ShowCheatMenu,
+            Backslash => return RunState::ShowCheatMenu,
             // Stairs
-            Period => {
-                if try_next_level(&mut gs.ecs) {
-                    RunState::NextLevel
-                } else {
-                    RunState::Ticking
-                }
+            Period => PlayerCommand::DescendStairs,
+            Comma => PlayerCommand::AscendStairs,
+            _ => return RunState::AwaitingInput,
+        };
+        perform_command(&mut gs.ecs, command)
+    } else {
+        RunState::AwaitingInput
+    }
+}
+
+fn number_key(key: VirtualKeyCode) -> Option<usize> {
+    use VirtualKeyCode::*;
+    match key {
+        Key1 => Some(1),
+        Key2 => Some(2),
+        Key3 => Some(3),
+        Key4 => Some(4),
+        Key5 => Some(5),
+        Key6 => Some(6),
+        Key7 => Some(7),
+        Key8 => Some(8),
+        Key9 => Some(9),
+        _ => None,
+    }
+}
+
+/// Applies a player command to the world and returns the state the game should move to.
+/// Every command is recorded for replays. Turns are counted as the keyboard handler always has:
+/// when a turn's command ends, or opens an item menu, but not when it asks for a target.
+pub fn perform_command(ecs: &mut World, command: PlayerCommand) -> RunState {
+    replay::record_command(ecs, &command);
+    let takes_turn = command.takes_turn();
+    let newrunstate = apply_command(ecs, command);
+    if takes_turn
+        && newrunstate != RunState::AwaitingInput
+        && !matches!(newrunstate, RunState::ShowTargeting { .. })
+    {
+        ecs.fetch_mut::<GameEvents>().record_event("Turn", 1);
+    }
+    newrunstate
+}
+
+fn apply_command(ecs: &mut World, command: PlayerCommand) -> RunState {
+    match command {
+        PlayerCommand::Move { dx, dy } => try_move_player(dx, dy, ecs),
+        PlayerCommand::SkipTurn => skip_turn(ecs),
+        PlayerCommand::CycleTarget => {
+            cycle_target(ecs);
+            RunState::AwaitingInput
+        }
+        PlayerCommand::Fire => fire_on_target(ecs),
+        PlayerCommand::PickUp => {
+            get_item(ecs);
+            RunState::Ticking
+        }
+        PlayerCommand::ConsumableHotkey { slot } => use_consumable_hotkey(ecs, slot),
+        PlayerCommand::SpellHotkey { slot } => use_spell_hotkey(ecs, slot),
+        PlayerCommand::UseItem { item, target } => {
+            let player_entity = *ecs.fetch::<Entity>();
+            ecs.write_storage::<WantsToUseItem>()
+                .insert(player_entity, WantsToUseItem { item, target })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::CastSpell { spell, target } => {
+            let player_entity = *ecs.fetch::<Entity>();
+            ecs.write_storage::<WantsToCastSpell>()
+                .insert(player_entity, WantsToCastSpell { spell, target })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::DropItem { item } => {
+            let player_entity = *ecs.fetch::<Entity>();
+            ecs.write_storage::<WantsToDropItem>()
+                .insert(player_entity, WantsToDropItem { item })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::RemoveItem { item } => {
+            let player_entity = *ecs.fetch::<Entity>();
+            ecs.write_storage::<WantsToRemoveItem>()
+                .insert(player_entity, WantsToRemoveItem { item })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::SellItem { item } => {
+            let price = ecs.read_storage::<Item>().get(item).unwrap().base_value * 0.8;
+            let player_entity = *ecs.fetch::<Entity>();
+            ecs.write_storage::<Pools>()
+                .get_mut(player_entity)
+                .unwrap()
+                .gold += price;
+            ecs.delete_entity(item).expect("Unable to delete");
+            RunState::AwaitingInput
+        }
+        PlayerCommand::BuyItem { tag, price } => {
+            let player_entity = *ecs.fetch::<Entity>();
+            ecs.write_storage::<IdentifiedItem>()
+                .insert(player_entity, IdentifiedItem { name: tag.clone() })
+                .expect("Unable to insert");
+            let mut pools = ecs.write_storage::<Pools>();
+            let player_pools = pools.get_mut(player_entity).unwrap();
+            if player_pools.gold >= price {
+                player_pools.gold -= price;
+                std::mem::drop(pools);
+                spawn_named_item(
+                    &get_raws(ecs),
+                    ecs,
+                    &tag,
+                    SpawnType::Carried { by: player_entity },
+                );
             }
-            Comma => {
-                if try_previous_level(&mut gs.ecs) {
-                    RunState::PreviousLevel
-                } else {
-                    RunState::Ticking
-                }
+            RunState::AwaitingInput
+        }
+        PlayerCommand::IdentifyItem { item } => {
+            if let Some(name) = ecs.read_storage::<Name>().get(item) {
+                let mut dm = ecs.fetch_mut::<MasterDungeonMap>();
+                dm.identified_items.insert(name.name.clone());
             }
-            _ => RunState::AwaitingInput,
+            RunState::Ticking
         }
-    } else {
-        RunState::AwaitingInput
+        PlayerCommand::RemoveCurse { item } => {
+            ecs.write_storage::<CursedItem>().remove(item);
+            RunState::Ticking
+        }
+        PlayerCommand::DescendStairs => {
+            if try_next_level(ecs) {
+                RunState::NextLevel
+            } else {
+                RunState::Ticking
+            }
+        }
+        PlayerCommand::AscendStairs => {
+            if try_previous_level(ecs) {
+                RunState::PreviousLevel
+            } else {
+                RunState::Ticking
+            }
+        }
+        PlayerCommand::ShowInventory => RunState::ShowInventory,
+        PlayerCommand::ShowDropItem => RunState::ShowDropItem,
+        PlayerCommand::ShowRemoveItem => RunState::ShowRemoveItem,
     }
 }
 
-fn use_consumable_hotkey(gs: &mut State, key: usize) -> RunState {
-    let consumables = gs.ecs.read_storage::<Consumable>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let entities = gs.ecs.entities();
+fn use_consumable_hotkey(ecs: &mut World, key: usize) -> RunState {
+    let consumables = ecs.read_storage::<Consumable>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
 
     let mut carried_consumables = Vec::new();
     for (entity, carried_by, _consumable) in (&entities, &backpack, &consumables).join() {
@@ -263,17 +442,13 @@ fn use_consumable_hotkey(gs: &mut State, key: usize) -> RunState {
     }
 
     if key < carried_consumables.len() {
-        if let Some(ranged) = gs
-            .ecs
-            .read_storage::<Ranged>()
-            .get(carried_consumables[key])
-        {
+        if let Some(ranged) = ecs.read_storage::<Ranged>().get(carried_consumables[key]) {
             return RunState::ShowTargeting {
                 range: ranged.range,
                 item: carried_consumables[key],
             };
         }
-        let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
+        let mut intent = ecs.write_storage::<WantsToUseItem>();
         intent
             .insert(
                 *player_entity,
@@ -288,24 +463,23 @@ fn use_consumable_hotkey(gs: &mut State, key: usize) -> RunState {
     RunState::Ticking
 }
 
-fn use_spell_hotkey(gs: &mut State, key: usize) -> RunState {
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let known_spells_storage = gs.ecs.read_storage::<KnownSpells>();
+fn use_spell_hotkey(ecs: &mut World, key: usize) -> RunState {
+    let player_entity = ecs.fetch::<Entity>();
+    let known_spells_storage = ecs.read_storage::<KnownSpells>();
     let known_spells = &known_spells_storage.get(*player_entity).unwrap().spells;
 
     if key < known_spells.len() {
-        let pools = gs.ecs.read_storage::<Pools>();
+        let pools = ecs.read_storage::<Pools>();
         let player_pools = pools.get(*player_entity).unwrap();
         if player_pools.mana.current >= known_spells[key].mana_cost {
-            if let Some(spell_entity) = find_spell_entity(&gs.ecs, &known_spells[key].display_name)
-            {
-                if let Some(ranged) = gs.ecs.read_storage::<Ranged>().get(spell_entity) {
+            if let Some(spell_entity) = find_spell_entity(ecs, &known_spells[key].display_name) {
+                if let Some(ranged) = ecs.read_storage::<Ranged>().get(spell_entity) {
                     return RunState::ShowTargeting {
                         range: ranged.range,
                         item: spell_entity,
                     };
                 };
-                let mut intent = gs.ecs.write_storage::<WantsToCastSpell>();
+                let mut intent = ecs.write_storage::<WantsToCastSpell>();
                 intent
                     .insert(
                         *player_entity,
@@ -320,7 +494,7 @@ fn use_spell_hotkey(gs: &mut State, key: usize) -> RunState {
         } else {
             Logger::new()
                 .white("You don't have enough mana to cast that!")
-                .log()
+                .log(&mut ecs.fetch_mut::<GameLog>())
         }
     }
 
@@ -346,7 +520,7 @@ fn get_item(ecs: &mut World) {
     match target_item {
         None => Logger::new()
             .white("There is nothing here to pick up.")
-            .log(),
+            .log(&mut ecs.fetch_mut::<GameLog>()),
         Some(item) => {
             let mut pickup = ecs.write_storage::<WantsToPickupItem>();
             pickup
@@ -368,7 +542,9 @@ fn try_next_level(ecs: &mut World) -> bool {
     let player_idx = map.xy_idx(p_pos.x, p_pos.y);
     let is_down_stairs = map.tiles[player_idx] == TileType::DownStairs;
     if !is_down_stairs {
-        Logger::new().white("There is no way down from here.").log()
+        Logger::new()
+            .white("There is no way down from here.")
+            .log(&mut ecs.fetch_mut::<GameLog>())
     }
     is_down_stairs
 }
@@ -379,7 +555,9 @@ fn try_previous_level(ecs: &mut World) -> bool {
     let player_idx = map.xy_idx(player_pos.x, player_pos.y);
     let is_up_stairs = map.tiles[player_idx] == TileType::UpStairs;
     if !is_up_stairs {
-        Logger::new().white("There is no way up from here.").log()
+        Logger::new()
+            .white("There is no way up from here.")
+            .log(&mut ecs.fetch_mut::<GameLog>())
     }
     is_up_stairs
 }
@@ -389,15 +567,16 @@ fn skip_turn(ecs: &mut World) -> RunState {
     let viewsheds = ecs.read_storage::<Viewshed>();
     let factions = ecs.read_storage::<Faction>();
     let map = ecs.fetch::<Map>();
+    let raws = get_raws(ecs);
     let mut can_heal = true;
 
     // Check that no monsters in player viewshed
     let viewshed = viewsheds.get(*player_entity).unwrap();
     for tile in viewshed.visible_tiles.iter() {
         let idx = map.xy_idx(tile.x, tile.y);
-        spatial::for_each_tile_content(idx, |entity| {
+        map.spatial.for_each_tile_content(idx, |entity| {
             if let Some(f) = factions.get(entity) {
-                let reaction = faction_reaction(&f.name, "Player", &RAWS.lock().unwrap());
+                let reaction = faction_reaction(&f.name, "Player", &raws);
                 if reaction == Reaction::Attack {
                     can_heal = false;
                 }
@@ -458,12 +637,14 @@ fn get_player_target_list(ecs: &mut World) -> Vec<(f32, Entity)> {
             let distance_to_target = rltk::DistanceAlg::Pythagoras
                 .distance2d(*tile_point, rltk::Point::new(ppos.x, ppos.y));
             if distance_to_target < range as f32 {
-                spatial::for_each_tile_content(tile_idx, |possible_target| {
-                    if possible_target != *player_entity && factions.get(possible_target).is_some()
-                    {
-                        possible_targets.push((distance_to_target, possible_target));
-                    }
-                });
+                map.spatial
+                    .for_each_tile_content(tile_idx, |possible_target| {
+                        if possible_target != *player_entity
+                            && factions.get(possible_target).is_some()
+                        {
+                            possible_targets.push((distance_to_target, possible_target));
+                        }
+                    });
             }
         }
     }
@@ -540,7 +721,10 @@ fn fire_on_target(ecs: &mut World) -> RunState {
         let mut shoot_store = ecs.write_storage::<WantsToShoot>();
         let names = ecs.read_storage::<Name>();
         if let Some(name) = names.get(target) {
-            Logger::new().white("You fire at").cyan(&name.name).log();
+            Logger::new()
+                .white("You fire at")
+                .cyan(&name.name)
+                .log(&mut ecs.fetch_mut::<GameLog>());
         }
         shoot_store
             .insert(*player_entity, WantsToShoot { target })
@@ -550,7 +734,7 @@ fn fire_on_target(ecs: &mut World) -> RunState {
     } else {
         Logger::new()
             .white("You don't have a target selected!")
-            .log();
+            .log(&mut ecs.fetch_mut::<GameLog>());
         RunState::AwaitingInput
     }
 }
@@ -1,9 +1,10 @@
 use crate::{
-    effects::{add_effect, EffectType, Targets},
-    gamelog::Logger,
+    dice::DiceExpr,
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, Logger},
     gamesystem::skill_bonus,
-    Attributes, EquipmentSlot, Map, NaturalAttackDefense, Pools, Position, Skill, Skills,
-    WantsToShoot, WeaponAttribute,
+    Attributes, EquipmentSlot, ItemSetBonuses, Map, NaturalAttackDefense, Pools, Position, Skill,
+    Skills, WantsToShoot, WeaponAttribute,
 };
 
 use super::{Equipped, HungerClock, HungerState, Name, Weapon, Wearable};
@@ -30,6 +31,9 @@ impl<'a> System<'a> for RangedCombatSystem {
         WriteExpect<'a, RandomNumberGenerator>,
         ReadStorage<'a, Position>,
         ReadExpect<'a, Map>,
+        WriteExpect<'a, EffectQueue>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, ItemSetBonuses>,
     );
     fn run(&mut self, data: Self::SystemData) {
         let (
@@ -47,6 +51,9 @@ impl<'a> System<'a> for RangedCombatSystem {
             mut rng,
             positions,
             map,
+            mut effects,
+            mut gamelog,
+            set_bonuses,
         ) = data;
 
         for (entity, wants_shoot, name, attacker_attributes, attacker_skills, attacker_pools) in (
@@ -72,7 +79,7 @@ impl<'a> System<'a> for RangedCombatSystem {
             // Fire projectile effect
             let apos = positions.get(entity).unwrap();
             let dpos = positions.get(wants_shoot.target).unwrap();
-            add_effect(
+            effects.add_effect(
                 None,
                 EffectType::ParticleProjectile {
                     glyph: to_cp437('*'),
@@ -95,9 +102,7 @@ impl<'a> System<'a> for RangedCombatSystem {
             // TODO(aalhendi): Refactor into MeleeWeapon::default()?
             let mut weapon_info = Weapon {
                 attribute: WeaponAttribute::Might,
-                damage_n_dice: 1,
-                damage_die_type: 4,
-                damage_bonus: 0,
+                damage: DiceExpr::dice(1, 4),
                 hit_bonus: 0,
                 proc_chance: None,
                 proc_target: None,
@@ -112,9 +117,7 @@ impl<'a> System<'a> for RangedCombatSystem {
                     rng.roll_dice(1, nat.attacks.len() as i32) as usize - 1
                 };
                 weapon_info.hit_bonus = nat.attacks[attack_index].hit_bonus;
-                weapon_info.damage_n_dice = nat.attacks[attack_index].damage_n_dice;
-                weapon_info.damage_die_type = nat.attacks[attack_index].damage_die_type;
-                weapon_info.damage_bonus = nat.attacks[attack_index].damage_bonus;
+                weapon_info.damage = nat.attacks[attack_index].damage.clone();
             }
 
             // If melee weapon, update its data
@@ -131,7 +134,10 @@ impl<'a> System<'a> for RangedCombatSystem {
                 WeaponAttribute::Might => attacker_attributes.might.bonus,
                 WeaponAttribute::Quickness => attacker_attributes.quickness.bonus,
             };
-            let skill_hit_bonus = skill_bonus(Skill::Melee, attacker_skills);
+            let attacker_set_skill = set_bonuses
+                .get(entity)
+                .map_or(0, |bonuses| bonuses.skill(Skill::Melee));
+            let skill_hit_bonus = skill_bonus(Skill::Melee, attacker_skills) + attacker_set_skill;
             let weapon_hit_bonus = weapon_info.hit_bonus;
             let mut status_hit_bonus = 0;
             if let Some(hc) = hunger_clock.get(entity) {
@@ -160,8 +166,11 @@ impl<'a> System<'a> for RangedCombatSystem {
                 None => 10,
             };
             let armor_quickness_bonus = target_attributes.quickness.bonus;
-            let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills);
-            let armor_item_bonus = armor_item_bonus as i32;
+            let target_set_bonuses = set_bonuses.get(wants_shoot.target);
+            let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills)
+                + target_set_bonuses.map_or(0, |bonuses| bonuses.skill(Skill::Defense));
+            let armor_item_bonus = armor_item_bonus as i32
+                + target_set_bonuses.map_or(0, |bonuses| bonuses.armor_class);
             let armor_class =
                 base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
 
@@ -173,8 +182,8 @@ impl<'a> System<'a> for RangedCombatSystem {
                         .white("considers attacking")
                         .cyan(&target_name.name)
                         .white("but misjudges the timing!")
-                        .log();
-                    add_effect(
+                        .log(&mut gamelog);
+                    effects.add_effect(
                         None,
                         EffectType::Particle {
                             glyph: to_cp437('‼'),
@@ -190,21 +199,16 @@ impl<'a> System<'a> for RangedCombatSystem {
 
                 // Target hit!
                 _ if natural_roll == 20 || modified_hit_roll > armor_class => {
-                    let base_damage =
-                        rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
+                    let base_damage = weapon_info.damage.roll(&mut rng);
                     let attr_damage_bonus = attacker_attributes.might.bonus;
-                    let skill_damage_bonus = skill_bonus(Skill::Melee, attacker_skills);
-                    let weapon_damage_bonus = weapon_info.damage_bonus;
+                    let skill_damage_bonus =
+                        skill_bonus(Skill::Melee, attacker_skills) + attacker_set_skill;
 
                     let damage = i32::max(
                         0,
-                        base_damage
-                            + attr_damage_bonus
-                            + skill_hit_bonus
-                            + skill_damage_bonus
-                            + weapon_damage_bonus,
+                        base_damage + attr_damage_bonus + skill_hit_bonus + skill_damage_bonus,
                     );
-                    add_effect(
+                    effects.add_effect(
                         Some(entity),
                         EffectType::Damage { amount: damage },
                         Targets::Single {
@@ -218,7 +222,7 @@ impl<'a> System<'a> for RangedCombatSystem {
                         .white("for")
                         .red(format!("{}", damage))
                         .white("hp.")
-                        .log();
+                        .log(&mut gamelog);
 
                     // Proc effects
                     if weapon_info
@@ -232,7 +236,7 @@ impl<'a> System<'a> for RangedCombatSystem {
                                 target: wants_shoot.target,
                             }
                         };
-                        add_effect(
+                        effects.add_effect(
                             Some(entity),
                             EffectType::ItemUse {
                                 item: weapon_entity.unwrap(),
@@ -249,8 +253,8 @@ impl<'a> System<'a> for RangedCombatSystem {
                         .white("attacks")
                         .cyan(&target_name.name)
                         .white("but can't connect.")
-                        .log();
-                    add_effect(
+                        .log(&mut gamelog);
+                    effects.add_effect(
                         None,
                         EffectType::Particle {
                             glyph: to_cp437('‼'),
@@ -0,0 +1,40 @@
+use super::{effect_structs::Effect, item_structs::ItemAttributeBonus};
+use crate::dice::DiceExpr;
+use serde::Deserialize;
+
+/// A prefix or suffix that can be rolled onto a weapon or wearable when it drops, e.g. the
+/// "Flaming" and "of Haste" in "Flaming Longsword of Haste". Stat bonuses are dice, rolled
+/// once for each item the affix lands on.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Affix {
+    pub name: String,
+    pub kind: AffixKind,
+    /// "common", "rare" or "legendary". An item takes the rarest class of its affixes.
+    pub rarity: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    /// "weapon" and/or "wearable"; empty means both.
+    #[serde(default)]
+    pub applies_to: Vec<String>,
+    pub hit_bonus: Option<DiceExpr>,
+    pub damage_bonus: Option<DiceExpr>,
+    pub armor_class: Option<DiceExpr>,
+    /// Taken off the item's initiative penalty.
+    pub initiative: Option<DiceExpr>,
+    pub attributes: Option<ItemAttributeBonus>,
+    /// Added to the item's base value.
+    #[serde(default)]
+    pub value: f32,
+    pub cursed: Option<bool>,
+    pub proc_chance: Option<f32>,
+    pub proc_target: Option<String>,
+    pub proc_effects: Option<Vec<Effect>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AffixKind {
+    Prefix,
+    Suffix,
+}
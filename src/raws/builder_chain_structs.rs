@@ -0,0 +1,121 @@
+use crate::map_builders::{RoomSort, XEnd, XStart, YEnd, YStart};
+use serde::Deserialize;
+
+/// A way of putting a builder chain together. Levels name the recipe they are built with.
+#[derive(Deserialize, Debug)]
+pub struct BuilderRecipe {
+    pub name: String,
+    pub steps: Vec<RecipeStep>,
+}
+
+/// Applied to the chain in order.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeStep {
+    /// Lays out the first draft of the map. A chain starts exactly once.
+    Start(InitialBuilder),
+    /// Reworks the map or adds to it, e.g. stairs, spawns or prefabs.
+    With(MetaBuilder),
+    /// Applies the steps of one of the choices, picked by weight.
+    OneOf(Vec<RecipeChoice>),
+    /// Applies the steps of the recipe with this name.
+    Recipe(String),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecipeChoice {
+    #[serde(default = "one")]
+    pub weight: i32,
+    /// Choices without steps leave the chain as it is.
+    #[serde(default)]
+    pub steps: Vec<RecipeStep>,
+}
+
+fn one() -> i32 {
+    1
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum InitialBuilder {
+    SimpleMap,
+    BspDungeon,
+    BspInterior,
+    CellularAutomata,
+    DrunkardsWalk(DrunkardPreset),
+    Maze,
+    Dla(DlaPreset),
+    Voronoi(VoronoiPreset),
+    Prefab(PrefabChoice),
+    Town,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MetaBuilder {
+    CellularAutomata,
+    DrunkardsWalk(DrunkardPreset),
+    Dla(DlaPreset),
+    WaveformCollapse,
+    Prefab(PrefabChoice),
+    RoomSorter(RoomSort),
+    RoomDrawer,
+    RoomExploder,
+    RoomCornerRounder,
+    BspCorridors,
+    DoglegCorridors,
+    NearestCorridors,
+    StraightLineCorridors,
+    CorridorSpawner,
+    AreaStartingPosition { x: XStart, y: YStart },
+    RoomBasedStartingPosition,
+    AreaEndingPosition { x: XEnd, y: YEnd },
+    DistantExit,
+    RoomBasedStairs,
+    YellowBrickRoad,
+    CullUnreachable,
+    VoronoiSpawning,
+    RoomBasedSpawner,
+    DoorPlacement,
+    CaveDecorator,
+    CaveTransition,
+    DragonsLair,
+    DragonSpawner,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DrunkardPreset {
+    OpenArea,
+    OpenHalls,
+    WindingPassages,
+    FatPassages,
+    FearfulSymmetry,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DlaPreset {
+    WalkInwards,
+    WalkOutwards,
+    CentralAttractor,
+    Insectoid,
+    HeavyErosion,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum VoronoiPreset {
+    Pythagoras,
+    Manhattan,
+    Chebyshev,
+}
+
+/// Prefab levels and sections are named by their file in `prefabs/`, without the extension.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefabChoice {
+    Level(String),
+    Section(String),
+    Vaults,
+}
@@ -0,0 +1,83 @@
+use crate::dice::DiceExpr;
+use serde::Deserialize;
+
+/// Something an item, spell, trap or weapon proc does. Written in the raws as an object naming
+/// the effect in `type`, next to its parameters: `{ "type": "damage", "amount": 8 }`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    ProvidesHealing {
+        amount: DiceExpr,
+    },
+    ProvidesMana {
+        amount: i32,
+    },
+    Ranged {
+        range: i32,
+    },
+    Damage {
+        amount: i32,
+    },
+    AreaOfEffect {
+        radius: i32,
+    },
+    Confusion {
+        duration: i32,
+    },
+    Slow {
+        initiative_penalty: f32,
+    },
+    DamageOverTime {
+        damage: i32,
+    },
+    TeachSpell {
+        spell: String,
+    },
+    Particle {
+        glyph: char,
+        color: String,
+        lifetime_ms: f32,
+    },
+    ParticleLine {
+        glyph: char,
+        color: String,
+        lifetime_ms: f32,
+    },
+    MagicMapping,
+    TownPortal,
+    Food,
+    SingleActivation,
+    RemoveCurse,
+    Identify,
+    TargetSelf,
+}
+
+impl Effect {
+    /// What the effect does, for tooltips. Effects that only change how others are aimed or
+    /// drawn have nothing to say.
+    pub fn describe(&self) -> Option<String> {
+        match self {
+            Effect::ProvidesHealing { amount } => Some(format!("heals {}", amount)),
+            Effect::ProvidesMana { amount } => Some(format!("restores {} mana", amount)),
+            Effect::Damage { amount } => Some(format!("deals {} damage", amount)),
+            Effect::Confusion { duration } => Some(format!("confuses for {} turns", duration)),
+            Effect::Slow { initiative_penalty } if *initiative_penalty < 0.0 => {
+                Some(format!("hastes by {}", -initiative_penalty))
+            }
+            Effect::Slow { initiative_penalty } => Some(format!("slows by {}", initiative_penalty)),
+            Effect::DamageOverTime { damage } => Some(format!("deals {} damage a turn", damage)),
+            Effect::TeachSpell { spell } => Some(format!("teaches {}", spell)),
+            Effect::MagicMapping => Some("reveals the map".to_string()),
+            Effect::TownPortal => Some("returns to town".to_string()),
+            Effect::Food => Some("feeds".to_string()),
+            Effect::RemoveCurse => Some("removes a curse".to_string()),
+            Effect::Identify => Some("identifies an item".to_string()),
+            Effect::Ranged { .. }
+            | Effect::AreaOfEffect { .. }
+            | Effect::Particle { .. }
+            | Effect::ParticleLine { .. }
+            | Effect::SingleActivation
+            | Effect::TargetSelf => None,
+        }
+    }
+}
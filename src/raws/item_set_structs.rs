@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use super::{effect_structs::Effect, item_structs::ItemAttributeBonus};
+use serde::Deserialize;
+
+/// Items that grant extra bonuses when several of them are equipped by the same wearer.
+#[derive(Deserialize, Debug)]
+pub struct ItemSet {
+    pub name: String,
+    pub pieces: Vec<String>,
+    pub bonuses: Vec<SetBonus>,
+}
+
+/// Granted while at least `pieces` items of the set are equipped. Bonuses for fewer pieces
+/// stay active alongside it.
+#[derive(Deserialize, Debug)]
+pub struct SetBonus {
+    pub pieces: i32,
+    pub attributes: Option<ItemAttributeBonus>,
+    pub skills: Option<HashMap<String, i32>>,
+    pub armor_class: Option<i32>,
+    /// Taken off the initiative penalty of the wearer's equipment, down to none.
+    pub initiative: Option<f32>,
+    /// Applied to the wearer once, like a potion, when the bonus becomes active.
+    pub effects: Option<Vec<Effect>>,
+}
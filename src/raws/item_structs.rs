@@ -1,5 +1,6 @@
+use super::effect_structs::Effect;
+use crate::dice::DiceExpr;
 use serde::Deserialize;
-use std::collections::HashMap;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Item {
@@ -14,7 +15,6 @@ pub struct Item {
     pub vendor_category: Option<String>,
     pub magic: Option<MagicItem>,
     pub attributes: Option<ItemAttributeBonus>,
-    pub template_magic: Option<ItemMagicTemplate>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -44,20 +44,20 @@ pub struct Renderable {
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Consumable {
-    pub effects: HashMap<String, String>, // effect_name, effect_value
+    pub effects: Vec<Effect>,
     pub charges: Option<i32>,
 }
 
-// TODO: Use an equipment_slot field in spawns.json and have an enum for equippables under Item struct. Makes it easier to expand equip slots to amulets, rings, etc.
+// TODO: Use an equipment_slot field in the item raws and have an enum for equippables under Item struct. Makes it easier to expand equip slots to amulets, rings, etc.
 #[derive(Deserialize, Debug, Clone)]
 pub struct Weapon {
     pub range: String,
     pub attribute: String,
-    pub base_damage: String,
+    pub base_damage: DiceExpr,
     pub hit_bonus: i32,
     pub proc_chance: Option<f32>,
     pub proc_target: Option<String>,
-    pub proc_effects: Option<HashMap<String, String>>,
+    pub proc_effects: Option<Vec<Effect>>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -65,11 +65,3 @@ pub struct Wearable {
     pub armor_class: f32,
     pub slot: String,
 }
-
-#[derive(Deserialize, Debug, Clone)]
-pub struct ItemMagicTemplate {
-    pub unidentified_name: String,
-    pub bonus_min: i32,
-    pub bonus_max: i32,
-    pub include_cursed: bool,
-}
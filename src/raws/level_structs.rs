@@ -0,0 +1,37 @@
+use crate::map::themes::DEFAULT_THEME;
+use serde::Deserialize;
+
+/// A level of the dungeon, or a run of levels built alike. Every depth from 1 down to the
+/// deepest `max_depth` belongs to exactly one level; depths past that repeat the deepest one.
+#[derive(Deserialize, Debug)]
+pub struct Level {
+    pub name: String,
+    /// Shown to the player. Defaults to `name`, which has to be unique.
+    pub map_name: Option<String>,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    /// The recipe in `builder_chains` that lays the level out.
+    pub builder: String,
+    #[serde(default = "default_width")]
+    pub width: i32,
+    #[serde(default = "default_height")]
+    pub height: i32,
+    /// The theme in `themes` the level is drawn with.
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// What kind of level this is, for spawn table entries to require or exclude.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_width() -> i32 {
+    80
+}
+
+fn default_height() -> i32 {
+    50
+}
+
+fn default_theme() -> String {
+    DEFAULT_THEME.to_string()
+}
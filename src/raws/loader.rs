@@ -0,0 +1,645 @@
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rltk::rex::XpFile;
+
+use super::{
+    affix_structs::Affix, builder_chain_structs::BuilderRecipe, faction_structs::FactionInfo,
+    item_set_structs::ItemSet, item_structs::Item, level_structs::Level, loot_structs::LootTable,
+    mob_structs::Mob, prefab_structs::Prefab, prop_structs::Prop,
+    spawn_table_structs::SpawnTableEntry, spell_structs::Spell, theme_structs::Theme,
+    validation::RawError, Raws,
+};
+
+/// Where the raws are read from at runtime, relative to the working directory. The game's own
+/// content is in `base/`; every directory in `mods/` is layered over it, in name order.
+pub const RAW_DIR: &str = "raws";
+
+/// Where prefabs are kept in `base/` and in each mod, one `.txt` file per prefab.
+const PREFAB_DIR: &str = "prefabs";
+/// Widest or tallest a prefab can be; larger than any map.
+const MAX_PREFAB_SIZE: usize = 500;
+
+/// The base raws built into the executable, for when there is no raws directory to read.
+const EMBEDDED_RAWS: [(&str, &str); 12] = [
+    (
+        "base/spawn_table.json",
+        include_str!("../../raws/base/spawn_table.json"),
+    ),
+    (
+        "base/loot_tables.json",
+        include_str!("../../raws/base/loot_tables.json"),
+    ),
+    (
+        "base/faction_table.json",
+        include_str!("../../raws/base/faction_table.json"),
+    ),
+    (
+        "base/items.json",
+        include_str!("../../raws/base/items.json"),
+    ),
+    ("base/mobs.json", include_str!("../../raws/base/mobs.json")),
+    (
+        "base/props.json",
+        include_str!("../../raws/base/props.json"),
+    ),
+    (
+        "base/spells.json",
+        include_str!("../../raws/base/spells.json"),
+    ),
+    (
+        "base/affixes.json",
+        include_str!("../../raws/base/affixes.json"),
+    ),
+    (
+        "base/item_sets.json",
+        include_str!("../../raws/base/item_sets.json"),
+    ),
+    (
+        "base/levels.json",
+        include_str!("../../raws/base/levels.json"),
+    ),
+    (
+        "base/builder_chains.json",
+        include_str!("../../raws/base/builder_chains.json"),
+    ),
+    (
+        "base/themes.json",
+        include_str!("../../raws/base/themes.json"),
+    ),
+];
+
+/// The base prefabs built into the executable.
+const EMBEDDED_PREFABS: [(&str, &str); 7] = [
+    (
+        "base/prefabs/checkerboard.txt",
+        include_str!("../../raws/base/prefabs/checkerboard.txt"),
+    ),
+    (
+        "base/prefabs/drow_entry.txt",
+        include_str!("../../raws/base/prefabs/drow_entry.txt"),
+    ),
+    (
+        "base/prefabs/orc_camp.txt",
+        include_str!("../../raws/base/prefabs/orc_camp.txt"),
+    ),
+    (
+        "base/prefabs/silly_smile.txt",
+        include_str!("../../raws/base/prefabs/silly_smile.txt"),
+    ),
+    (
+        "base/prefabs/totally_not_a_trap.txt",
+        include_str!("../../raws/base/prefabs/totally_not_a_trap.txt"),
+    ),
+    (
+        "base/prefabs/underground_fort.txt",
+        include_str!("../../raws/base/prefabs/underground_fort.txt"),
+    ),
+    (
+        "base/prefabs/wfc_populated.txt",
+        include_str!("../../raws/base/prefabs/wfc_populated.txt"),
+    ),
+];
+
+/// The REXPaint files the embedded prefabs take their templates from.
+const EMBEDDED_REX: [(&str, &[u8]); 1] = [(
+    "base/prefabs/wfc_populated.xp",
+    include_bytes!("../../raws/base/prefabs/wfc_populated.xp"),
+)];
+
+/// A single raw file. Any file can hold any of the sections of `Raws`. Files in a mod can also
+/// remove entries by name, e.g. `"remove": { "mobs": ["Rat"] }`.
+#[derive(Default)]
+struct RawFile {
+    raws: Raws,
+    remove: HashMap<String, Vec<String>>,
+    /// Keys that aren't a section, in file order, such as a misspelled `"mbos"`.
+    unknown: Vec<String>,
+}
+
+// Sections are read straight from the file rather than flattened into `Raws`, which would
+// buffer the whole file first and report every error at its end instead of at its entry.
+impl<'de> Deserialize<'de> for RawFile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(RawFileVisitor)
+    }
+}
+
+struct RawFileVisitor;
+
+impl<'de> Visitor<'de> for RawFileVisitor {
+    type Value = RawFile;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object of raw sections")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<RawFile, A::Error> {
+        let mut file = RawFile::default();
+        let raws = &mut file.raws;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "items" => raws.items = map.next_value()?,
+                "mobs" => raws.mobs = map.next_value()?,
+                "props" => raws.props = map.next_value()?,
+                "spawn_table" => raws.spawn_table = map.next_value()?,
+                "loot_tables" => raws.loot_tables = map.next_value()?,
+                "faction_table" => raws.faction_table = map.next_value()?,
+                "spells" => raws.spells = map.next_value()?,
+                "affixes" => raws.affixes = map.next_value()?,
+                "item_sets" => raws.item_sets = map.next_value()?,
+                "levels" => raws.levels = map.next_value()?,
+                "builder_chains" => raws.builder_chains = map.next_value()?,
+                "themes" => raws.themes = map.next_value()?,
+                "remove" => file.remove = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                    file.unknown.push(key);
+                }
+            }
+        }
+        Ok(file)
+    }
+}
+
+/// Entries are identified by name, both for spawning and for mods replacing them.
+trait Named {
+    fn name(&self) -> &str;
+}
+
+macro_rules! impl_named {
+    ($($type:ty),*) => {
+        $(
+        impl Named for $type {
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+        )*
+    };
+}
+
+impl_named!(
+    Item,
+    Mob,
+    Prop,
+    SpawnTableEntry,
+    LootTable,
+    FactionInfo,
+    Spell,
+    Affix,
+    ItemSet,
+    Level,
+    BuilderRecipe,
+    Theme,
+    Prefab
+);
+
+/// Reads the base raws in `dir`, then layers each mod over them. Returns every file that
+/// couldn't be read or applied.
+pub fn load_raw_dir(dir: &Path) -> Result<Raws, Vec<RawError>> {
+    let mut raws = Raws::default();
+    let mut errors = Vec::new();
+
+    for path in json_files(&dir.join("base"), &mut errors) {
+        if let Some(file) = read_raw_file(&path, &mut errors) {
+            apply_file(
+                &mut raws,
+                &path.display().to_string(),
+                file,
+                false,
+                &mut errors,
+            );
+        }
+    }
+    for path in prefab_files(&dir.join("base").join(PREFAB_DIR), &mut errors) {
+        if let Some(prefab) = read_prefab_file(&path, &mut errors) {
+            apply_prefab(&mut raws, &path.display().to_string(), prefab, false);
+        }
+    }
+
+    for mod_dir in subdirectories(&dir.join("mods"), &mut errors) {
+        for path in json_files(&mod_dir, &mut errors) {
+            if let Some(file) = read_raw_file(&path, &mut errors) {
+                apply_file(
+                    &mut raws,
+                    &path.display().to_string(),
+                    file,
+                    true,
+                    &mut errors,
+                );
+            }
+        }
+        for path in prefab_files(&mod_dir.join(PREFAB_DIR), &mut errors) {
+            if let Some(prefab) = read_prefab_file(&path, &mut errors) {
+                apply_prefab(&mut raws, &path.display().to_string(), prefab, true);
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(raws)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses the copy of the base raws built into the executable.
+pub fn load_embedded_raws() -> Result<Raws, Vec<RawError>> {
+    let mut raws = Raws::default();
+    let mut errors = Vec::new();
+    for (name, data) in EMBEDDED_RAWS.iter() {
+        match serde_json::from_str(data) {
+            Ok(file) => apply_file(&mut raws, name, file, false, &mut errors),
+            Err(e) => errors.push(file_error(name, e)),
+        }
+    }
+    for (name, data) in EMBEDDED_PREFABS.iter() {
+        let read_rex = |rex: &Path| -> std::io::Result<XpFile> {
+            let (_, bytes) = EMBEDDED_REX
+                .iter()
+                .find(|(file, _)| Path::new(file) == rex)
+                .ok_or(std::io::ErrorKind::NotFound)?;
+            XpFile::read(&mut &bytes[..])
+        };
+        match parse_prefab(Path::new(name), data, read_rex) {
+            Ok(prefab) => apply_prefab(&mut raws, name, prefab, false),
+            Err(message) => errors.push(file_error(name, message)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(raws)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Adds a file's entries to `raws`. Mods replace entries with the same name; base files only
+/// ever add, so the validator can report names they use twice.
+fn apply_file(
+    raws: &mut Raws,
+    file_name: &str,
+    file: RawFile,
+    is_mod: bool,
+    errors: &mut Vec<RawError>,
+) {
+    for section in file.unknown.iter() {
+        errors.push(RawError {
+            path: format!("{} $.{}", file_name, section),
+            message: format!("Unknown section [{}]", section),
+        });
+    }
+
+    let mut layer = Layer {
+        file_name,
+        is_mod,
+        remove: file.remove,
+        errors,
+    };
+    let sources = &mut raws.sources;
+    layer.apply("items", &mut raws.items, sources, file.raws.items);
+    layer.apply("mobs", &mut raws.mobs, sources, file.raws.mobs);
+    layer.apply("props", &mut raws.props, sources, file.raws.props);
+    layer.apply(
+        "spawn_table",
+        &mut raws.spawn_table,
+        sources,
+        file.raws.spawn_table,
+    );
+    layer.apply(
+        "loot_tables",
+        &mut raws.loot_tables,
+        sources,
+        file.raws.loot_tables,
+    );
+    layer.apply(
+        "faction_table",
+        &mut raws.faction_table,
+        sources,
+        file.raws.faction_table,
+    );
+    layer.apply("spells", &mut raws.spells, sources, file.raws.spells);
+    layer.apply("affixes", &mut raws.affixes, sources, file.raws.affixes);
+    layer.apply(
+        "item_sets",
+        &mut raws.item_sets,
+        sources,
+        file.raws.item_sets,
+    );
+    layer.apply("levels", &mut raws.levels, sources, file.raws.levels);
+    layer.apply(
+        "builder_chains",
+        &mut raws.builder_chains,
+        sources,
+        file.raws.builder_chains,
+    );
+    layer.apply("themes", &mut raws.themes, sources, file.raws.themes);
+    // Prefabs come from their own files, but mods can still remove them by name
+    layer.apply("prefabs", &mut raws.prefabs, sources, file.raws.prefabs);
+
+    let mut unknown: Vec<_> = layer.remove.keys().cloned().collect();
+    unknown.sort();
+    for section in unknown {
+        layer.errors.push(RawError {
+            path: format!("{} $.remove.{}", file_name, section),
+            message: format!("Unknown section [{}]", section),
+        });
+    }
+}
+
+struct Layer<'a> {
+    file_name: &'a str,
+    is_mod: bool,
+    remove: HashMap<String, Vec<String>>,
+    errors: &'a mut Vec<RawError>,
+}
+
+impl Layer<'_> {
+    /// Applies the file's removals from and additions to one section, keeping `sources` in
+    /// step with its entries.
+    fn apply<T: Named>(
+        &mut self,
+        section: &str,
+        entries: &mut Vec<T>,
+        sources: &mut HashMap<String, Vec<String>>,
+        additions: Vec<T>,
+    ) {
+        let sources = sources.entry(section.to_string()).or_default();
+
+        for (i, name) in self
+            .remove
+            .remove(section)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+        {
+            let path = format!("{} $.remove.{}[{}]", self.file_name, section, i);
+            if !self.is_mod {
+                self.errors.push(RawError {
+                    path,
+                    message: "Only mods can remove entries".to_string(),
+                });
+                continue;
+            }
+            match entries.iter().position(|e| e.name() == name) {
+                Some(index) => {
+                    entries.remove(index);
+                    sources.remove(index);
+                }
+                None => self.errors.push(RawError {
+                    path,
+                    message: format!("Nothing named [{}] to remove", name),
+                }),
+            }
+        }
+
+        for (i, entry) in additions.into_iter().enumerate() {
+            let source = format!("{} $.{}[{}]", self.file_name, section, i);
+            let replaces = if self.is_mod {
+                entries.iter().position(|e| e.name() == entry.name())
+            } else {
+                None
+            };
+            match replaces {
+                Some(index) => {
+                    entries[index] = entry;
+                    sources[index] = source;
+                }
+                None => {
+                    entries.push(entry);
+                    sources.push(source);
+                }
+            }
+        }
+    }
+}
+
+fn read_raw_file(path: &Path, errors: &mut Vec<RawError>) -> Option<RawFile> {
+    let name = path.display().to_string();
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => {
+            errors.push(file_error(&name, e));
+            return None;
+        }
+    };
+    match serde_json::from_str(&data) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            errors.push(file_error(&name, e));
+            None
+        }
+    }
+}
+
+/// Adds a prefab read from `file_name`. Prefabs in mods replace those with the same name.
+fn apply_prefab(raws: &mut Raws, file_name: &str, prefab: Prefab, is_mod: bool) {
+    let sources = raws.sources.entry("prefabs".to_string()).or_default();
+    let source = format!("{} $", file_name);
+    let replaces = if is_mod {
+        raws.prefabs.iter().position(|p| p.name == prefab.name)
+    } else {
+        None
+    };
+    match replaces {
+        Some(index) => {
+            raws.prefabs[index] = prefab;
+            sources[index] = source;
+        }
+        None => {
+            raws.prefabs.push(prefab);
+            sources.push(source);
+        }
+    }
+}
+
+fn read_prefab_file(path: &Path, errors: &mut Vec<RawError>) -> Option<Prefab> {
+    let name = path.display().to_string();
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => {
+            errors.push(file_error(&name, e));
+            return None;
+        }
+    };
+    let read_rex =
+        |rex: &Path| -> std::io::Result<XpFile> { XpFile::read(&mut std::fs::File::open(rex)?) };
+    match parse_prefab(path, &data, read_rex) {
+        Ok(prefab) => Some(prefab),
+        Err(message) => {
+            errors.push(file_error(&name, message));
+            None
+        }
+    }
+}
+
+/// Parses the prefab file at `path`: its JSON header, then a line of `---` and the template's
+/// rows. `read_rex` opens the REXPaint file a header names instead of rows, given its path.
+fn parse_prefab(
+    path: &Path,
+    data: &str,
+    read_rex: impl FnOnce(&Path) -> std::io::Result<XpFile>,
+) -> Result<Prefab, String> {
+    // Files edited on Windows end their lines with \r\n, which `lines` takes off as well
+    let lines: Vec<&str> = data.lines().collect();
+    let (header, rows) = match lines.iter().position(|line| line.trim() == "---") {
+        Some(split) => (lines[..split].join("\n"), Some(&lines[split + 1..])),
+        None => (data.to_string(), None),
+    };
+    let mut prefab: Prefab = serde_json::from_str(&header).map_err(|e| e.to_string())?;
+    prefab.name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if prefab.width > MAX_PREFAB_SIZE || prefab.height > MAX_PREFAB_SIZE {
+        return Err(format!(
+            "Is {}x{}, but prefabs can be at most {}x{}",
+            prefab.width, prefab.height, MAX_PREFAB_SIZE, MAX_PREFAB_SIZE
+        ));
+    }
+    prefab.cells = vec![' '; prefab.width * prefab.height];
+
+    match (&prefab.rex, rows) {
+        (Some(_), Some(_)) => return Err("Has both rows and a REXPaint file".to_string()),
+        (None, None) => return Err("Has no rows after a line of ---".to_string()),
+        (None, Some(rows)) => {
+            if rows.len() > prefab.height {
+                return Err(format!(
+                    "Has {} rows, but is {} high",
+                    rows.len(),
+                    prefab.height
+                ));
+            }
+            for (y, row) in rows.iter().enumerate() {
+                let row: Vec<char> = row.chars().collect();
+                if row.len() > prefab.width {
+                    return Err(format!(
+                        "Row {} is {} wide, but the prefab is {} wide",
+                        y + 1,
+                        row.len(),
+                        prefab.width
+                    ));
+                }
+                let start = y * prefab.width;
+                prefab.cells[start..start + row.len()].copy_from_slice(&row);
+            }
+        }
+        (Some(rex), None) => {
+            // Only files next to the prefab, so a mod can't read from anywhere else
+            if rex.contains(['/', '\\']) || Path::new(rex).file_name() != Some(OsStr::new(rex)) {
+                return Err(format!("[{}] isn't a file name in the same folder", rex));
+            }
+            let rex_path = path.with_file_name(rex);
+            let xp_file = read_rex(&rex_path)
+                .map_err(|e| format!("Can't read [{}]: {}", rex_path.display(), e))?;
+            for layer in xp_file.layers.iter() {
+                if layer.width > prefab.width || layer.height > prefab.height {
+                    return Err(format!(
+                        "[{}] is {}x{}, but the prefab is {}x{}",
+                        rex, layer.width, layer.height, prefab.width, prefab.height
+                    ));
+                }
+                for y in 0..layer.height {
+                    for x in 0..layer.width {
+                        if let Some(cell) = layer.get(x, y) {
+                            prefab.cells[y * prefab.width + x] = rltk::to_char(cell.ch as u8);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(prefab)
+}
+
+fn file_error(name: &str, e: impl std::fmt::Display) -> RawError {
+    RawError {
+        path: name.to_string(),
+        message: e.to_string(),
+    }
+}
+
+/// The `.json` files in `dir`, in name order.
+fn json_files(dir: &Path, errors: &mut Vec<RawError>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = read_dir(dir, errors)
+        .into_iter()
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// The `.txt` prefab files in `dir`, in name order. A missing `dir` just has none.
+fn prefab_files(dir: &Path, errors: &mut Vec<RawError>) -> Vec<PathBuf> {
+    if !dir.exists() {
+        return Vec::new();
+    }
+    let mut files: Vec<PathBuf> = read_dir(dir, errors)
+        .into_iter()
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// The directories in `dir`, in name order. A missing `dir` just has none.
+fn subdirectories(dir: &Path, errors: &mut Vec<RawError>) -> Vec<PathBuf> {
+    if !dir.exists() {
+        return Vec::new();
+    }
+    let mut dirs: Vec<PathBuf> = read_dir(dir, errors)
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn read_dir(dir: &Path, errors: &mut Vec<RawError>) -> Vec<PathBuf> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            errors.push(file_error(&dir.display().to_string(), e));
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(data: &str) -> Vec<RawError> {
+        let mut errors = Vec::new();
+        let file = serde_json::from_str(data).unwrap();
+        apply_file(&mut Raws::default(), "test.json", file, true, &mut errors);
+        errors
+    }
+
+    #[test]
+    fn reports_unknown_sections() {
+        let errors = apply(r#"{ "mbos": [{ "name": "Rat" }], "items": [], "colour": {} }"#);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["test.json $.mbos", "test.json $.colour"]);
+        assert_eq!(errors[0].message, "Unknown section [mbos]");
+    }
+
+    #[test]
+    fn reports_bad_effects_at_their_entry() {
+        let data = r#"{
+  "items": [
+    { "name": "Bad Potion", "consumable": { "effects": [{ "type": "damge", "amount": 8 }] } }
+  ]
+}"#;
+        let error = serde_json::from_str::<RawFile>(data).err().unwrap();
+        assert!(error.to_string().contains("unknown variant `damge`"));
+        assert_eq!(error.line(), 3);
+    }
+}
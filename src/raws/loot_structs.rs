@@ -1,13 +1,33 @@
+use crate::dice::DiceExpr;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
 pub struct LootTable {
     pub name: String,
+    /// Dropped every time the table is rolled.
+    #[serde(default)]
+    pub guaranteed: Vec<LootDrop>,
+    /// How many times to pick from `drops`.
+    #[serde(default = "one_pick")]
+    pub picks: DiceExpr,
+    #[serde(default)]
     pub drops: Vec<LootDrop>,
 }
 
+fn one_pick() -> DiceExpr {
+    DiceExpr::constant(1)
+}
+
+/// One entry of a loot table: an item by `name`, a roll on another `table`, or a pile of
+/// `gold`. An entry with none of them drops nothing when picked.
 #[derive(Deserialize, Debug)]
 pub struct LootDrop {
-    pub name: String,
+    pub name: Option<String>,
+    pub table: Option<String>,
+    pub gold: Option<DiceExpr>,
+    /// How many times the entry drops. Defaults to once.
+    pub quantity: Option<DiceExpr>,
+    /// Chance of being picked from `drops`, relative to the other entries.
+    #[serde(default)]
     pub weight: i32,
 }
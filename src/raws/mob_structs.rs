@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use super::item_structs::Renderable;
+use crate::dice::DiceExpr;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -21,7 +22,7 @@ pub struct Mob {
     pub loot_table: Option<String>,
     pub light: Option<MobLight>,
     pub faction: Option<String>,
-    pub gold: Option<String>,
+    pub gold: Option<DiceExpr>,
     pub vendor: Option<Vec<String>>,
     pub abilities: Option<Vec<MobAbility>>,
     pub on_death: Option<Vec<MobAbility>>,
@@ -54,7 +55,7 @@ pub struct MobNatural {
 pub struct NaturalAttack {
     pub name: String,
     pub hit_bonus: i32,
-    pub damage: String,
+    pub damage: DiceExpr,
 }
 
 #[derive(Deserialize, Debug)]
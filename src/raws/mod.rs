@@ -1,10 +1,16 @@
+mod effect_structs;
 mod item_structs;
 mod mob_structs;
 mod prop_structs;
 pub mod rawsmaster;
-mod spawn_table_structs;
-use serde::Deserialize;
-use std::sync::Mutex;
+pub mod spawn_table_structs;
+use crate::{
+    gamelog::{GameLog, Logger},
+    map::dungeon::MasterDungeonMap,
+};
+use specs::World;
+use std::collections::HashMap;
+use std::sync::Arc;
 mod loot_structs;
 use item_structs::Item;
 use loot_structs::LootTable;
@@ -14,12 +20,23 @@ use rawsmaster::RawMaster;
 use spawn_table_structs::SpawnTableEntry;
 pub mod faction_structs;
 use faction_structs::FactionInfo;
-mod weapon_traits;
+mod affix_structs;
+pub mod builder_chain_structs;
+pub mod item_set_structs;
+pub mod level_structs;
+pub mod prefab_structs;
+pub mod theme_structs;
 
-use self::{spell_structs::Spell, weapon_traits::WeaponTrait};
+use self::{
+    affix_structs::Affix, builder_chain_structs::BuilderRecipe, item_set_structs::ItemSet,
+    level_structs::Level, prefab_structs::Prefab, spell_structs::Spell, theme_structs::Theme,
+};
+pub mod loader;
 mod spell_structs;
+pub mod validation;
 
-#[derive(Deserialize, Debug, Default)]
+/// Every section of the raws. Files are read into it section by section by the loader.
+#[derive(Debug, Default)]
 pub struct Raws {
     pub items: Vec<Item>,
     pub mobs: Vec<Mob>,
@@ -28,25 +45,88 @@ pub struct Raws {
     pub loot_tables: Vec<LootTable>,
     pub faction_table: Vec<FactionInfo>,
     pub spells: Vec<Spell>,
-    pub weapon_traits: Vec<WeaponTrait>,
+    pub affixes: Vec<Affix>,
+    pub item_sets: Vec<ItemSet>,
+    pub levels: Vec<Level>,
+    pub builder_chains: Vec<BuilderRecipe>,
+    pub themes: Vec<Theme>,
+    /// Read from the files in `prefabs/` rather than from JSON sections.
+    pub prefabs: Vec<Prefab>,
+    /// Where each entry was read from, by section, for reporting problems with it.
+    pub sources: HashMap<String, Vec<String>>,
 }
 
-rltk::embedded_resource!(RAW_FILE, "../../raws/spawns.json");
+/// Returns the raws loaded into `ecs`. The handle is shared rather than borrowed from the world,
+/// so it can be held while spawning into that same world.
+pub fn get_raws(ecs: &World) -> Arc<RawMaster> {
+    ecs.fetch::<Arc<RawMaster>>().clone()
+}
+
+/// Reads the raws and their mods from `RAW_DIR`, falling back to the copy built into the game
+/// when there is no such directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_raws() -> Result<Raws, Vec<validation::RawError>> {
+    let dir = std::path::Path::new(loader::RAW_DIR);
+    if dir.is_dir() {
+        loader::load_raw_dir(dir)
+    } else {
+        loader::load_embedded_raws()
+    }
+}
+
+// There is no file system to read mods from in the browser
+#[cfg(target_arch = "wasm32")]
+fn read_raws() -> Result<Raws, Vec<validation::RawError>> {
+    loader::load_embedded_raws()
+}
+
+fn build_raw_master() -> Result<RawMaster, Vec<validation::RawError>> {
+    let raws = read_raws().and_then(validation::validated)?;
+    let mut master = RawMaster::default();
+    master.load(raws);
+    Ok(master)
+}
 
-lazy_static! {
-    pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::default());
+pub fn load_raws(ecs: &mut World) {
+    match build_raw_master() {
+        Ok(raws) => ecs.insert(Arc::new(raws)),
+        Err(errors) => {
+            for error in errors.iter() {
+                rltk::console::log(error.to_string());
+            }
+            panic!("The raws have {} problem(s), listed above", errors.len());
+        }
+    }
 }
 
-pub fn load_raws() {
-    rltk::link_resource!(RAW_FILE, "../../raws/spawns.json");
-
-    // Retrieve raw data as u8 array
-    let raw_data = rltk::embedding::EMBED
-        .lock()
-        .get_resource("../../raws/spawns.json".to_string())
-        .unwrap();
-    let raw_string =
-        std::str::from_utf8(raw_data).expect("Unable to convert to a valid UTF-8 string.");
-    let decoder: Raws = serde_json::from_str(raw_string).expect("Unable to parse JSON");
-    RAWS.lock().unwrap().load(decoder);
+/// Most problems shown in the game log when a reload fails; the console gets all of them.
+const MAX_LOGGED_RAW_ERRORS: usize = 5;
+
+/// Re-reads the raws during a game. Entities already spawned keep their components; anything
+/// spawned from now on uses the new raws. If the new raws have problems they are reported in
+/// the game log and the current raws stay in place.
+pub fn reload_raws(ecs: &mut World) {
+    match build_raw_master() {
+        Ok(raws) => {
+            ecs.fetch_mut::<MasterDungeonMap>()
+                .name_new_magic_items(&raws);
+            ecs.insert(Arc::new(raws));
+            Logger::new()
+                .white("Raws reloaded.")
+                .log(&mut ecs.fetch_mut::<GameLog>());
+        }
+        Err(errors) => {
+            let mut log = ecs.fetch_mut::<GameLog>();
+            Logger::new()
+                .red("Raws not reloaded:")
+                .white(format!("{} problem(s)", errors.len()))
+                .log(&mut log);
+            for error in errors.iter() {
+                rltk::console::log(error.to_string());
+            }
+            for error in errors.iter().take(MAX_LOGGED_RAW_ERRORS) {
+                Logger::new().orange(error).log(&mut log);
+            }
+        }
+    }
 }
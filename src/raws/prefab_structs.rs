@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use crate::map_builders::{HorizontalPlacement, VerticalPlacement};
+use serde::Deserialize;
+
+/// A vault, section or whole level read from a file in `prefabs/`. The file starts with this as
+/// JSON, followed by a line of `---` and the template's rows, unless `rex` names a REXPaint file
+/// to take them from instead.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Prefab {
+    /// The file's name, without the extension.
+    #[serde(skip)]
+    pub name: String,
+    pub kind: PrefabKind,
+    pub width: usize,
+    pub height: usize,
+    /// Vaults are only placed on levels within these depths.
+    #[serde(default = "first_depth")]
+    pub min_depth: i32,
+    pub max_depth: Option<i32>,
+    /// Where a section is stamped onto the map.
+    #[serde(default)]
+    pub placement: Placement,
+    /// How often a vault is picked, against the others that fit the level.
+    #[serde(default = "one")]
+    pub weight: i32,
+    /// Most times a vault is placed on one level.
+    #[serde(default = "one")]
+    pub max_per_level: i32,
+    /// Whether a vault or section can be placed turned by quarters.
+    #[serde(default)]
+    pub rotate: bool,
+    /// Whether a vault or section can be placed flipped left to right or top to bottom.
+    #[serde(default)]
+    pub mirror: bool,
+    /// What to spawn for characters other than ` `, `#`, `>` and `@`, by name. Spawns stand on
+    /// floor; any other character leaves the tile underneath as it was.
+    #[serde(default)]
+    pub legend: HashMap<String, String>,
+    /// A REXPaint file next to this one to read the template from.
+    pub rex: Option<String>,
+    /// The template row by row, filled out with floor to `width` by `height`.
+    #[serde(skip)]
+    pub cells: Vec<char>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefabKind {
+    /// Stamped onto floor space by `{ "prefab": "vaults" }`.
+    Vault,
+    Section,
+    Level,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct Placement {
+    #[serde(default)]
+    pub x: HorizontalPlacement,
+    #[serde(default)]
+    pub y: VerticalPlacement,
+}
+
+impl Prefab {
+    /// The name of what to spawn on the character `ch`, if the legend has it.
+    pub fn spawn_for(&self, ch: char) -> Option<&String> {
+        self.legend.get(ch.encode_utf8(&mut [0; 4]) as &str)
+    }
+
+    pub fn in_depth(&self, depth: i32) -> bool {
+        depth >= self.min_depth && !self.max_depth.is_some_and(|max| depth > max)
+    }
+}
+
+fn first_depth() -> i32 {
+    1
+}
+
+fn one() -> i32 {
+    1
+}
@@ -1,6 +1,5 @@
-use super::{item_structs::Renderable, mob_structs};
+use super::{effect_structs::Effect, item_structs::Renderable, mob_structs};
 use serde::Deserialize;
-use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
 pub struct Prop {
@@ -12,9 +11,11 @@ pub struct Prop {
     pub door_open: Option<bool>,
     pub entry_trigger: Option<EntryTrigger>,
     pub light: Option<mob_structs::MobLight>,
+    /// Dropped where the prop stands when its entry trigger fires, e.g. for chests.
+    pub loot_table: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct EntryTrigger {
-    pub effects: HashMap<String, String>,
+    pub effects: Vec<Effect>,
 }
@@ -1,4 +1,17 @@
-use super::{faction_structs::Reaction, spawn_table_structs::SpawnTableEntry, Raws, RAWS};
+use super::{
+    affix_structs::{Affix, AffixKind},
+    builder_chain_structs::BuilderRecipe,
+    effect_structs::Effect,
+    faction_structs::Reaction,
+    get_raws,
+    item_set_structs::ItemSet,
+    item_structs::ItemAttributeBonus,
+    level_structs::Level,
+    loot_structs::LootDrop,
+    prefab_structs::Prefab,
+    spawn_table_structs::{SpawnGroup, SpawnTableEntry},
+    Raws,
+};
 use crate::{
     components::{
         AreaOfEffect, BlocksTile, BlocksVisibility, Confusion, Consumable, Door, EntryTrigger,
@@ -7,88 +20,85 @@ use crate::{
     },
     dungeon::MasterDungeonMap,
     gamesystem::{attr_bonus, mana_at_level, npc_hp},
-    random_table::{MasterTable, RandomTable},
+    map::themes::{DrawTheme, DEFAULT_THEME},
+    random_table::MasterTable,
     AlwaysTargetsSelf, Attribute, AttributeBonus, Attributes, CursedItem, DamageOverTime, Duration,
-    Equipped, Faction, InBackpack, Initiative, IsSerialized, LightSource, LootTable, MagicItem,
-    MagicItemClass, MoveMode, Movement, NaturalAttack, NaturalAttackDefense, ObfuscatedName,
-    OnDeath, Pool, Pools, ProvidesIdentification, ProvidesMana, ProvidesRemoveCurse, Skill, Skills,
-    Slow, SpawnParticleBurst, SpawnParticleLine, SpecialAbilities, SpecialAbility, SpellTemplate,
-    TeachesSpell, TileSize, TownPortal, Vendor, WeaponAttribute, Wearable,
+    Equipped, Faction, InBackpack, Initiative, IsSerialized, ItemSetPiece, LightSource, LootTable,
+    MagicItem, MagicItemClass, Map, MoveMode, Movement, NaturalAttack, NaturalAttackDefense,
+    ObfuscatedName, OnDeath, Pool, Pools, ProvidesIdentification, ProvidesMana,
+    ProvidesRemoveCurse, Skill, Skills, Slow, SpawnParticleBurst, SpawnParticleLine,
+    SpecialAbilities, SpecialAbility, SpellTemplate, TeachesSpell, TileSize, TownPortal, Vendor,
+    WeaponAttribute, Wearable,
 };
-use regex::Regex;
 use specs::{
     saveload::{MarkedBuilder, SimpleMarker},
     Builder, Entities, Entity, EntityBuilder, Join, ReadStorage, World, WorldExt,
 };
-use std::{
-    cmp::Ordering,
-    collections::{HashMap, HashSet},
-};
+use std::collections::{HashMap, HashSet};
 
 macro_rules! apply_effects {
     ( $effects:expr, $eb:expr ) => {
         for effect in $effects.iter() {
-            let effect_name = effect.0.as_str();
-            match effect_name {
-                "provides_healing" => {
+            match effect {
+                Effect::ProvidesHealing { amount } => {
                     $eb = $eb.with(ProvidesHealing {
-                        heal_amount: effect.1.parse::<i32>().unwrap(),
+                        heal_amount: amount.clone(),
                     })
                 }
-                "provides_mana" => {
+                Effect::ProvidesMana { amount } => {
                     $eb = $eb.with(ProvidesMana {
-                        mana_amount: effect.1.parse::<i32>().unwrap(),
+                        mana_amount: *amount,
                     })
                 }
-                "ranged" => {
-                    $eb = $eb.with(Ranged {
-                        range: effect.1.parse::<i32>().unwrap(),
-                    })
+                Effect::Ranged { range } => $eb = $eb.with(Ranged { range: *range }),
+                Effect::Damage { amount } => $eb = $eb.with(InflictsDamage { damage: *amount }),
+                Effect::AreaOfEffect { radius } => $eb = $eb.with(AreaOfEffect { radius: *radius }),
+                Effect::Confusion { duration } => {
+                    $eb = $eb.with(Confusion {});
+                    $eb = $eb.with(Duration { turns: *duration });
                 }
-                "damage" => {
-                    $eb = $eb.with(InflictsDamage {
-                        damage: effect.1.parse::<i32>().unwrap(),
+                Effect::MagicMapping => $eb = $eb.with(MagicMapper {}),
+                Effect::TownPortal => $eb = $eb.with(TownPortal {}),
+                Effect::Food => $eb = $eb.with(ProvidesFood {}),
+                Effect::SingleActivation => $eb = $eb.with(SingleActivation {}),
+                Effect::ParticleLine {
+                    glyph,
+                    color,
+                    lifetime_ms,
+                } => {
+                    $eb = $eb.with(SpawnParticleLine {
+                        glyph: rltk::to_cp437(*glyph),
+                        color: rltk::RGB::from_hex(color).expect("Bad RGB"),
+                        lifetime_ms: *lifetime_ms,
                     })
                 }
-                "area_of_effect" => {
-                    $eb = $eb.with(AreaOfEffect {
-                        radius: effect.1.parse::<i32>().unwrap(),
+                Effect::Particle {
+                    glyph,
+                    color,
+                    lifetime_ms,
+                } => {
+                    $eb = $eb.with(SpawnParticleBurst {
+                        glyph: rltk::to_cp437(*glyph),
+                        color: rltk::RGB::from_hex(color).expect("Bad RGB"),
+                        lifetime_ms: *lifetime_ms,
                     })
                 }
-                "confusion" => {
-                    $eb = $eb.with(Confusion {});
-                    $eb = $eb.with(Duration {
-                        turns: effect.1.parse::<i32>().unwrap(),
-                    });
-                }
-                "magic_mapping" => $eb = $eb.with(MagicMapper {}),
-                "town_portal" => $eb = $eb.with(TownPortal {}),
-                "food" => $eb = $eb.with(ProvidesFood {}),
-                "single_activation" => $eb = $eb.with(SingleActivation {}),
-                "particle_line" => $eb = $eb.with(parse_particle_line(&effect.1)),
-                "particle" => $eb = $eb.with(parse_particle(&effect.1)),
-                "remove_curse" => $eb = $eb.with(ProvidesRemoveCurse {}),
-                "identify" => $eb = $eb.with(ProvidesIdentification {}),
-                "target_self" => $eb = $eb.with(AlwaysTargetsSelf {}),
-                "teach_spell" => {
+                Effect::RemoveCurse => $eb = $eb.with(ProvidesRemoveCurse {}),
+                Effect::Identify => $eb = $eb.with(ProvidesIdentification {}),
+                Effect::TargetSelf => $eb = $eb.with(AlwaysTargetsSelf {}),
+                Effect::TeachSpell { spell } => {
                     $eb = $eb.with(TeachesSpell {
-                        spell: effect.1.to_string(),
+                        spell: spell.clone(),
                     })
                 }
-                "slow" => {
+                Effect::Slow { initiative_penalty } => {
                     $eb = $eb.with(Slow {
-                        initiative_penalty: effect.1.parse::<f32>().unwrap(),
+                        initiative_penalty: *initiative_penalty,
                     })
                 }
-                "damage_over_time" => {
-                    $eb = $eb.with(DamageOverTime {
-                        damage: effect.1.parse::<i32>().unwrap(),
-                    })
+                Effect::DamageOverTime { damage } => {
+                    $eb = $eb.with(DamageOverTime { damage: *damage })
                 }
-                _ => rltk::console::log(format!(
-                    "Warning: consumable effect {} not implemented.",
-                    effect_name
-                )),
             }
         }
     };
@@ -107,199 +117,37 @@ pub struct RawMaster {
     pub mob_index: HashMap<String, usize>,
     pub prop_index: HashMap<String, usize>,
     pub spawn_table: Vec<SpawnTableEntry>,
+    group_index: HashMap<String, usize>,
     pub loot_index: HashMap<String, usize>,
     faction_index: HashMap<String, HashMap<String, Reaction>>,
     spell_index: HashMap<String, usize>,
-}
-
-struct NewMagicItem {
-    name: String,
-    bonus: i32,
+    item_set_index: HashMap<String, usize>,
+    /// The set each set piece belongs to, by item name.
+    set_piece_index: HashMap<String, usize>,
+    builder_recipe_index: HashMap<String, usize>,
+    prefab_index: HashMap<String, usize>,
+    themes: HashMap<String, DrawTheme>,
 }
 
 impl RawMaster {
-    fn append_magic_template(items_to_build: &mut Vec<NewMagicItem>, item: &super::Item) {
-        let maybe_template = item.template_magic.as_ref();
-        if maybe_template.is_none() {
-            return;
-        }
-        let template = maybe_template.unwrap();
-        if item.weapon.is_none() && item.wearable.is_none() {
-            rltk::console::log(format!(
-                "{} is marked as templated, but isn't a weapon or armor.",
-                item.name
-            ));
-            return;
-        }
-
-        if template.include_cursed {
-            items_to_build.push(NewMagicItem {
-                name: item.name.clone(),
-                bonus: -1,
-            });
-        }
-        for bonus in template.bonus_min..=template.bonus_max {
-            items_to_build.push(NewMagicItem {
-                name: item.name.clone(),
-                bonus,
-            });
-        }
-    }
-
-    fn build_base_magic_item(&self, nmw: &NewMagicItem) -> super::Item {
-        let base_item_index = self.item_index[&nmw.name];
-        let mut base_item_copy = self.raws.items[base_item_index].clone();
-        base_item_copy.vendor_category = None; // Don't sell magic items!
-
-        if nmw.bonus == -1 {
-            base_item_copy.name = format!("{} -1", nmw.name);
-        } else {
-            base_item_copy.name = format!("{} +{}", nmw.name, nmw.bonus);
-        }
-
-        base_item_copy.magic = Some(crate::raws::item_structs::MagicItem {
-            class: match nmw.bonus {
-                2 => "rare".to_string(),
-                3 => "rare".to_string(),
-                4 => "rare".to_string(),
-                5 => "legendary".to_string(),
-                _ => "common".to_string(),
-            },
-            naming: base_item_copy
-                .template_magic
-                .as_ref()
-                .unwrap()
-                .unidentified_name
-                .clone(),
-            cursed: if nmw.bonus == -1 { Some(true) } else { None },
-        });
-
-        if let Some(initiative_penalty) = base_item_copy.initiative_penalty.as_mut() {
-            *initiative_penalty -= nmw.bonus as f32;
-        }
-        if let Some(base_value) = base_item_copy.base_value.as_mut() {
-            *base_value += (nmw.bonus as f32 + 1.0) * 50.0;
-        }
-        if let Some(weapon) = base_item_copy.weapon.as_mut() {
-            weapon.hit_bonus += nmw.bonus;
-            let (n, die, plus) = parse_dice_string(&weapon.base_damage);
-            let final_bonus = plus + nmw.bonus;
-            match final_bonus.cmp(&0) {
-                Ordering::Less => {
-                    weapon.base_damage = format!("{n}d{die}{final_bonus}"); // - sign included in negatives
-                }
-                Ordering::Equal => (),
-                Ordering::Greater => {
-                    weapon.base_damage = format!("{n}d{die}+{final_bonus}");
-                }
-            }
-        }
-        if let Some(armor) = base_item_copy.wearable.as_mut() {
-            armor.armor_class += nmw.bonus as f32;
-        }
-        base_item_copy
-    }
-
-    fn build_magic_weapon_or_armor(&mut self, items_to_build: &[NewMagicItem]) {
-        for nmw in items_to_build.iter() {
-            let base_item_copy = self.build_base_magic_item(nmw);
-
-            let real_name = base_item_copy.name.clone();
-            self.raws.items.push(base_item_copy);
-            self.item_index
-                .insert(real_name.clone(), self.raws.items.len() - 1);
-
-            self.raws.spawn_table.push(super::SpawnTableEntry {
-                name: real_name.clone(),
-                weight: 10 - i32::abs(nmw.bonus),
-                min_depth: 1 + i32::abs((nmw.bonus - 1) * 3),
-                max_depth: 100,
-                add_map_depth_to_weight: None,
-            });
-        }
-    }
-
-    fn build_traited_weapons(&mut self, items_to_build: &[NewMagicItem]) {
-        items_to_build
-            .iter()
-            .filter(|i| i.bonus > 0)
-            .for_each(|nmw| {
-                for wt in self.raws.weapon_traits.iter() {
-                    let mut base_item_copy = self.build_base_magic_item(nmw);
-                    let maybe_weapon = base_item_copy.weapon.as_mut();
-                    if maybe_weapon.is_none() {
-                        continue;
-                    }
-                    let weapon = maybe_weapon.unwrap();
-                    base_item_copy.name = format!("{} {}", wt.name, base_item_copy.name);
-                    if let Some(base_value) = base_item_copy.base_value.as_mut() {
-                        *base_value *= 2.0;
-                    }
-                    weapon.proc_chance = Some(0.25);
-                    weapon.proc_effects = Some(wt.effects.clone());
-
-                    let real_name = base_item_copy.name.clone();
-                    self.raws.items.push(base_item_copy);
-                    self.item_index
-                        .insert(real_name.clone(), self.raws.items.len() - 1);
-
-                    self.raws.spawn_table.push(super::SpawnTableEntry {
-                        name: real_name.clone(),
-                        weight: 9 - i32::abs(nmw.bonus),
-                        min_depth: 2 + i32::abs((nmw.bonus - 1) * 3),
-                        max_depth: 100,
-                        add_map_depth_to_weight: None,
-                    });
-                }
-            });
-    }
-
+    /// Indexes `raws`. Expects raws that passed `validate_raws`.
     pub fn load(&mut self, raws: Raws) {
         self.raws = raws;
         self.item_index = HashMap::new();
-        let mut used_names: HashSet<String> = HashSet::new();
-        let mut items_to_build = Vec::new();
 
         for (i, item) in self.raws.items.iter().enumerate() {
-            if used_names.contains(&item.name) {
-                rltk::console::log(format!(
-                    "WARNING -  duplicate item name in raws [{}]",
-                    item.name
-                ));
-            }
             self.item_index.insert(item.name.clone(), i);
-            used_names.insert(item.name.clone());
-
-            RawMaster::append_magic_template(&mut items_to_build, item);
         }
-
         for (i, mob) in self.raws.mobs.iter().enumerate() {
-            if used_names.contains(&mob.name) {
-                rltk::console::log(format!(
-                    "WARNING -  duplicate mob name in raws [{}]",
-                    mob.name
-                ));
-            }
             self.mob_index.insert(mob.name.clone(), i);
-            used_names.insert(mob.name.clone());
         }
         for (i, prop) in self.raws.props.iter().enumerate() {
-            if used_names.contains(&prop.name) {
-                rltk::console::log(format!(
-                    "WARNING -  duplicate prop name in raws [{}]",
-                    prop.name
-                ));
-            }
             self.prop_index.insert(prop.name.clone(), i);
-            used_names.insert(prop.name.clone());
         }
 
-        for spawn in self.raws.spawn_table.iter() {
-            if !used_names.contains(&spawn.name) {
-                rltk::console::log(format!(
-                    "WARNING - Spawn tables references unspecified entity {}",
-                    spawn.name
-                ));
+        for (i, entry) in self.raws.spawn_table.iter().enumerate() {
+            if entry.group.is_some() {
+                self.group_index.insert(entry.name.clone(), i);
             }
         }
 
@@ -328,8 +176,25 @@ impl RawMaster {
             self.spell_index.insert(spell.name.clone(), i);
         }
 
-        self.build_magic_weapon_or_armor(&items_to_build);
-        self.build_traited_weapons(&items_to_build);
+        for (i, item_set) in self.raws.item_sets.iter().enumerate() {
+            self.item_set_index.insert(item_set.name.clone(), i);
+            for piece in item_set.pieces.iter() {
+                self.set_piece_index.insert(piece.clone(), i);
+            }
+        }
+
+        for (i, recipe) in self.raws.builder_chains.iter().enumerate() {
+            self.builder_recipe_index.insert(recipe.name.clone(), i);
+        }
+
+        for (i, prefab) in self.raws.prefabs.iter().enumerate() {
+            self.prefab_index.insert(prefab.name.clone(), i);
+        }
+
+        for theme in self.raws.themes.iter() {
+            self.themes
+                .insert(theme.name.clone(), DrawTheme::new(&self.raws, theme));
+        }
     }
 }
 
@@ -351,9 +216,9 @@ pub fn spawn_named_entity(
 }
 
 pub fn spawn_all_spells(ecs: &mut World) {
-    let raws = &RAWS.lock().unwrap();
+    let raws = get_raws(ecs);
     for spell in raws.raws.spells.iter() {
-        spawn_named_spell(raws, ecs, &spell.name);
+        spawn_named_spell(&raws, ecs, &spell.name);
     }
 }
 
@@ -385,7 +250,23 @@ pub fn spawn_named_item(
     if !raws.item_index.contains_key(key) {
         return None;
     }
-    let item_template = &raws.raws.items[raws.item_index[key]];
+    let base_template = &raws.raws.items[raws.item_index[key]];
+    let affixed_template;
+    let item_template = match pos {
+        // Items found lying around may have rolled affixes
+        SpawnType::AtPosition { .. } => {
+            let depth = ecs.fetch::<Map>().depth;
+            let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+            let affixes = roll_affixes(raws, &mut rng, base_template, depth);
+            if affixes.is_empty() {
+                base_template
+            } else {
+                affixed_template = apply_affixes(base_template, &affixes, &mut rng);
+                &affixed_template
+            }
+        }
+        _ => base_template,
+    };
     let dm = ecs.fetch::<MasterDungeonMap>();
     let scroll_names = dm.scroll_mappings.clone();
     let potion_names = dm.potion_mappings.clone();
@@ -416,12 +297,9 @@ pub fn spawn_named_item(
             slot: EquipmentSlot::Melee,
         });
 
-        let (n_dice, die_type, bonus) = parse_dice_string(&weapon.base_damage);
         let mut wpn = Weapon {
             attribute: WeaponAttribute::Might,
-            damage_n_dice: n_dice,
-            damage_die_type: die_type,
-            damage_bonus: bonus,
+            damage: weapon.base_damage.clone(),
             hit_bonus: weapon.hit_bonus,
             proc_chance: weapon.proc_chance,
             proc_target: weapon.proc_target.clone(),
@@ -494,6 +372,12 @@ pub fn spawn_named_item(
         });
     }
 
+    if let Some(set_index) = raws.set_piece_index.get(key) {
+        eb = eb.with(ItemSetPiece {
+            set: raws.raws.item_sets[*set_index].name.clone(),
+        });
+    }
+
     if let Some(consumable) = &item_template.consumable {
         let max_charges = consumable.charges.unwrap_or(1);
         eb = eb.with(Consumable {
@@ -506,6 +390,172 @@ pub fn spawn_named_item(
     Some(eb.build())
 }
 
+/// Chance, in percent, that a weapon or wearable dropping on the first level rolls affixes.
+const AFFIX_BASE_CHANCE: i32 = 10;
+/// Added to the affix chance for every level deeper.
+const AFFIX_CHANCE_PER_DEPTH: i32 = 3;
+/// Used for affixes with proc effects that don't give a chance of their own.
+const DEFAULT_PROC_CHANCE: f32 = 0.25;
+
+/// Picks the affixes for `item` dropping at `depth`: a prefix, a suffix, both or (most of the
+/// time) neither. Items that are already magic never roll any.
+fn roll_affixes<'a>(
+    raws: &'a RawMaster,
+    rng: &mut rltk::RandomNumberGenerator,
+    item: &super::Item,
+    depth: i32,
+) -> Vec<&'a Affix> {
+    if item.magic.is_some() || (item.weapon.is_none() && item.wearable.is_none()) {
+        return Vec::new();
+    }
+    if rng.roll_dice(1, 100) > AFFIX_BASE_CHANCE + depth * AFFIX_CHANCE_PER_DEPTH {
+        return Vec::new();
+    }
+
+    let kinds: &[AffixKind] = match rng.roll_dice(1, 3) {
+        1 => &[AffixKind::Prefix],
+        2 => &[AffixKind::Suffix],
+        _ => &[AffixKind::Prefix, AffixKind::Suffix],
+    };
+    kinds
+        .iter()
+        .filter_map(|kind| pick_affix(raws, rng, item, *kind, depth))
+        .collect()
+}
+
+fn pick_affix<'a>(
+    raws: &'a RawMaster,
+    rng: &mut rltk::RandomNumberGenerator,
+    item: &super::Item,
+    kind: AffixKind,
+    depth: i32,
+) -> Option<&'a Affix> {
+    let candidates: Vec<&Affix> = raws
+        .raws
+        .affixes
+        .iter()
+        .filter(|affix| {
+            affix.kind == kind
+                && depth >= affix.min_depth
+                && depth <= affix.max_depth
+                && affix_applies_to(affix, item)
+        })
+        .collect();
+    let total_weight: i32 = candidates.iter().map(|affix| affix.weight).sum();
+    if total_weight < 1 {
+        return None;
+    }
+
+    let mut roll = rng.roll_dice(1, total_weight) - 1;
+    for affix in candidates {
+        if roll < affix.weight {
+            return Some(affix);
+        }
+        roll -= affix.weight;
+    }
+    None
+}
+
+fn affix_applies_to(affix: &Affix, item: &super::Item) -> bool {
+    affix.applies_to.is_empty()
+        || (item.weapon.is_some() && affix.applies_to.iter().any(|a| a == "weapon"))
+        || (item.wearable.is_some() && affix.applies_to.iter().any(|a| a == "wearable"))
+}
+
+/// A copy of `item` with `affixes` rolled onto it: renamed after them, made magic (and so
+/// unidentified until the player learns what it is), and with their bonuses added.
+fn apply_affixes(
+    item: &super::Item,
+    affixes: &[&Affix],
+    rng: &mut rltk::RandomNumberGenerator,
+) -> super::Item {
+    let mut affixed = item.clone();
+    affixed.vendor_category = None;
+
+    for affix in affixes.iter() {
+        affixed.name = match affix.kind {
+            AffixKind::Prefix => format!("{} {}", affix.name, affixed.name),
+            AffixKind::Suffix => format!("{} {}", affixed.name, affix.name),
+        };
+    }
+
+    let rarity_rank = |rarity: &str| match rarity {
+        "legendary" => 2,
+        "rare" => 1,
+        _ => 0,
+    };
+    affixed.magic = Some(super::item_structs::MagicItem {
+        class: affixes
+            .iter()
+            .map(|affix| affix.rarity.as_str())
+            .max_by_key(|rarity| rarity_rank(rarity))
+            .unwrap_or("common")
+            .to_string(),
+        naming: format!("Unidentified {}", item.name),
+        cursed: affixes
+            .iter()
+            .any(|affix| affix.cursed.is_some_and(|c| c))
+            .then_some(true),
+    });
+
+    for affix in affixes.iter() {
+        if let Some(weapon) = affixed.weapon.as_mut() {
+            if let Some(hit_bonus) = &affix.hit_bonus {
+                weapon.hit_bonus += hit_bonus.roll(rng);
+            }
+            if let Some(damage_bonus) = &affix.damage_bonus {
+                weapon.base_damage = weapon.base_damage.plus(damage_bonus.roll(rng));
+            }
+            if let Some(proc_effects) = &affix.proc_effects {
+                let chance = affix.proc_chance.unwrap_or(DEFAULT_PROC_CHANCE);
+                weapon.proc_chance = Some(f32::max(weapon.proc_chance.unwrap_or(0.0), chance));
+                weapon.proc_target = affix
+                    .proc_target
+                    .clone()
+                    .or(weapon.proc_target.take())
+                    .or_else(|| Some("Target".to_string()));
+                weapon
+                    .proc_effects
+                    .get_or_insert_with(Vec::new)
+                    .extend(proc_effects.iter().cloned());
+            }
+        }
+        if let Some(wearable) = affixed.wearable.as_mut() {
+            if let Some(armor_class) = &affix.armor_class {
+                wearable.armor_class += armor_class.roll(rng) as f32;
+            }
+        }
+        if let Some(initiative) = &affix.initiative {
+            affixed.initiative_penalty =
+                Some(affixed.initiative_penalty.unwrap_or(0.0) - initiative.roll(rng) as f32);
+        }
+        if let Some(bonus) = &affix.attributes {
+            let attributes = affixed.attributes.get_or_insert(ItemAttributeBonus {
+                might: None,
+                fitness: None,
+                quickness: None,
+                intelligence: None,
+            });
+            attributes.might = add_bonus(attributes.might, bonus.might);
+            attributes.fitness = add_bonus(attributes.fitness, bonus.fitness);
+            attributes.quickness = add_bonus(attributes.quickness, bonus.quickness);
+            attributes.intelligence = add_bonus(attributes.intelligence, bonus.intelligence);
+        }
+        if let Some(base_value) = affixed.base_value.as_mut() {
+            *base_value += affix.value;
+        }
+    }
+
+    affixed
+}
+
+fn add_bonus(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+    match (a, b) {
+        (None, None) => None,
+        _ => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    }
+}
+
 pub fn spawn_named_mob(
     raws: &RawMaster,
     ecs: &mut World,
@@ -516,6 +566,14 @@ pub fn spawn_named_mob(
         return None;
     }
     let mob_template = &raws.raws.mobs[raws.mob_index[key]];
+    let gold = mob_template
+        .gold
+        .as_ref()
+        .map(|gold| {
+            let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+            gold.roll(&mut rng) as f32
+        })
+        .unwrap_or(0.0);
     let mut eb = ecs.create_entity().marked::<SimpleMarker<IsSerialized>>();
 
     // Spawn in the specified location
@@ -640,17 +698,11 @@ pub fn spawn_named_mob(
     }
     if let Some(mobskills) = &mob_template.skills {
         for (skill_name, skill_value) in mobskills.iter() {
-            match skill_name.as_str() {
-                "Melee" => {
-                    skills.skills.insert(Skill::Melee, *skill_value);
+            match string_to_skill(skill_name) {
+                Some(skill) => {
+                    skills.skills.insert(skill, *skill_value);
                 }
-                "Defense" => {
-                    skills.skills.insert(Skill::Defense, *skill_value);
-                }
-                "Magic" => {
-                    skills.skills.insert(Skill::Magic, *skill_value);
-                }
-                _ => {
+                None => {
                     rltk::console::log(format!("Unknown skill referenced: [{}]", skill_name));
                 }
             }
@@ -662,7 +714,6 @@ pub fn spawn_named_mob(
     let mob_hp = npc_hp(mob_fitness, mob_level);
     let mob_mana = mana_at_level(mob_int, mob_level);
 
-    let mut rng = rltk::RandomNumberGenerator::new();
     let pools = Pools {
         level: mob_level,
         xp: 0,
@@ -677,14 +728,7 @@ pub fn spawn_named_mob(
         total_weight: 0.0,
         total_initiative_penalty: 0.0,
         god_mode: false,
-        gold: mob_template
-            .gold
-            .as_ref()
-            .map(|gold| {
-                let (n, d, b) = parse_dice_string(gold);
-                (rng.roll_dice(n, d) + b) as f32
-            })
-            .unwrap_or(0.0),
+        gold,
     };
     eb = eb.with(pools);
 
@@ -695,15 +739,10 @@ pub fn spawn_named_mob(
             .map(|attacks| {
                 attacks
                     .iter()
-                    .map(|nattack| {
-                        let (n, d, b) = parse_dice_string(&nattack.damage);
-                        NaturalAttack {
-                            name: nattack.name.clone(),
-                            hit_bonus: nattack.hit_bonus,
-                            damage_n_dice: n,
-                            damage_die_type: d,
-                            damage_bonus: b,
-                        }
+                    .map(|nattack| NaturalAttack {
+                        name: nattack.name.clone(),
+                        hit_bonus: nattack.hit_bonus,
+                        damage: nattack.damage.clone(),
                     })
                     .collect::<Vec<_>>()
             })
@@ -870,6 +909,12 @@ pub fn spawn_named_prop(
         });
     }
 
+    if let Some(loot_table_name) = &prop_template.loot_table {
+        eb = eb.with(LootTable {
+            name: loot_table_name.clone(),
+        });
+    }
+
     Some(eb.build())
 }
 
@@ -884,12 +929,19 @@ fn get_renderable_component(
     }
 }
 
-pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> MasterTable {
+/// The spawn table for a level at `depth` with the given `tags`.
+pub fn get_spawn_table_for_level(
+    raws: &RawMaster,
+    depth: i32,
+    tags: &HashSet<String>,
+) -> MasterTable {
     let available_options: Vec<&SpawnTableEntry> = raws
         .raws
         .spawn_table
         .iter()
         .filter(|a| depth >= a.min_depth && depth <= a.max_depth)
+        .filter(|a| a.require_tags.iter().all(|tag| tags.contains(tag)))
+        .filter(|a| !a.exclude_tags.iter().any(|tag| tags.contains(tag)))
         .collect();
 
     let mut mt = MasterTable::new();
@@ -904,26 +956,65 @@ pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> MasterTable {
     mt
 }
 
-pub fn parse_dice_string(dice: &str) -> (i32, i32, i32) {
-    lazy_static! {
-        static ref DICE_RE: Regex =
-            Regex::new(r"(\d+)d(\d+)([\+\-]\d+)?").expect("Could not create dice parsing regex.");
-    }
-    let mut n_dice = 1;
-    let mut die_type = 4;
-    let mut die_bonus = 0;
-    for capture in DICE_RE.captures_iter(dice) {
-        if let Some(group) = capture.get(1) {
-            n_dice = group.as_str().parse::<i32>().expect("Not a digit");
-        }
-        if let Some(group) = capture.get(2) {
-            die_type = group.as_str().parse::<i32>().expect("Not a digit");
-        }
-        if let Some(group) = capture.get(3) {
-            die_bonus = group.as_str().parse::<i32>().expect("Not a digit");
-        }
-    }
-    (n_dice, die_type, die_bonus)
+/// The item set named `name`.
+pub fn get_item_set<'a>(raws: &'a RawMaster, name: &str) -> Option<&'a ItemSet> {
+    let index = raws.item_set_index.get(name)?;
+    Some(&raws.raws.item_sets[*index])
+}
+
+/// Spawns an entity carrying the effects of the `bonus`th bonus of `set`, for the effects
+/// system to trigger. It isn't saved, so delete it once it has been used.
+pub fn spawn_item_set_effects(
+    raws: &RawMaster,
+    ecs: &mut World,
+    set: &str,
+    bonus: usize,
+) -> Option<Entity> {
+    let item_set = get_item_set(raws, set)?;
+    let effects = item_set.bonuses.get(bonus)?.effects.as_ref()?;
+
+    let mut eb = ecs.create_entity();
+    eb = eb.with(Name {
+        name: item_set.name.clone(),
+    });
+    apply_effects!(effects, eb);
+    Some(eb.build())
+}
+
+/// The level at `depth`. Depths past the deepest level repeat it.
+pub fn get_level(raws: &RawMaster, depth: i32) -> &Level {
+    let levels = &raws.raws.levels;
+    levels
+        .iter()
+        .find(|level| depth >= level.min_depth && depth <= level.max_depth)
+        .or_else(|| levels.iter().max_by_key(|level| level.max_depth))
+        .expect("The raws have no levels")
+}
+
+/// The builder chain recipe named `name`.
+pub fn get_builder_recipe<'a>(raws: &'a RawMaster, name: &str) -> Option<&'a BuilderRecipe> {
+    let index = raws.builder_recipe_index.get(name)?;
+    Some(&raws.raws.builder_chains[*index])
+}
+
+/// The prefab named `name`, of any kind.
+pub fn get_prefab<'a>(raws: &'a RawMaster, name: &str) -> Option<&'a Prefab> {
+    let index = raws.prefab_index.get(name)?;
+    Some(&raws.raws.prefabs[*index])
+}
+
+/// The theme named `name`, or the default theme if there is none by that name.
+pub fn get_theme<'a>(raws: &'a RawMaster, name: &str) -> &'a DrawTheme {
+    raws.themes
+        .get(name)
+        .or_else(|| raws.themes.get(DEFAULT_THEME))
+        .expect("The raws have no default theme")
+}
+
+/// The group spawned by the spawn table entry `key`, if that entry describes one.
+pub fn get_spawn_group<'a>(raws: &'a RawMaster, key: &str) -> Option<&'a SpawnGroup> {
+    let index = raws.group_index.get(key)?;
+    raws.raws.spawn_table[*index].group.as_ref()
 }
 
 fn find_slot_for_equippable_item(tag: &str, raws: &RawMaster) -> EquipmentSlot {
@@ -940,6 +1031,15 @@ fn find_slot_for_equippable_item(tag: &str, raws: &RawMaster) -> EquipmentSlot {
     panic!("Trying to equip {}, but it has no slot tag.", tag);
 }
 
+pub fn string_to_skill(skill: &str) -> Option<Skill> {
+    match skill {
+        "Melee" => Some(Skill::Melee),
+        "Defense" => Some(Skill::Defense),
+        "Magic" => Some(Skill::Magic),
+        _ => None,
+    }
+}
+
 pub fn string_to_slot(slot: &str) -> EquipmentSlot {
     match slot {
         "Shield" => EquipmentSlot::Shield,
@@ -956,25 +1056,83 @@ pub fn string_to_slot(slot: &str) -> EquipmentSlot {
     }
 }
 
-/// Check if table with name exists, and return None if it doesn't.
-/// If it does exist, make a table of names and weights from the raw file information
-/// Then roll to determine a randomly weighted result to return
-pub fn get_item_drop(
+/// Something rolled from a loot table.
+pub enum Loot {
+    Item(String),
+    Gold(i32),
+}
+
+/// Tables nested deeper than this are skipped, so a table that contains itself still ends.
+const MAX_LOOT_NESTING: i32 = 8;
+
+/// Rolls the loot table `table`, including any tables it refers to.
+pub fn roll_loot(
     raws: &RawMaster,
     rng: &mut rltk::RandomNumberGenerator,
     table: &str,
-) -> Option<String> {
-    if !raws.loot_index.contains_key(table) {
-        return None;
+) -> Vec<Loot> {
+    let mut loot = Vec::new();
+    roll_loot_table(raws, rng, table, 0, &mut loot);
+    loot
+}
+
+fn roll_loot_table(
+    raws: &RawMaster,
+    rng: &mut rltk::RandomNumberGenerator,
+    table: &str,
+    nesting: i32,
+    loot: &mut Vec<Loot>,
+) {
+    let Some(index) = raws.loot_index.get(table) else {
+        return;
+    };
+    if nesting > MAX_LOOT_NESTING {
+        return;
+    }
+    let table = &raws.raws.loot_tables[*index];
+
+    for drop in table.guaranteed.iter() {
+        roll_loot_drop(raws, rng, drop, nesting, loot);
     }
 
-    let mut rt = RandomTable::new();
-    let available_options = &raws.raws.loot_tables[raws.loot_index[table]];
-    for item in available_options.drops.iter() {
-        rt.add(item.name.clone(), item.weight);
+    let total_weight: i32 = table.drops.iter().map(|d| d.weight).sum();
+    if total_weight < 1 {
+        return;
+    }
+    for _ in 0..table.picks.roll(rng) {
+        let mut roll = rng.roll_dice(1, total_weight) - 1;
+        for drop in table.drops.iter() {
+            if roll < drop.weight {
+                roll_loot_drop(raws, rng, drop, nesting, loot);
+                break;
+            }
+            roll -= drop.weight;
+        }
+    }
+}
+
+fn roll_loot_drop(
+    raws: &RawMaster,
+    rng: &mut rltk::RandomNumberGenerator,
+    drop: &LootDrop,
+    nesting: i32,
+    loot: &mut Vec<Loot>,
+) {
+    let quantity = drop.quantity.as_ref().map_or(1, |q| q.roll(rng));
+    for _ in 0..quantity {
+        if let Some(name) = &drop.name {
+            loot.push(Loot::Item(name.clone()));
+        }
+        if let Some(table) = &drop.table {
+            roll_loot_table(raws, rng, table, nesting + 1, loot);
+        }
+        if let Some(gold) = &drop.gold {
+            let amount = gold.roll(rng);
+            if amount > 0 {
+                loot.push(Loot::Gold(amount));
+            }
+        }
     }
-    let result = rt.roll(rng);
-    Some(result)
 }
 
 pub fn faction_reaction(my_faction: &str, their_faction: &str, raws: &RawMaster) -> Reaction {
@@ -1003,8 +1161,7 @@ pub fn get_vendor_items(categories: &[String], raws: &RawMaster) -> Vec<(String,
         .collect::<Vec<_>>()
 }
 
-pub fn get_scroll_tags() -> Vec<String> {
-    let raws = &RAWS.lock().unwrap();
+pub fn get_scroll_tags(raws: &RawMaster) -> Vec<String> {
     let mut result = Vec::new();
 
     for item in raws.raws.items.iter() {
@@ -1020,8 +1177,7 @@ pub fn get_scroll_tags() -> Vec<String> {
     result
 }
 
-pub fn get_potion_tags() -> Vec<String> {
-    let raws = &RAWS.lock().unwrap();
+pub fn get_potion_tags(raws: &RawMaster) -> Vec<String> {
     let mut result = Vec::new();
 
     for item in raws.raws.items.iter() {
@@ -1037,8 +1193,7 @@ pub fn get_potion_tags() -> Vec<String> {
     result
 }
 
-pub fn is_tag_magic(tag: &str) -> bool {
-    let raws = &RAWS.lock().unwrap();
+pub fn is_tag_magic(tag: &str, raws: &RawMaster) -> bool {
     if raws.item_index.contains_key(tag) {
         let item_template = &raws.raws.items[raws.item_index[tag]];
         item_template.magic.is_some()
@@ -1047,24 +1202,6 @@ pub fn is_tag_magic(tag: &str) -> bool {
     }
 }
 
-fn parse_particle_line(n: &str) -> SpawnParticleLine {
-    let tokens: Vec<_> = n.split(';').collect();
-    SpawnParticleLine {
-        glyph: rltk::to_cp437(tokens[0].chars().next().unwrap()),
-        color: rltk::RGB::from_hex(tokens[1]).expect("Bad RGB"),
-        lifetime_ms: tokens[2].parse::<f32>().unwrap(),
-    }
-}
-
-fn parse_particle(n: &str) -> SpawnParticleBurst {
-    let tokens: Vec<_> = n.split(';').collect();
-    SpawnParticleBurst {
-        glyph: rltk::to_cp437(tokens[0].chars().next().unwrap()),
-        color: rltk::RGB::from_hex(tokens[1]).expect("Bad RGB"),
-        lifetime_ms: tokens[2].parse::<f32>().unwrap(),
-    }
-}
-
 pub fn find_spell_entity(ecs: &World, name: &str) -> Option<Entity> {
     let names = ecs.read_storage::<Name>();
     let spell_templates = ecs.read_storage::<SpellTemplate>();
@@ -1102,7 +1239,7 @@ pub enum SpawnTableType {
 pub fn spawn_type_by_name(raws: &RawMaster, key: &str) -> SpawnTableType {
     if raws.item_index.contains_key(key) {
         SpawnTableType::Item
-    } else if raws.mob_index.contains_key(key) {
+    } else if raws.mob_index.contains_key(key) || raws.group_index.contains_key(key) {
         SpawnTableType::Mob
     } else {
         SpawnTableType::Prop
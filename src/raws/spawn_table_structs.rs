@@ -1,3 +1,4 @@
+use crate::dice::DiceExpr;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -7,4 +8,26 @@ pub struct SpawnTableEntry {
     pub min_depth: i32,
     pub max_depth: i32,
     pub add_map_depth_to_weight: Option<bool>,
+    /// Level tags the entry only spawns with, all of them.
+    #[serde(default)]
+    pub require_tags: Vec<String>,
+    /// Level tags the entry never spawns with, any of them.
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+    pub group: Option<SpawnGroup>,
+}
+
+/// An encounter spawned together in place of a single entity. The entry's name only identifies
+/// the group; the leader and members name the mobs to spawn.
+#[derive(Deserialize, Debug)]
+pub struct SpawnGroup {
+    pub leader: Option<String>,
+    #[serde(default)]
+    pub members: Vec<GroupMember>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GroupMember {
+    pub name: String,
+    pub count: DiceExpr,
 }
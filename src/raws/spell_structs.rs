@@ -1,9 +1,9 @@
+use super::effect_structs::Effect;
 use serde::Deserialize;
-use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
 pub struct Spell {
     pub name: String,
-    pub effects: HashMap<String, String>,
+    pub effects: Vec<Effect>,
     pub mana_cost: i32,
 }
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::map::TileType;
+use serde::Deserialize;
+
+/// How a level's tiles are drawn. Levels pick their theme by name.
+#[derive(Deserialize, Debug)]
+pub struct Theme {
+    pub name: String,
+    /// Theme to take anything this one leaves out from.
+    pub base: Option<String>,
+    #[serde(default)]
+    pub tiles: HashMap<TileType, TileLook>,
+    /// Drawn for the tile types missing from `tiles`, before looking at `base`.
+    pub fallback: Option<TileLook>,
+    /// Background of bloodstained tiles in sight. Defaults to dark red.
+    pub bloodstain: Option<String>,
+    /// How remembered tiles out of sight are drawn.
+    pub out_of_sight: Option<OutOfSight>,
+    /// Strips of the map drawn with the tiles of another theme.
+    #[serde(default)]
+    pub regions: Vec<ThemeRegion>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TileLook {
+    pub glyph: String,
+    /// Drawn in place of `glyph` on some tiles. Each tile always gets the same one.
+    #[serde(default)]
+    pub variants: Vec<String>,
+    /// Sixteen glyphs to pick from by which neighbours are revealed tiles of the same type:
+    /// 1 for north, 2 for south, 4 for west and 8 for east, added up. Tiles on the edge of the
+    /// map use `glyph`.
+    pub mask_glyphs: Option<String>,
+    pub fg: String,
+    #[serde(default = "black")]
+    pub bg: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct OutOfSight {
+    pub greyscale: bool,
+    /// Multiplied into the foreground, after it is greyed.
+    pub tint: Option<String>,
+    pub bg: String,
+}
+
+impl Default for OutOfSight {
+    fn default() -> Self {
+        OutOfSight {
+            greyscale: true,
+            tint: None,
+            bg: black(),
+        }
+    }
+}
+
+/// Tiles from `from_x` up to `to_x`, in fractions of the map's width, use `theme`'s tiles.
+#[derive(Deserialize, Debug)]
+pub struct ThemeRegion {
+    pub theme: String,
+    #[serde(default)]
+    pub from_x: f32,
+    #[serde(default = "full_width")]
+    pub to_x: f32,
+}
+
+fn black() -> String {
+    "#000000".to_string()
+}
+
+fn full_width() -> f32 {
+    1.0
+}
@@ -0,0 +1,993 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::map::{themes::DEFAULT_THEME, TILE_TYPES};
+
+use super::{
+    builder_chain_structs::{InitialBuilder, MetaBuilder, PrefabChoice, RecipeStep},
+    effect_structs::Effect,
+    item_structs::Renderable,
+    loader,
+    loot_structs::LootDrop,
+    mob_structs::MobAbility,
+    prefab_structs::PrefabKind,
+    rawsmaster::string_to_skill,
+    theme_structs::{Theme, TileLook},
+    Raws,
+};
+
+/// A problem found in the raws. `path` points at the offending value in the JSON, e.g.
+/// `$.mobs[3].abilities[0].spell`, after the file it is in when there is more than one.
+#[derive(Debug)]
+pub struct RawError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Passes `raws` through if they validate, or returns every problem found.
+pub fn validated(raws: Raws) -> Result<Raws, Vec<RawError>> {
+    let errors = validate_raws(&raws);
+    if errors.is_empty() {
+        Ok(raws)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks everything in `raws` that would otherwise only fail, or be quietly ignored, once it
+/// is spawned: duplicate names, references to things that don't exist, and malformed dice,
+/// numbers and colors. Effects are typed, so serde has already rejected unknown ones. Problems
+/// are returned in file order.
+pub fn validate_raws(raws: &Raws) -> Vec<RawError> {
+    let mut validator = Validator::new(raws);
+    validator.check_names();
+    validator.check_spawn_table();
+    validator.check_loot_tables();
+    validator.check_factions();
+    validator.check_items();
+    validator.check_mobs();
+    validator.check_props();
+    validator.check_spells();
+    validator.check_affixes();
+    validator.check_item_sets();
+    validator.check_levels();
+    validator.check_builder_chains();
+    validator.check_themes();
+    validator.check_prefabs();
+    validator.errors
+}
+
+struct Validator<'a> {
+    raws: &'a Raws,
+    items: HashSet<String>,
+    /// Everything a spawn table can name: items, mobs and props.
+    entities: HashSet<String>,
+    spells: HashSet<&'a str>,
+    loot_tables: HashSet<&'a str>,
+    factions: HashSet<&'a str>,
+    /// Every tag a level has, for spawn tables to require or exclude.
+    level_tags: HashSet<&'a str>,
+    /// The steps of each builder chain recipe, by name.
+    recipes: HashMap<&'a str, &'a [RecipeStep]>,
+    errors: Vec<RawError>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(raws: &'a Raws) -> Self {
+        let items: HashSet<String> = raws.items.iter().map(|i| i.name.clone()).collect();
+        let entities = items
+            .iter()
+            .cloned()
+            .chain(raws.mobs.iter().map(|m| m.name.clone()))
+            .chain(raws.props.iter().map(|p| p.name.clone()))
+            .collect();
+
+        Validator {
+            raws,
+            items,
+            entities,
+            spells: raws.spells.iter().map(|s| s.name.as_str()).collect(),
+            loot_tables: raws.loot_tables.iter().map(|l| l.name.as_str()).collect(),
+            factions: raws.faction_table.iter().map(|f| f.name.as_str()).collect(),
+            level_tags: raws
+                .levels
+                .iter()
+                .flat_map(|l| l.tags.iter().map(|t| t.as_str()))
+                .collect(),
+            recipes: raws
+                .builder_chains
+                .iter()
+                .map(|r| (r.name.as_str(), r.steps.as_slice()))
+                .collect(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Path to entry `index` of `section`, prefixed with the file it came from when the raws
+    /// were loaded from a directory.
+    fn entry(&self, section: &str, index: usize) -> String {
+        self.raws
+            .sources
+            .get(section)
+            .and_then(|sources| sources.get(index))
+            .cloned()
+            .unwrap_or_else(|| format!("$.{}[{}]", section, index))
+    }
+
+    fn error(&mut self, path: String, message: String) {
+        self.errors.push(RawError { path, message });
+    }
+
+    /// Items, mobs and props are spawned by name, so they share one namespace.
+    fn check_names(&mut self) {
+        let raws = self.raws;
+        let mut entity_names = HashSet::new();
+        let entities = raws
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (format!("{}.name", self.entry("items", i)), &item.name))
+            .chain(
+                raws.mobs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, mob)| (format!("{}.name", self.entry("mobs", i)), &mob.name)),
+            )
+            .chain(
+                raws.props
+                    .iter()
+                    .enumerate()
+                    .map(|(i, prop)| (format!("{}.name", self.entry("props", i)), &prop.name)),
+            )
+            .collect::<Vec<_>>();
+        for (path, name) in entities {
+            if !entity_names.insert(name) {
+                self.error(path, format!("Duplicate name [{}]", name));
+            }
+        }
+
+        let mut check_unique = |section: &str, names: Vec<&String>| {
+            let mut seen = HashSet::new();
+            for (i, name) in names.into_iter().enumerate() {
+                if !seen.insert(name) {
+                    self.error(
+                        format!("{}.name", self.entry(section, i)),
+                        format!("Duplicate name [{}]", name),
+                    );
+                }
+            }
+        };
+        check_unique("spells", raws.spells.iter().map(|s| &s.name).collect());
+        check_unique(
+            "loot_tables",
+            raws.loot_tables.iter().map(|l| &l.name).collect(),
+        );
+        check_unique(
+            "faction_table",
+            raws.faction_table.iter().map(|f| &f.name).collect(),
+        );
+        check_unique("affixes", raws.affixes.iter().map(|a| &a.name).collect());
+        check_unique(
+            "item_sets",
+            raws.item_sets.iter().map(|s| &s.name).collect(),
+        );
+        check_unique("levels", raws.levels.iter().map(|l| &l.name).collect());
+        check_unique(
+            "builder_chains",
+            raws.builder_chains.iter().map(|r| &r.name).collect(),
+        );
+        check_unique("themes", raws.themes.iter().map(|t| &t.name).collect());
+    }
+
+    fn check_spawn_table(&mut self) {
+        let raws = self.raws;
+        let mobs: HashSet<&str> = raws.mobs.iter().map(|m| m.name.as_str()).collect();
+        for (i, entry) in raws.spawn_table.iter().enumerate() {
+            match &entry.group {
+                Some(group) => {
+                    let path = format!("{}.group", self.entry("spawn_table", i));
+                    if self.entities.contains(&entry.name) {
+                        self.error(
+                            format!("{}.name", self.entry("spawn_table", i)),
+                            format!(
+                                "Group [{}] has the name of an item, mob or prop",
+                                entry.name
+                            ),
+                        );
+                    }
+                    if group.leader.is_none() && group.members.is_empty() {
+                        self.error(path.clone(), "Group has no leader or members".to_string());
+                    }
+                    if let Some(leader) = &group.leader {
+                        if !mobs.contains(leader.as_str()) {
+                            self.error(
+                                format!("{}.leader", path),
+                                format!("Unknown mob [{}]", leader),
+                            );
+                        }
+                    }
+                    for (j, member) in group.members.iter().enumerate() {
+                        if !mobs.contains(member.name.as_str()) {
+                            self.error(
+                                format!("{}.members[{}].name", path, j),
+                                format!("Unknown mob [{}]", member.name),
+                            );
+                        }
+                    }
+                }
+                None => {
+                    if !self.entities.contains(&entry.name) {
+                        self.error(
+                            format!("{}.name", self.entry("spawn_table", i)),
+                            format!("Unknown item, mob or prop [{}]", entry.name),
+                        );
+                    }
+                }
+            }
+            for (field, tags) in [
+                ("require_tags", &entry.require_tags),
+                ("exclude_tags", &entry.exclude_tags),
+            ] {
+                for (j, tag) in tags.iter().enumerate() {
+                    if !self.level_tags.contains(tag.as_str()) {
+                        self.error(
+                            format!("{}.{}[{}]", self.entry("spawn_table", i), field, j),
+                            format!("Unknown level tag [{}]", tag),
+                        );
+                    }
+                }
+            }
+            if entry.min_depth > entry.max_depth {
+                self.error(
+                    format!("{}.min_depth", self.entry("spawn_table", i)),
+                    format!(
+                        "min_depth {} is deeper than max_depth {}",
+                        entry.min_depth, entry.max_depth
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_loot_tables(&mut self) {
+        let raws = self.raws;
+        for (i, table) in raws.loot_tables.iter().enumerate() {
+            let path = self.entry("loot_tables", i);
+            for (j, drop) in table.guaranteed.iter().enumerate() {
+                self.check_loot_drop(format!("{}.guaranteed[{}]", path, j), drop);
+            }
+            for (j, drop) in table.drops.iter().enumerate() {
+                let drop_path = format!("{}.drops[{}]", path, j);
+                if drop.weight < 1 {
+                    self.error(
+                        format!("{}.weight", drop_path),
+                        format!("Weight must be positive, got {}", drop.weight),
+                    );
+                }
+                self.check_loot_drop(drop_path, drop);
+            }
+        }
+    }
+
+    fn check_loot_drop(&mut self, path: String, drop: &LootDrop) {
+        let kinds = [
+            drop.name.is_some(),
+            drop.table.is_some(),
+            drop.gold.is_some(),
+        ];
+        if kinds.iter().filter(|k| **k).count() > 1 {
+            self.error(
+                path.clone(),
+                "Drop names more than one of name, table and gold".to_string(),
+            );
+        }
+        if let Some(name) = &drop.name {
+            if !self.items.contains(name) {
+                self.error(format!("{}.name", path), format!("Unknown item [{}]", name));
+            }
+        }
+        if let Some(table) = &drop.table {
+            if !self.loot_tables.contains(table.as_str()) {
+                self.error(
+                    format!("{}.table", path),
+                    format!("Unknown loot table [{}]", table),
+                );
+            }
+        }
+    }
+
+    fn check_factions(&mut self) {
+        let raws = self.raws;
+        for (i, faction) in raws.faction_table.iter().enumerate() {
+            let mut others: Vec<_> = faction.responses.iter().collect();
+            others.sort();
+            for (other, response) in others {
+                let path = format!("{}.responses.{}", self.entry("faction_table", i), other);
+                if other != "Default" && !self.factions.contains(other.as_str()) {
+                    self.error(path.clone(), format!("Unknown faction [{}]", other));
+                }
+                if !matches!(response.as_str(), "ignore" | "flee" | "attack") {
+                    self.error(
+                        path,
+                        format!(
+                            "Unknown response [{}], expected ignore, flee or attack",
+                            response
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_items(&mut self) {
+        let raws = self.raws;
+        for (i, item) in raws.items.iter().enumerate() {
+            let path = self.entry("items", i);
+            if let Some(renderable) = &item.renderable {
+                self.check_renderable(&path, renderable);
+            }
+            if let Some(consumable) = &item.consumable {
+                self.check_effects(&format!("{}.consumable.effects", path), &consumable.effects);
+            }
+            if let Some(weapon) = &item.weapon {
+                if weapon.range != "melee" && weapon.range.parse::<i32>().is_err() {
+                    self.error(
+                        format!("{}.weapon.range", path),
+                        format!("Expected \"melee\" or a number, found [{}]", weapon.range),
+                    );
+                }
+                if let Some(proc_effects) = &weapon.proc_effects {
+                    self.check_effects(&format!("{}.weapon.proc_effects", path), proc_effects);
+                }
+            }
+            if let Some(wearable) = &item.wearable {
+                if !matches!(
+                    wearable.slot.as_str(),
+                    "Shield" | "Head" | "Torso" | "Legs" | "Feet" | "Hands" | "Melee"
+                ) {
+                    self.error(
+                        format!("{}.wearable.slot", path),
+                        format!("Unknown equipment slot [{}]", wearable.slot),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_mobs(&mut self) {
+        let raws = self.raws;
+        for (i, mob) in raws.mobs.iter().enumerate() {
+            let path = self.entry("mobs", i);
+            if let Some(renderable) = &mob.renderable {
+                self.check_renderable(&path, renderable);
+            }
+            if let Some(loot_table) = &mob.loot_table {
+                if !self.loot_tables.contains(loot_table.as_str()) {
+                    self.error(
+                        format!("{}.loot_table", path),
+                        format!("Unknown loot table [{}]", loot_table),
+                    );
+                }
+            }
+            if let Some(faction) = &mob.faction {
+                if !self.factions.contains(faction.as_str()) {
+                    self.error(
+                        format!("{}.faction", path),
+                        format!("Unknown faction [{}]", faction),
+                    );
+                }
+            }
+            if let Some(light) = &mob.light {
+                self.check_color(format!("{}.light.color", path), &light.color);
+            }
+            if let Some(equipped) = &mob.equipped {
+                for (j, tag) in equipped.iter().enumerate() {
+                    self.check_equippable(format!("{}.equipped[{}]", path, j), tag);
+                }
+            }
+            if let Some(abilities) = &mob.abilities {
+                self.check_abilities(&format!("{}.abilities", path), abilities);
+            }
+            if let Some(on_death) = &mob.on_death {
+                self.check_abilities(&format!("{}.on_death", path), on_death);
+            }
+        }
+    }
+
+    fn check_props(&mut self) {
+        let raws = self.raws;
+        for (i, prop) in raws.props.iter().enumerate() {
+            let path = self.entry("props", i);
+            if let Some(renderable) = &prop.renderable {
+                self.check_renderable(&path, renderable);
+            }
+            if let Some(trigger) = &prop.entry_trigger {
+                self.check_effects(&format!("{}.entry_trigger.effects", path), &trigger.effects);
+            }
+            if let Some(light) = &prop.light {
+                self.check_color(format!("{}.light.color", path), &light.color);
+            }
+            if let Some(loot_table) = &prop.loot_table {
+                if !self.loot_tables.contains(loot_table.as_str()) {
+                    self.error(
+                        format!("{}.loot_table", path),
+                        format!("Unknown loot table [{}]", loot_table),
+                    );
+                }
+                if prop.entry_trigger.is_none() {
+                    self.error(
+                        format!("{}.loot_table", path),
+                        "Loot is only dropped by props with an entry_trigger".to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_spells(&mut self) {
+        let raws = self.raws;
+        for (i, spell) in raws.spells.iter().enumerate() {
+            self.check_effects(
+                &format!("{}.effects", self.entry("spells", i)),
+                &spell.effects,
+            );
+        }
+    }
+
+    fn check_affixes(&mut self) {
+        let raws = self.raws;
+        for (i, affix) in raws.affixes.iter().enumerate() {
+            let path = self.entry("affixes", i);
+            if !matches!(affix.rarity.as_str(), "common" | "rare" | "legendary") {
+                self.error(
+                    format!("{}.rarity", path),
+                    format!("Unknown rarity [{}]", affix.rarity),
+                );
+            }
+            for (j, kind) in affix.applies_to.iter().enumerate() {
+                if !matches!(kind.as_str(), "weapon" | "wearable") {
+                    self.error(
+                        format!("{}.applies_to[{}]", path, j),
+                        format!("Expected \"weapon\" or \"wearable\", found [{}]", kind),
+                    );
+                }
+            }
+            if affix.weight < 1 {
+                self.error(
+                    format!("{}.weight", path),
+                    format!("Weight must be positive, got {}", affix.weight),
+                );
+            }
+            if affix.min_depth > affix.max_depth {
+                self.error(
+                    format!("{}.min_depth", path),
+                    format!(
+                        "min_depth {} is deeper than max_depth {}",
+                        affix.min_depth, affix.max_depth
+                    ),
+                );
+            }
+            if let Some(proc_target) = &affix.proc_target {
+                if !matches!(proc_target.as_str(), "Self" | "Target") {
+                    self.error(
+                        format!("{}.proc_target", path),
+                        format!("Expected \"Self\" or \"Target\", found [{}]", proc_target),
+                    );
+                }
+            }
+            if let Some(proc_effects) = &affix.proc_effects {
+                self.check_effects(&format!("{}.proc_effects", path), proc_effects);
+            }
+        }
+    }
+
+    fn check_item_sets(&mut self) {
+        let raws = self.raws;
+        let mut set_pieces = HashSet::new();
+        for (i, item_set) in raws.item_sets.iter().enumerate() {
+            let path = self.entry("item_sets", i);
+            for (j, piece) in item_set.pieces.iter().enumerate() {
+                let piece_path = format!("{}.pieces[{}]", path, j);
+                if !set_pieces.insert(piece) {
+                    self.error(
+                        piece_path.clone(),
+                        format!("{} is already a piece of a set", piece),
+                    );
+                }
+                self.check_equippable(piece_path, piece);
+            }
+            for (j, bonus) in item_set.bonuses.iter().enumerate() {
+                let bonus_path = format!("{}.bonuses[{}]", path, j);
+                if bonus.pieces < 1 || bonus.pieces as usize > item_set.pieces.len() {
+                    self.error(
+                        format!("{}.pieces", bonus_path),
+                        format!(
+                            "Needs {} pieces, but the set has {}",
+                            bonus.pieces,
+                            item_set.pieces.len()
+                        ),
+                    );
+                }
+                for skill in bonus.skills.iter().flat_map(|skills| skills.keys()) {
+                    if string_to_skill(skill).is_none() {
+                        self.error(
+                            format!("{}.skills.{}", bonus_path, skill),
+                            format!("Unknown skill [{}]", skill),
+                        );
+                    }
+                }
+                if let Some(effects) = &bonus.effects {
+                    self.check_effects(&format!("{}.effects", bonus_path), effects);
+                }
+            }
+        }
+    }
+
+    fn check_levels(&mut self) {
+        let raws = self.raws;
+        if raws.levels.is_empty() {
+            self.error("$.levels".to_string(), "There are no levels".to_string());
+            return;
+        }
+        for (i, level) in raws.levels.iter().enumerate() {
+            let path = self.entry("levels", i);
+            if level.min_depth < 1 || level.min_depth > level.max_depth {
+                self.error(
+                    format!("{}.min_depth", path),
+                    format!(
+                        "Depths {} to {} aren't a range of levels",
+                        level.min_depth, level.max_depth
+                    ),
+                );
+            }
+            for other in raws.levels[..i].iter() {
+                if level.min_depth <= other.max_depth && other.min_depth <= level.max_depth {
+                    self.error(
+                        format!("{}.min_depth", path),
+                        format!("Overlaps the depths of [{}]", other.name),
+                    );
+                }
+            }
+            match self.recipes.get(level.builder.as_str()) {
+                Some(&steps) => {
+                    let (fewest, most) =
+                        self.start_counts(steps, &mut vec![level.builder.as_str()]);
+                    if fewest != 1 || most != 1 {
+                        self.error(
+                            format!("{}.builder", path),
+                            format!(
+                                "Builder chain [{}] doesn't always start with exactly one builder",
+                                level.builder
+                            ),
+                        );
+                    }
+                }
+                None => self.error(
+                    format!("{}.builder", path),
+                    format!("Unknown builder chain [{}]", level.builder),
+                ),
+            }
+            if !raws.themes.iter().any(|t| t.name == level.theme) {
+                self.error(
+                    format!("{}.theme", path),
+                    format!("Unknown theme [{}]", level.theme),
+                );
+            }
+            if level.width < 1 || level.height < 1 {
+                self.error(
+                    format!("{}.width", path),
+                    format!("A {}x{} map has no room", level.width, level.height),
+                );
+            }
+        }
+
+        let deepest = raws.levels.iter().map(|l| l.max_depth).max().unwrap_or(0);
+        let missing: Vec<String> = (1..=deepest)
+            .filter(|depth| {
+                !raws
+                    .levels
+                    .iter()
+                    .any(|l| *depth >= l.min_depth && *depth <= l.max_depth)
+            })
+            .map(|depth| depth.to_string())
+            .collect();
+        if !missing.is_empty() {
+            self.error(
+                "$.levels".to_string(),
+                format!("No level at depth {}", missing.join(", ")),
+            );
+        }
+    }
+
+    fn check_builder_chains(&mut self) {
+        let raws = self.raws;
+        for (i, recipe) in raws.builder_chains.iter().enumerate() {
+            let path = format!("{}.steps", self.entry("builder_chains", i));
+            self.check_recipe_steps(&path, &recipe.steps);
+            if self.includes_recipe(&recipe.steps, &recipe.name, &mut HashSet::new()) {
+                self.error(path, format!("[{}] includes itself", recipe.name));
+            }
+        }
+    }
+
+    fn check_recipe_steps(&mut self, path: &str, steps: &[RecipeStep]) {
+        for (i, step) in steps.iter().enumerate() {
+            let path = format!("{}[{}]", path, i);
+            match step {
+                RecipeStep::Start(InitialBuilder::Prefab(choice)) => {
+                    self.check_prefab_choice(format!("{}.start.prefab", path), choice);
+                }
+                RecipeStep::With(MetaBuilder::Prefab(choice)) => {
+                    self.check_prefab_choice(format!("{}.with.prefab", path), choice);
+                }
+                RecipeStep::OneOf(choices) => {
+                    if choices.is_empty() {
+                        self.error(path.clone(), "Nothing to choose from".to_string());
+                    }
+                    for (j, choice) in choices.iter().enumerate() {
+                        let choice_path = format!("{}.one_of[{}]", path, j);
+                        if choice.weight < 1 {
+                            self.error(
+                                format!("{}.weight", choice_path),
+                                format!("Weight must be positive, got {}", choice.weight),
+                            );
+                        }
+                        self.check_recipe_steps(&format!("{}.steps", choice_path), &choice.steps);
+                    }
+                }
+                RecipeStep::Recipe(name) => {
+                    if !self.recipes.contains_key(name.as_str()) {
+                        self.error(
+                            format!("{}.recipe", path),
+                            format!("Unknown builder chain [{}]", name),
+                        );
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn check_prefab_choice(&mut self, path: String, choice: &PrefabChoice) {
+        let (path, name, kind) = match choice {
+            PrefabChoice::Level(name) => (format!("{}.level", path), name, PrefabKind::Level),
+            PrefabChoice::Section(name) => (format!("{}.section", path), name, PrefabKind::Section),
+            PrefabChoice::Vaults => return,
+        };
+        match self.raws.prefabs.iter().find(|prefab| prefab.name == *name) {
+            None => self.error(path, format!("Unknown prefab [{}]", name)),
+            Some(prefab) if prefab.kind != kind => self.error(
+                path,
+                format!("[{}] is a {:?}, not a {:?}", name, prefab.kind, kind),
+            ),
+            _ => (),
+        }
+    }
+
+    /// Whether `steps` apply the recipe `name`, directly or through other recipes.
+    fn includes_recipe(
+        &self,
+        steps: &'a [RecipeStep],
+        name: &str,
+        seen: &mut HashSet<&'a str>,
+    ) -> bool {
+        steps.iter().any(|step| match step {
+            RecipeStep::OneOf(choices) => choices
+                .iter()
+                .any(|choice| self.includes_recipe(&choice.steps, name, seen)),
+            RecipeStep::Recipe(included) => {
+                included == name
+                    || (seen.insert(included.as_str())
+                        && self
+                            .recipes
+                            .get(included.as_str())
+                            .copied()
+                            .is_some_and(|steps| self.includes_recipe(steps, name, seen)))
+            }
+            _ => false,
+        })
+    }
+
+    /// The fewest and the most initial builders `steps` can start a chain with. Recipes
+    /// already being counted in `including` add none; `check_builder_chains` reports them.
+    fn start_counts(&self, steps: &'a [RecipeStep], including: &mut Vec<&'a str>) -> (u32, u32) {
+        let mut counts = (0, 0);
+        for step in steps {
+            let (fewest, most) = match step {
+                RecipeStep::Start(_) => (1, 1),
+                RecipeStep::With(_) => (0, 0),
+                RecipeStep::OneOf(choices) => {
+                    let mut range: Option<(u32, u32)> = None;
+                    for choice in choices {
+                        let (f, m) = self.start_counts(&choice.steps, including);
+                        range = Some(match range {
+                            Some((lo, hi)) => (lo.min(f), hi.max(m)),
+                            None => (f, m),
+                        });
+                    }
+                    range.unwrap_or((0, 0))
+                }
+                RecipeStep::Recipe(name) => match self.recipes.get(name.as_str()) {
+                    Some(&included) if !including.contains(&name.as_str()) => {
+                        including.push(name.as_str());
+                        let counts = self.start_counts(included, including);
+                        including.pop();
+                        counts
+                    }
+                    _ => (0, 0),
+                },
+            };
+            counts = (counts.0 + fewest, counts.1 + most);
+        }
+        counts
+    }
+
+    fn check_themes(&mut self) {
+        let raws = self.raws;
+        if !raws.themes.iter().any(|t| t.name == DEFAULT_THEME) {
+            self.error(
+                "$.themes".to_string(),
+                format!("There is no [{}] theme", DEFAULT_THEME),
+            );
+        }
+        for (i, theme) in raws.themes.iter().enumerate() {
+            let path = self.entry("themes", i);
+            for tile in TILE_TYPES.iter() {
+                if let Some(look) = theme.tiles.get(tile) {
+                    self.check_tile_look(format!("{}.tiles.{:?}", path, tile), look);
+                }
+            }
+            if let Some(fallback) = &theme.fallback {
+                self.check_tile_look(format!("{}.fallback", path), fallback);
+            }
+            if let Some(bloodstain) = &theme.bloodstain {
+                self.check_color(format!("{}.bloodstain", path), bloodstain);
+            }
+            if let Some(out_of_sight) = &theme.out_of_sight {
+                if let Some(tint) = &out_of_sight.tint {
+                    self.check_color(format!("{}.out_of_sight.tint", path), tint);
+                }
+                self.check_color(format!("{}.out_of_sight.bg", path), &out_of_sight.bg);
+            }
+
+            let lineage = self.theme_lineage(theme);
+            if let Some(base) = &theme.base {
+                if !raws.themes.iter().any(|t| t.name == *base) {
+                    self.error(
+                        format!("{}.base", path),
+                        format!("Unknown theme [{}]", base),
+                    );
+                } else if lineage[lineage.len() - 1].base.as_ref() == Some(&theme.name) {
+                    self.error(
+                        format!("{}.base", path),
+                        format!("[{}] is based on itself", theme.name),
+                    );
+                }
+            }
+            let missing: Vec<String> = TILE_TYPES
+                .iter()
+                .filter(|tile| {
+                    !lineage
+                        .iter()
+                        .any(|t| t.fallback.is_some() || t.tiles.contains_key(*tile))
+                })
+                .map(|tile| format!("{:?}", tile))
+                .collect();
+            if !missing.is_empty() {
+                self.error(
+                    format!("{}.tiles", path),
+                    format!("Nothing to draw {} with", missing.join(", ")),
+                );
+            }
+
+            for (j, region) in theme.regions.iter().enumerate() {
+                let region_path = format!("{}.regions[{}]", path, j);
+                match raws.themes.iter().find(|t| t.name == region.theme) {
+                    None => self.error(
+                        format!("{}.theme", region_path),
+                        format!("Unknown theme [{}]", region.theme),
+                    ),
+                    Some(other) if !other.regions.is_empty() => self.error(
+                        format!("{}.theme", region_path),
+                        format!("[{}] has regions of its own", other.name),
+                    ),
+                    _ => (),
+                }
+                if region.from_x < 0.0 || region.from_x >= region.to_x || region.to_x > 1.0 {
+                    self.error(
+                        format!("{}.from_x", region_path),
+                        format!(
+                            "{} to {} isn't a strip of the map between 0 and 1",
+                            region.from_x, region.to_x
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// `theme` followed by its base themes, stopping at one that is missing or already listed.
+    fn theme_lineage(&self, theme: &'a Theme) -> Vec<&'a Theme> {
+        let mut lineage = vec![theme];
+        while let Some(base) = &lineage[lineage.len() - 1].base {
+            match self.raws.themes.iter().find(|t| t.name == *base) {
+                Some(base) if !lineage.iter().any(|t| t.name == base.name) => lineage.push(base),
+                _ => break,
+            }
+        }
+        lineage
+    }
+
+    fn check_tile_look(&mut self, path: String, look: &TileLook) {
+        for (glyph_path, glyph) in std::iter::once((format!("{}.glyph", path), &look.glyph)).chain(
+            look.variants
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| (format!("{}.variants[{}]", path, i), variant)),
+        ) {
+            if glyph.chars().count() != 1 {
+                self.error(glyph_path, format!("[{}] isn't a single glyph", glyph));
+            }
+        }
+        if let Some(mask_glyphs) = &look.mask_glyphs {
+            let count = mask_glyphs.chars().count();
+            if count != 16 {
+                self.error(
+                    format!("{}.mask_glyphs", path),
+                    format!("Needs 16 glyphs, got {}", count),
+                );
+            }
+        }
+        self.check_color(format!("{}.fg", path), &look.fg);
+        self.check_color(format!("{}.bg", path), &look.bg);
+    }
+
+    fn check_prefabs(&mut self) {
+        let raws = self.raws;
+        for (i, prefab) in raws.prefabs.iter().enumerate() {
+            let path = self.entry("prefabs", i);
+            if prefab.width < 1 || prefab.height < 1 {
+                self.error(
+                    format!("{}.width", path),
+                    format!("A {}x{} prefab has no room", prefab.width, prefab.height),
+                );
+            }
+            if let Some(max_depth) = prefab.max_depth {
+                if prefab.min_depth > max_depth {
+                    self.error(
+                        format!("{}.min_depth", path),
+                        format!(
+                            "Depths {} to {} aren't a range of levels",
+                            prefab.min_depth, max_depth
+                        ),
+                    );
+                }
+            }
+            if prefab.weight < 1 {
+                self.error(
+                    format!("{}.weight", path),
+                    format!("Weight must be positive, got {}", prefab.weight),
+                );
+            }
+            if prefab.max_per_level < 1 {
+                self.error(
+                    format!("{}.max_per_level", path),
+                    format!("Must be positive, got {}", prefab.max_per_level),
+                );
+            }
+            if prefab.kind == PrefabKind::Level && (prefab.rotate || prefab.mirror) {
+                self.error(
+                    format!("{}.rotate", path),
+                    "Levels are always placed as drawn".to_string(),
+                );
+            }
+
+            let mut legend: Vec<_> = prefab.legend.iter().collect();
+            legend.sort();
+            for (ch, name) in legend {
+                let legend_path = format!("{}.legend.{}", path, ch);
+                if ch.chars().count() != 1 {
+                    self.error(
+                        legend_path.clone(),
+                        format!("[{}] isn't a single character", ch),
+                    );
+                } else if matches!(ch.as_str(), " " | "#" | ">" | "@") {
+                    self.error(
+                        legend_path.clone(),
+                        format!("[{}] already stands for a tile", ch),
+                    );
+                }
+                if !self.entities.contains(name) {
+                    self.error(legend_path, format!("Unknown item, mob or prop [{}]", name));
+                }
+            }
+        }
+    }
+
+    fn check_effects(&mut self, path: &str, effects: &[Effect]) {
+        for (i, effect) in effects.iter().enumerate() {
+            let path = format!("{}[{}]", path, i);
+            match effect {
+                Effect::TeachSpell { spell } => {
+                    if !self.spells.contains(spell.as_str()) {
+                        self.error(
+                            format!("{}.spell", path),
+                            format!("Unknown spell [{}]", spell),
+                        );
+                    }
+                }
+                Effect::Particle { color, .. } | Effect::ParticleLine { color, .. } => {
+                    self.check_color(format!("{}.color", path), color);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn check_abilities(&mut self, path: &str, abilities: &[MobAbility]) {
+        for (i, ability) in abilities.iter().enumerate() {
+            if !self.spells.contains(ability.spell.as_str()) {
+                self.error(
+                    format!("{}[{}].spell", path, i),
+                    format!("Unknown spell [{}]", ability.spell),
+                );
+            }
+        }
+    }
+
+    fn check_equippable(&mut self, path: String, tag: &str) {
+        let raws = self.raws;
+        match raws.items.iter().find(|item| item.name == tag) {
+            None => self.error(path, format!("Unknown item [{}]", tag)),
+            Some(item) if item.weapon.is_none() && item.wearable.is_none() => {
+                self.error(path, format!("{} can't be equipped", tag))
+            }
+            Some(_) => (),
+        }
+    }
+
+    fn check_renderable(&mut self, path: &str, renderable: &Renderable) {
+        if renderable.glyph.is_empty() {
+            self.error(
+                format!("{}.renderable.glyph", path),
+                "Glyph is empty".to_string(),
+            );
+        }
+        self.check_color(format!("{}.renderable.fg", path), &renderable.fg);
+        self.check_color(format!("{}.renderable.bg", path), &renderable.bg);
+    }
+
+    fn check_color(&mut self, path: String, color: &str) {
+        if rltk::RGB::from_hex(color).is_err() {
+            self.error(path, format!("Invalid color [{}]", color));
+        }
+    }
+}
+
+/// Returns the raws directory to check if the game was started with `--validate-raws [dir]`.
+pub fn validate_path_from_args() -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--validate-raws");
+    args.next()?;
+    Some(args.next().unwrap_or_else(|| loader::RAW_DIR.to_string()))
+}
+
+/// Checks a raws directory, mods included, and prints every problem found. Exits with an
+/// error code if there are any, so it can be run before committing changes to the raws.
+pub fn run_validator(dir: &str) {
+    match loader::load_raw_dir(std::path::Path::new(dir)).and_then(validated) {
+        Ok(_) => println!("{}: no problems found", dir),
+        Err(errors) => {
+            for error in errors.iter() {
+                println!("{}", error);
+            }
+            println!("{}: {} problem(s) found", dir, errors.len());
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+
+use rltk::Point;
+use serde::{Deserialize, Serialize};
+use specs::{
+    prelude::*,
+    saveload::{Marker, SimpleMarker},
+};
+
+use crate::{
+    gamelog::{GameEvents, GameLog, Logger},
+    player::PlayerCommand,
+    IsSerialized,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const REPLAY_PATH: &str = "./last_run.replay.json";
+
+/// A `PlayerCommand` with entities replaced by their save marker ids. Markers are handed out in
+/// creation order and never reused, so a replay of the same seed resolves them to the same
+/// entities.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ReplayCommand {
+    Move { dx: i32, dy: i32 },
+    SkipTurn,
+    CycleTarget,
+    Fire,
+    PickUp,
+    ConsumableHotkey { slot: usize },
+    SpellHotkey { slot: usize },
+    UseItem { item: u64, target: Option<Point> },
+    CastSpell { spell: u64, target: Option<Point> },
+    DropItem { item: u64 },
+    RemoveItem { item: u64 },
+    SellItem { item: u64 },
+    BuyItem { tag: String, price: f32 },
+    IdentifyItem { item: u64 },
+    RemoveCurse { item: u64 },
+    DescendStairs,
+    AscendStairs,
+    ShowInventory,
+    ShowDropItem,
+    ShowRemoveItem,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedCommand {
+    pub turn: i32,
+    pub command: ReplayCommand,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ReplayFile {
+    pub seed: u64,
+    pub commands: Vec<RecordedCommand>,
+}
+
+/// Resource collecting every command of the current run. Games loaded from a save have no
+/// recorder, as their starting state can't be rebuilt from the seed.
+pub struct ReplayRecorder {
+    pub replay: ReplayFile,
+}
+
+impl ReplayRecorder {
+    pub fn new(seed: u64) -> Self {
+        ReplayRecorder {
+            replay: ReplayFile {
+                seed,
+                commands: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Feeds the commands of a replay file back, one per call.
+pub struct ReplayPlayer {
+    commands: VecDeque<RecordedCommand>,
+    diverged: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: ReplayFile) -> Self {
+        ReplayPlayer {
+            commands: replay.commands.into(),
+            diverged: false,
+        }
+    }
+
+    /// Returns the next command, or `None` once the replay is exhausted. Warns once in the log
+    /// if the run no longer matches the recording.
+    pub fn next_command(&mut self, ecs: &World) -> Option<PlayerCommand> {
+        while let Some(recorded) = self.commands.pop_front() {
+            let turn = ecs.fetch::<GameEvents>().get_event_count("Turn");
+            let command = from_replay_command(ecs, &recorded.command);
+            if (recorded.turn != turn || command.is_none()) && !self.diverged {
+                self.diverged = true;
+                Logger::new()
+                    .red("Replay diverged at turn")
+                    .yellow(turn)
+                    .red("(recorded")
+                    .yellow(recorded.turn)
+                    .red(")")
+                    .log(&mut ecs.fetch_mut::<GameLog>());
+            }
+            if command.is_some() {
+                return command;
+            }
+        }
+        None
+    }
+
+    pub fn has_diverged(&self) -> bool {
+        self.diverged
+    }
+}
+
+/// Appends a command to the run's recording, if it is being recorded.
+pub fn record_command(ecs: &World, command: &PlayerCommand) {
+    let recorded = RecordedCommand {
+        turn: ecs.fetch::<GameEvents>().get_event_count("Turn"),
+        command: to_replay_command(ecs, command),
+    };
+    if let Some(mut recorder) = ecs.try_fetch_mut::<ReplayRecorder>() {
+        recorder.replay.commands.push(recorded);
+    }
+}
+
+/// Writes the run's recording next to the game, if it is being recorded.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_replay(ecs: &World) -> std::io::Result<()> {
+    if let Some(recorder) = ecs.try_fetch::<ReplayRecorder>() {
+        let writer = std::io::BufWriter::new(std::fs::File::create(REPLAY_PATH)?);
+        serde_json::to_writer(writer, &recorder.replay)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_replay(_ecs: &World) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Reads the replay file at `path`, or exits with an error if it can't.
+pub fn load_replay(path: &str) -> ReplayFile {
+    let replay = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()));
+    match replay {
+        Ok(replay) => replay,
+        Err(error) => {
+            println!("Unable to read replay [{}]: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns the path given with `--replay <path>`, if any.
+pub fn replay_path_from_args() -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--replay");
+    args.next()?;
+    args.next()
+}
+
+fn marker_id(ecs: &World, entity: Entity) -> u64 {
+    ecs.read_storage::<SimpleMarker<IsSerialized>>()
+        .get(entity)
+        .map(|marker| marker.id())
+        .expect("Replayed entity has no save marker")
+}
+
+fn entity_by_marker(ecs: &World, id: u64) -> Option<Entity> {
+    let markers = ecs.read_storage::<SimpleMarker<IsSerialized>>();
+    let entities = ecs.entities();
+    (&entities, &markers)
+        .join()
+        .find(|(_, marker)| marker.id() == id)
+        .map(|(entity, _)| entity)
+}
+
+fn to_replay_command(ecs: &World, command: &PlayerCommand) -> ReplayCommand {
+    match command {
+        PlayerCommand::Move { dx, dy } => ReplayCommand::Move { dx: *dx, dy: *dy },
+        PlayerCommand::SkipTurn => ReplayCommand::SkipTurn,
+        PlayerCommand::CycleTarget => ReplayCommand::CycleTarget,
+        PlayerCommand::Fire => ReplayCommand::Fire,
+        PlayerCommand::PickUp => ReplayCommand::PickUp,
+        PlayerCommand::ConsumableHotkey { slot } => ReplayCommand::ConsumableHotkey { slot: *slot },
+        PlayerCommand::SpellHotkey { slot } => ReplayCommand::SpellHotkey { slot: *slot },
+        PlayerCommand::UseItem { item, target } => ReplayCommand::UseItem {
+            item: marker_id(ecs, *item),
+            target: *target,
+        },
+        PlayerCommand::CastSpell { spell, target } => ReplayCommand::CastSpell {
+            spell: marker_id(ecs, *spell),
+            target: *target,
+        },
+        PlayerCommand::DropItem { item } => ReplayCommand::DropItem {
+            item: marker_id(ecs, *item),
+        },
+        PlayerCommand::RemoveItem { item } => ReplayCommand::RemoveItem {
+            item: marker_id(ecs, *item),
+        },
+        PlayerCommand::SellItem { item } => ReplayCommand::SellItem {
+            item: marker_id(ecs, *item),
+        },
+        PlayerCommand::BuyItem { tag, price } => ReplayCommand::BuyItem {
+            tag: tag.clone(),
+            price: *price,
+        },
+        PlayerCommand::IdentifyItem { item } => ReplayCommand::IdentifyItem {
+            item: marker_id(ecs, *item),
+        },
+        PlayerCommand::RemoveCurse { item } => ReplayCommand::RemoveCurse {
+            item: marker_id(ecs, *item),
+        },
+        PlayerCommand::DescendStairs => ReplayCommand::DescendStairs,
+        PlayerCommand::AscendStairs => ReplayCommand::AscendStairs,
+        PlayerCommand::ShowInventory => ReplayCommand::ShowInventory,
+        PlayerCommand::ShowDropItem => ReplayCommand::ShowDropItem,
+        PlayerCommand::ShowRemoveItem => ReplayCommand::ShowRemoveItem,
+    }
+}
+
+fn from_replay_command(ecs: &World, command: &ReplayCommand) -> Option<PlayerCommand> {
+    let command = match command {
+        ReplayCommand::Move { dx, dy } => PlayerCommand::Move { dx: *dx, dy: *dy },
+        ReplayCommand::SkipTurn => PlayerCommand::SkipTurn,
+        ReplayCommand::CycleTarget => PlayerCommand::CycleTarget,
+        ReplayCommand::Fire => PlayerCommand::Fire,
+        ReplayCommand::PickUp => PlayerCommand::PickUp,
+        ReplayCommand::ConsumableHotkey { slot } => PlayerCommand::ConsumableHotkey { slot: *slot },
+        ReplayCommand::SpellHotkey { slot } => PlayerCommand::SpellHotkey { slot: *slot },
+        ReplayCommand::UseItem { item, target } => PlayerCommand::UseItem {
+            item: entity_by_marker(ecs, *item)?,
+            target: *target,
+        },
+        ReplayCommand::CastSpell { spell, target } => PlayerCommand::CastSpell {
+            spell: entity_by_marker(ecs, *spell)?,
+            target: *target,
+        },
+        ReplayCommand::DropItem { item } => PlayerCommand::DropItem {
+            item: entity_by_marker(ecs, *item)?,
+        },
+        ReplayCommand::RemoveItem { item } => PlayerCommand::RemoveItem {
+            item: entity_by_marker(ecs, *item)?,
+        },
+        ReplayCommand::SellItem { item } => PlayerCommand::SellItem {
+            item: entity_by_marker(ecs, *item)?,
+        },
+        ReplayCommand::BuyItem { tag, price } => PlayerCommand::BuyItem {
+            tag: tag.clone(),
+            price: *price,
+        },
+        ReplayCommand::IdentifyItem { item } => PlayerCommand::IdentifyItem {
+            item: entity_by_marker(ecs, *item)?,
+        },
+        ReplayCommand::RemoveCurse { item } => PlayerCommand::RemoveCurse {
+            item: entity_by_marker(ecs, *item)?,
+        },
+        ReplayCommand::DescendStairs => PlayerCommand::DescendStairs,
+        ReplayCommand::AscendStairs => PlayerCommand::AscendStairs,
+        ReplayCommand::ShowInventory => PlayerCommand::ShowInventory,
+        ReplayCommand::ShowDropItem => PlayerCommand::ShowDropItem,
+        ReplayCommand::ShowRemoveItem => PlayerCommand::ShowRemoveItem,
+    };
+    Some(command)
+}
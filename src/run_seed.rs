@@ -0,0 +1,32 @@
+use rltk::RandomNumberGenerator;
+
+const NAMES_SALT: u64 = 0x6E61_6D65_7321;
+const DEPTH_SALT: u64 = 0x6465_7074_6800;
+
+/// Returns the seed given with `--seed <n>`, or a fresh one from entropy.
+pub fn new_run_seed() -> u64 {
+    let mut args = std::env::args().skip_while(|arg| arg != "--seed");
+    args.next();
+    args.next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| RandomNumberGenerator::new().next_u64())
+}
+
+/// Seed for the builder chain of a depth. Regenerating the same depth of the same run always
+/// produces the same map and spawn list.
+pub fn depth_seed(run_seed: u64, depth: i32) -> u64 {
+    mix(run_seed, DEPTH_SALT.wrapping_add(depth as u64))
+}
+
+/// Seed for the scroll and potion name obfuscation tables.
+pub fn names_seed(run_seed: u64) -> u64 {
+    mix(run_seed, NAMES_SALT)
+}
+
+// SplitMix64 finalizer, so neighbouring depths get unrelated streams.
+fn mix(run_seed: u64, salt: u64) -> u64 {
+    let mut z = run_seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
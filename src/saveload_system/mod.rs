@@ -0,0 +1,554 @@
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specs::prelude::*;
+use specs::saveload::{
+    DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
+};
+
+use crate::dice::DiceExpr;
+use crate::gamelog::{GameEvents, GameLog};
+
+use super::{components::*, map};
+use std::convert::Infallible as NoError;
+use std::io::{Read, Write};
+
+#[macro_use]
+mod registry;
+mod storage;
+pub use registry::register_components;
+use registry::SAVED_COMPONENTS;
+pub use storage::{default_storage, MemoryStorage, SaveStorage};
+
+/// Version of the save layout written by this build. Bump it whenever a saved component or
+/// resource changes shape, and add a migration from the previous version to `MIGRATIONS`.
+const SAVE_VERSION: u32 = 7;
+
+/// Upgrades a save's data by one version: `MIGRATIONS[v]` turns a version `v` save into a
+/// version `v + 1` one. The data holds one serialized storage per saved component type, in the
+/// order `with_components!` lists them.
+type Migration = fn(&mut Vec<Value>) -> Result<(), LoadError>;
+
+const MIGRATIONS: [Migration; SAVE_VERSION as usize] = [
+    // Version 0 saves predate the header and are otherwise identical
+    |_| Ok(()),
+    // Version 1 saves dropped equipment and removal requests; equipped items can't be recovered
+    |values| {
+        values.push(Value::Array(Vec::new())); // Equipped
+        values.push(Value::Array(Vec::new())); // WantsToRemoveItem
+        Ok(())
+    },
+    // Version 2 saves split weapon and natural attack damage into dice count, type and bonus.
+    // Healing amounts were plain numbers, which still load as dice.
+    |values| {
+        for_each_saved(values, "Weapon", convert_damage_dice)?;
+        for_each_saved(values, "NaturalAttackDefense", |natural| {
+            if let Some(Value::Array(attacks)) = natural.get_mut("attacks") {
+                attacks.iter_mut().for_each(convert_damage_dice);
+            }
+        })
+    },
+    // Version 3 saves predate spawn groups, so nothing follows a leader
+    |values| {
+        values.push(Value::Array(Vec::new())); // Follower
+        Ok(())
+    },
+    // Version 4 saves predate gold dropping as an item
+    |values| {
+        values.push(Value::Array(Vec::new())); // Gold
+        Ok(())
+    },
+    // Version 5 saves predate item sets
+    |values| {
+        values.push(Value::Array(Vec::new())); // ItemSetPiece
+        values.push(Value::Array(Vec::new())); // ItemSetBonuses
+        Ok(())
+    },
+    // Version 6 maps predate themes, which were picked by depth
+    |values| {
+        for_each_saved(values, "SerializationHelper", |helper| {
+            if let Some(map) = helper.get_mut("map") {
+                add_depth_theme(map);
+            }
+        })?;
+        for_each_saved(values, "DMSerializationHelper", |helper| {
+            if let Some(Value::Object(maps)) = helper.pointer_mut("/map/maps") {
+                maps.values_mut().for_each(add_depth_theme);
+            }
+        })
+    },
+];
+
+/// Calls `f` with each serialized component of the saved type `name`.
+fn for_each_saved(
+    values: &mut [Value],
+    name: &str,
+    f: impl FnMut(&mut Value),
+) -> Result<(), LoadError> {
+    let index = SAVED_COMPONENTS
+        .iter()
+        .position(|saved| *saved == name)
+        .expect("Migrating a component that isn't saved");
+    let Some(Value::Array(entities)) = values.get_mut(index) else {
+        return Err(LoadError::Incomplete);
+    };
+    entities
+        .iter_mut()
+        .filter_map(|entity| entity.get_mut("components").and_then(|c| c.get_mut(0)))
+        .for_each(f);
+    Ok(())
+}
+
+/// Replaces the `damage_n_dice`, `damage_die_type` and `damage_bonus` fields of a version 2
+/// weapon or natural attack with a `damage` dice expression.
+fn convert_damage_dice(component: &mut Value) {
+    let Some(fields) = component.as_object_mut() else {
+        return;
+    };
+    let mut take = |field: &str, default: i64| {
+        fields
+            .remove(field)
+            .and_then(|value| value.as_i64())
+            .unwrap_or(default) as i32
+    };
+    let damage = DiceExpr::dice(take("damage_n_dice", 1), take("damage_die_type", 4))
+        .plus(take("damage_bonus", 0));
+    fields.insert("damage".to_string(), Value::String(damage.to_string()));
+}
+
+/// Gives a version 6 map the theme its depth was drawn with.
+fn add_depth_theme(map: &mut Value) {
+    let theme = match map.get("depth").and_then(Value::as_i64) {
+        Some(2) => "forest",
+        Some(3 | 4) => "limestone",
+        Some(5) => "limestone_transition",
+        Some(7) => "mushroom_entrance",
+        Some(8 | 9) => "mushroom",
+        _ => "default",
+    };
+    if let Some(fields) = map.as_object_mut() {
+        fields.insert("theme".to_string(), Value::String(theme.to_string()));
+    }
+}
+
+/// Every gzip stream starts with these bytes, which can't begin a JSON save.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Starts a MessagePack save, ahead of the header. JSON saves begin with `{` or `[`.
+const BINARY_MAGIC: [u8; 4] = *b"RLMP";
+
+/// How a save is encoded on disk. Loading detects the format, so slots can hold either.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub enum SaveFormat {
+    /// Plain JSON, handy for inspecting saves.
+    Json,
+    /// Gzip-compressed MessagePack. Much smaller and quicker to write than JSON text.
+    #[default]
+    Binary,
+}
+
+impl SaveFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SaveFormat::Json => "JSON",
+            SaveFormat::Binary => "Binary",
+        }
+    }
+
+    /// The other format, for menus that flip between them.
+    pub fn toggled(&self) -> SaveFormat {
+        match self {
+            SaveFormat::Json => SaveFormat::Binary,
+            SaveFormat::Binary => SaveFormat::Json,
+        }
+    }
+}
+
+/// Written ahead of the component data.
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Corrupt(serde_json::Error),
+    CorruptBinary(rmpv::decode::Error),
+    TooNew { version: u32 },
+    Incomplete,
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "Unable to read save: {}", e),
+            LoadError::Corrupt(e) => write!(f, "Save is corrupt: {}", e),
+            LoadError::CorruptBinary(e) => write!(f, "Save is corrupt: {}", e),
+            LoadError::TooNew { version } => write!(
+                f,
+                "Save is from a newer version of the game (format {})",
+                version
+            ),
+            LoadError::Incomplete => write!(f, "Save is incomplete"),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        LoadError::Corrupt(e)
+    }
+}
+
+impl From<rmpv::decode::Error> for LoadError {
+    fn from(e: rmpv::decode::Error) -> Self {
+        LoadError::CorruptBinary(e)
+    }
+}
+pub const SAVE_SLOT_COUNT: usize = 3;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum SaveSlot {
+    Manual(usize),
+    Autosave,
+}
+
+impl SaveSlot {
+    /// Every slot a game can be loaded from, manual slots first.
+    pub fn all() -> impl Iterator<Item = SaveSlot> {
+        (0..SAVE_SLOT_COUNT)
+            .map(SaveSlot::Manual)
+            .chain(std::iter::once(SaveSlot::Autosave))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            SaveSlot::Manual(n) => format!("Slot {}", n + 1),
+            SaveSlot::Autosave => "Autosave".to_string(),
+        }
+    }
+}
+
+/// Resource describing how the running game is saved. Stored in the save itself, so a game
+/// loaded from the autosave slot goes on saving to the slot it was started in.
+#[derive(Serialize, Deserialize, Copy, Clone, Default)]
+pub struct SaveSettings {
+    pub slot: usize,
+    /// Ironman saves are deleted once loaded, and autosave over their own slot.
+    pub ironman: bool,
+    /// Picked when the game is started; older saves go on as binary.
+    #[serde(default)]
+    pub format: SaveFormat,
+}
+
+impl SaveSettings {
+    /// The slot level transitions save to. Ironman runs can't fall back to an older autosave.
+    pub fn autosave_slot(&self) -> SaveSlot {
+        if self.ironman {
+            SaveSlot::Manual(self.slot)
+        } else {
+            SaveSlot::Autosave
+        }
+    }
+}
+
+// Used to avoid compile fail when over 16 component types...
+macro_rules! serialize_individually {
+    ($ecs:expr, $ser:expr, $data:expr, saved: [$( $type:ty),* $(,)?], transient: $transient:tt) => {
+        $(
+        SerializeComponents::<NoError, SimpleMarker<IsSerialized>>::serialize(
+            &( $ecs.read_storage::<$type>(), ),
+            &$data.0,
+            &$data.1,
+            &mut $ser,
+        )
+        .unwrap();
+        )*
+    };
+}
+
+macro_rules! deserialize_individually {
+    ($ecs:expr, $values:expr, $data:expr, saved: [$( $type:ty),* $(,)?], transient: $transient:tt) => {
+        $(
+        DeserializeComponents::<NoError, _>::deserialize(
+            &mut ( &mut $ecs.write_storage::<$type>(), ),
+            &    $data.0, // entities
+            &mut $data.1, // marker
+            &mut $data.2, // allocater
+            $values.next().ok_or(LoadError::Incomplete)?,
+        )?;
+        )*
+    };
+}
+
+/// Writes the world to `slot` in the format its settings ask for. The world is left as it
+/// was, even if the write fails.
+pub fn save_game(
+    ecs: &mut World,
+    storage: &mut dyn SaveStorage,
+    slot: SaveSlot,
+) -> std::io::Result<()> {
+    // Create helper
+    let mapcopy = ecs.get_mut::<map::Map>().unwrap().clone();
+    let dungeon_master = ecs
+        .get_mut::<map::dungeon::MasterDungeonMap>()
+        .unwrap()
+        .clone();
+    let settings = *ecs.fetch::<SaveSettings>();
+
+    let savehelper = ecs
+        .create_entity()
+        .with(SerializationHelper { map: mapcopy })
+        .marked::<SimpleMarker<IsSerialized>>()
+        .build();
+
+    let savehelper2 = ecs
+        .create_entity()
+        .with(DMSerializationHelper {
+            map: dungeon_master,
+            log: ecs.fetch::<GameLog>().clone_log(),
+            events: ecs.fetch::<GameEvents>().clone_events(),
+            settings,
+        })
+        .marked::<SimpleMarker<IsSerialized>>()
+        .build();
+
+    // Actually serialize
+    let result = encode_world(ecs, settings.format).and_then(|bytes| storage.write(slot, &bytes));
+
+    // Clean up
+    ecs.delete_entity(savehelper).expect("Crash on cleanup");
+    ecs.delete_entity(savehelper2).expect("Crash on cleanup");
+    result
+}
+
+fn encode_world(ecs: &World, format: SaveFormat) -> std::io::Result<Vec<u8>> {
+    match format {
+        SaveFormat::Json => {
+            Ok(serialize_world(ecs, serde_json::Serializer::new(Vec::new())).into_inner())
+        }
+        SaveFormat::Binary => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+            encoder.write_all(&BINARY_MAGIC)?;
+            let serializer = rmp_serde::Serializer::new(encoder).with_struct_map();
+            serialize_world(ecs, serializer).into_inner().finish()
+        }
+    }
+}
+
+/// Writes the header and every saved storage to `serializer`, one value after another.
+fn serialize_world<S>(ecs: &World, mut serializer: S) -> S
+where
+    for<'a> &'a mut S: serde::Serializer,
+{
+    let data = (
+        ecs.entities(),
+        ecs.read_storage::<SimpleMarker<IsSerialized>>(),
+    );
+
+    SaveHeader {
+        version: SAVE_VERSION,
+    }
+    .serialize(&mut serializer)
+    .unwrap();
+    with_components!(serialize_individually!(ecs, serializer, data,));
+
+    serializer
+}
+
+/// Whether any slot, including the autosave, holds a game.
+pub fn save_exists(storage: &dyn SaveStorage) -> bool {
+    SaveSlot::all().any(|slot| storage.exists(slot))
+}
+
+/// Replaces the world's entities with those saved in `slot`. Errors found before anything is
+/// deleted leave the world untouched; later ones leave it partly loaded.
+pub fn load_game(
+    ecs: &mut World,
+    storage: &dyn SaveStorage,
+    slot: SaveSlot,
+) -> Result<(), LoadError> {
+    let mut values = read_save_data(&storage.read(slot)?)?.into_iter();
+
+    {
+        // Delete everything
+        let mut to_delete = Vec::new();
+        for e in ecs.entities().join() {
+            to_delete.push(e);
+        }
+        for del in to_delete.iter() {
+            ecs.delete_entity(*del).expect("Deletion failed");
+        }
+    }
+
+    {
+        let mut d = (
+            &mut ecs.entities(),
+            &mut ecs.write_storage::<SimpleMarker<IsSerialized>>(),
+            &mut ecs.write_resource::<SimpleMarkerAllocator<IsSerialized>>(),
+        );
+
+        with_components!(deserialize_individually!(ecs, values, d,));
+    }
+
+    let mut deleteme: Option<Entity> = None;
+    let mut deleteme2: Option<Entity> = None;
+    {
+        let entities = ecs.entities();
+        let helper = ecs.read_storage::<SerializationHelper>();
+        let helper2 = ecs.read_storage::<DMSerializationHelper>();
+        let player = ecs.read_storage::<Player>();
+        let position = ecs.read_storage::<Position>();
+        for (e, h) in (&entities, &helper).join() {
+            let mut worldmap = ecs.write_resource::<map::Map>();
+            *worldmap = h.map.clone();
+            let map_tile_count = (worldmap.height * worldmap.width) as usize;
+            worldmap.spatial.set_size(map_tile_count);
+            deleteme = Some(e);
+        }
+        for (e, h) in (&entities, &helper2).join() {
+            let mut dungeonmaster = ecs.write_resource::<map::dungeon::MasterDungeonMap>();
+            *dungeonmaster = h.map.clone();
+            deleteme2 = Some(e);
+            ecs.fetch_mut::<GameLog>().restore_log(&mut h.log.clone());
+            ecs.fetch_mut::<GameEvents>().load_events(h.events.clone());
+            *ecs.fetch_mut::<SaveSettings>() = h.settings;
+        }
+        for (e, _p, pos) in (&entities, &player, &position).join() {
+            let mut ppos = ecs.write_resource::<rltk::Point>();
+            *ppos = rltk::Point::new(pos.x, pos.y);
+            let mut player_resource = ecs.write_resource::<Entity>();
+            *player_resource = e;
+        }
+    }
+    ecs.delete_entity(deleteme.ok_or(LoadError::Incomplete)?)
+        .expect("Unable to delete helper");
+    ecs.delete_entity(deleteme2.ok_or(LoadError::Incomplete)?)
+        .expect("Unable to delete helper");
+    Ok(())
+}
+
+/// Parses a save in any format into its component data, migrated to the current version.
+fn read_save_data(bytes: &[u8]) -> Result<Vec<Value>, LoadError> {
+    let mut decompressed = Vec::new();
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        &decompressed[..]
+    } else {
+        bytes
+    };
+    let mut values = match bytes.strip_prefix(&BINARY_MAGIC) {
+        Some(binary) => read_binary_values(binary)?,
+        None => serde_json::Deserializer::from_slice(bytes)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    let version = match values.first() {
+        Some(Value::Object(_)) => serde_json::from_value::<SaveHeader>(values.remove(0))?.version,
+        // Component data is stored as arrays; saves without a header are from version 0
+        Some(_) => 0,
+        None => return Err(LoadError::Incomplete),
+    };
+    if version > SAVE_VERSION {
+        return Err(LoadError::TooNew { version });
+    }
+
+    for migration in MIGRATIONS[version as usize..].iter() {
+        migration(&mut values)?;
+    }
+    Ok(values)
+}
+
+/// Reads the MessagePack values of a binary save as the JSON they'd have been saved as, so
+/// both formats share the migrations.
+fn read_binary_values(mut bytes: &[u8]) -> Result<Vec<Value>, LoadError> {
+    let mut values = Vec::new();
+    while !bytes.is_empty() {
+        values.push(binary_to_json(rmpv::decode::read_value(&mut bytes)?));
+    }
+    Ok(values)
+}
+
+fn binary_to_json(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil | rmpv::Value::Ext(..) => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => Value::from(n),
+            (None, Some(n)) => Value::from(n),
+            (None, None) => Value::Null,
+        },
+        rmpv::Value::F32(n) => Value::from(n as f64),
+        rmpv::Value::F64(n) => Value::from(n),
+        rmpv::Value::String(s) => s.into_str().map_or(Value::Null, Value::String),
+        rmpv::Value::Binary(bytes) => Value::from(bytes),
+        rmpv::Value::Array(items) => Value::Array(items.into_iter().map(binary_to_json).collect()),
+        // JSON writes map keys such as map indices as strings, and reads them back the same way
+        rmpv::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        rmpv::Value::String(s) => s.into_str().unwrap_or_default(),
+                        key => key.to_string(),
+                    };
+                    (key, binary_to_json(value))
+                })
+                .collect(),
+        ),
+    }
+}
+
+pub fn delete_save(storage: &mut dyn SaveStorage, slot: SaveSlot) -> std::io::Result<()> {
+    storage.delete(slot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    enum Kind {
+        Plain,
+        Counted(i32),
+    }
+
+    #[derive(Serialize)]
+    struct Sample {
+        kinds: Vec<Kind>,
+        by_depth: HashMap<i32, String>,
+        missing: Option<u8>,
+        scale: f32,
+        seed: u64,
+    }
+
+    #[test]
+    fn binary_values_read_as_their_json() {
+        let sample = Sample {
+            kinds: vec![Kind::Plain, Kind::Counted(-3)],
+            by_depth: HashMap::from([(1, "Town".to_string()), (2, "Woods".to_string())]),
+            missing: None,
+            scale: 0.1,
+            seed: u64::MAX,
+        };
+        let mut bytes = Vec::new();
+        let mut serializer = rmp_serde::Serializer::new(&mut bytes).with_struct_map();
+        sample.serialize(&mut serializer).unwrap();
+        sample.serialize(&mut serializer).unwrap();
+
+        let json = serde_json::to_value(&sample).unwrap();
+        assert_eq!(
+            read_binary_values(&bytes).unwrap(),
+            vec![json.clone(), json]
+        );
+    }
+}
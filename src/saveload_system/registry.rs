@@ -0,0 +1,189 @@
+//! Every component type the world knows about, listed once. Registering storages and saving
+//! and loading them are all generated from `with_components!`, so a component can't be
+//! registered without being saved by accident.
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use specs::prelude::*;
+use specs::saveload::{ConvertSaveload, SimpleMarker};
+
+use crate::components::*;
+
+/// Invokes `$callback!` with its arguments followed by the component lists.
+///
+/// `saved` components are written to saves in the order listed, so add new ones at the end,
+/// bump `SAVE_VERSION` and add a migration giving older saves an empty storage for them.
+/// `transient` components are registered but never saved; `register_components` refuses to
+/// start if one of them could be.
+macro_rules! with_components {
+    ($callback:ident!($($args:tt)*)) => {
+        $callback!(
+            $($args)*
+            saved: [
+                Position,
+                Renderable,
+                Player,
+                Viewshed,
+                Name,
+                BlocksTile,
+                WantsToMelee,
+                Item,
+                Consumable,
+                Ranged,
+                InflictsDamage,
+                AreaOfEffect,
+                Confusion,
+                ProvidesHealing,
+                InBackpack,
+                WantsToPickupItem,
+                WantsToUseItem,
+                WantsToDropItem,
+                SerializationHelper,
+                Equippable,
+                ParticleLifetime,
+                HungerClock,
+                ProvidesFood,
+                MagicMapper,
+                Hidden,
+                EntryTrigger,
+                EntityMoved,
+                SingleActivation,
+                BlocksVisibility,
+                Door,
+                Quips,
+                Attributes,
+                Skills,
+                Pools,
+                Weapon,
+                Wearable,
+                NaturalAttackDefense,
+                LootTable,
+                OtherLevelPosition,
+                DMSerializationHelper,
+                LightSource,
+                Initiative,
+                MyTurn,
+                Faction,
+                WantsToApproach,
+                WantsToFlee,
+                MoveMode,
+                Chasing,
+                EquipmentChanged,
+                Vendor,
+                TownPortal,
+                TeleportTo,
+                ApplyMove,
+                ApplyTeleport,
+                MagicItem,
+                ObfuscatedName,
+                IdentifiedItem,
+                SpawnParticleBurst,
+                SpawnParticleLine,
+                CursedItem,
+                ProvidesRemoveCurse,
+                ProvidesIdentification,
+                AttributeBonus,
+                Duration,
+                StatusEffect,
+                KnownSpells,
+                WantsToCastSpell,
+                SpellTemplate,
+                ProvidesMana,
+                TeachesSpell,
+                Slow,
+                DamageOverTime,
+                SpecialAbilities,
+                TileSize,
+                OnDeath,
+                AlwaysTargetsSelf,
+                Target,
+                WantsToShoot,
+                Equipped,
+                WantsToRemoveItem,
+                Follower,
+                Gold,
+                ItemSetPiece,
+                ItemSetBonuses,
+            ],
+            transient: []
+        )
+    };
+}
+
+macro_rules! saved_names {
+    (saved: [$($saved:ty),* $(,)?], transient: $transient:tt) => {
+        /// Names of the saved components, in the order their storages are written.
+        pub const SAVED_COMPONENTS: &[&str] = &[$( stringify!($saved) ),*];
+    };
+}
+
+with_components!(saved_names!());
+
+/// Picks `Saveable::saveable` through autoref when the component can be saved, and falls back
+/// to `NotSaveable::saveable` otherwise.
+struct SaveProbe<T>(PhantomData<T>);
+
+trait Saveable {
+    fn saveable(&self) -> bool {
+        true
+    }
+}
+
+impl<T: ConvertSaveload<SimpleMarker<IsSerialized>>> Saveable for SaveProbe<T> {}
+
+// Only picked for transient components, and there may be none of those
+#[allow(dead_code)]
+trait NotSaveable {
+    fn saveable(&self) -> bool {
+        false
+    }
+}
+
+impl<T> NotSaveable for &SaveProbe<T> {}
+
+macro_rules! saveable {
+    ($type:ty) => {{
+        let probe = &SaveProbe::<$type>(PhantomData);
+        probe.saveable()
+    }};
+}
+
+macro_rules! register_all {
+    (saved: [$($saved:ty),* $(,)?], transient: [$($transient:ty),* $(,)?]) => {
+        /// Registers a storage for every component, then checks that the registry saves
+        /// everything that can be saved.
+        pub fn register_components(ecs: &mut World) {
+            $( ecs.register::<$saved>(); )*
+            $( ecs.register::<$transient>(); )*
+            ecs.register::<SimpleMarker<IsSerialized>>();
+
+            check_registry(&[
+                $( (stringify!($saved), saveable!($saved), true), )*
+                $( (stringify!($transient), saveable!($transient), false), )*
+            ]);
+        }
+    };
+}
+
+with_components!(register_all!());
+
+/// Panics on components listed twice, which would register fine but shift every storage
+/// after them when loading, and on saveable components that are left out of saves.
+fn check_registry(components: &[(&str, bool, bool)]) {
+    let mut seen = HashSet::new();
+    for (name, saveable, saved) in components {
+        if !seen.insert(name) {
+            panic!(
+                "{} is listed more than once in the component registry",
+                name
+            );
+        }
+        if *saveable && !*saved {
+            panic!(
+                "{} can be serialized but is not saved; move it to the saved components",
+                name
+            );
+        }
+    }
+}
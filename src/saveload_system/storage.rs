@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::io;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs::File, io::Write, path::PathBuf};
+
+use super::SaveSlot;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "./saves";
+
+/// Where saves are kept. A write has to replace the slot's contents in one go, so a save that
+/// is interrupted can't leave a slot half written.
+pub trait SaveStorage {
+    fn exists(&self, slot: SaveSlot) -> bool;
+    fn read(&self, slot: SaveSlot) -> io::Result<Vec<u8>>;
+    fn write(&mut self, slot: SaveSlot, data: &[u8]) -> io::Result<()>;
+    fn delete(&mut self, slot: SaveSlot) -> io::Result<()>;
+}
+
+/// Keeps each slot in its own file under `dir`.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileStorage { dir: dir.into() }
+    }
+
+    fn path(&self, slot: SaveSlot) -> PathBuf {
+        let file_name = match slot {
+            SaveSlot::Manual(n) => format!("slot{}.sav", n + 1),
+            SaveSlot::Autosave => "autosave.sav".to_string(),
+        };
+        self.dir.join(file_name)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStorage for FileStorage {
+    fn exists(&self, slot: SaveSlot) -> bool {
+        self.path(slot).exists()
+    }
+
+    fn read(&self, slot: SaveSlot) -> io::Result<Vec<u8>> {
+        std::fs::read(self.path(slot))
+    }
+
+    /// Writes next to the slot's file and renames over it once the data is on disk.
+    fn write(&mut self, slot: SaveSlot, data: &[u8]) -> io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(slot);
+        let tmp_path = path.with_extension("sav.tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &path)
+    }
+
+    fn delete(&mut self, slot: SaveSlot) -> io::Result<()> {
+        if self.exists(slot) {
+            std::fs::remove_file(self.path(slot))?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps saves for as long as the storage lives. Used where there is no disk to write to.
+#[derive(Default)]
+pub struct MemoryStorage {
+    slots: HashMap<SaveSlot, Vec<u8>>,
+}
+
+impl SaveStorage for MemoryStorage {
+    fn exists(&self, slot: SaveSlot) -> bool {
+        self.slots.contains_key(&slot)
+    }
+
+    fn read(&self, slot: SaveSlot) -> io::Result<Vec<u8>> {
+        self.slots
+            .get(&slot)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No save in slot"))
+    }
+
+    fn write(&mut self, slot: SaveSlot, data: &[u8]) -> io::Result<()> {
+        self.slots.insert(slot, data.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, slot: SaveSlot) -> io::Result<()> {
+        self.slots.remove(&slot);
+        Ok(())
+    }
+}
+
+/// The storage the game saves to on this platform.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_storage() -> Box<dyn SaveStorage> {
+    Box::new(FileStorage::new(SAVE_DIR))
+}
+
+// TODO: Impl a local storage backend via web-sys, so browser saves outlive the page
+#[cfg(target_arch = "wasm32")]
+pub fn default_storage() -> Box<dyn SaveStorage> {
+    Box::new(MemoryStorage::default())
+}
@@ -1,127 +1,107 @@
-use std::sync::Mutex;
-
 use specs::Entity;
 
-use crate::{tile_walkable, Map, RunState};
+use crate::{tile_walkable, RunState, TileType};
 
-struct SpatialMap {
+/// Per-map index of blocked tiles and tile contents. Owned by the `Map` it describes, so the
+/// pathfinding `BaseMap` impl can see blocking entities and every `World` keeps its own copy.
+#[derive(Clone, Default)]
+pub struct SpatialMap {
     blocked: Vec<(bool, bool)>, // is_map_blocked, is_entity_blocked
     tile_content: Vec<Vec<(Entity, bool)>>,
 }
 
 impl SpatialMap {
-    // deliberately not public
-    // avoid sharing data directly, use API instead
-    fn new() -> Self {
-        Self {
-            blocked: Vec::new(),
-            tile_content: Vec::new(),
+    pub fn populate_blocked_from_map(&mut self, tiles: &[TileType]) {
+        // maps restored from a save come back without an index
+        if self.blocked.len() != tiles.len() {
+            self.set_size(tiles.len());
+        }
+        for (i, tile) in tiles.iter().enumerate() {
+            self.blocked[i].0 = !tile_walkable(*tile);
         }
     }
-}
 
-// allows access without burdening Specs' resources system
-lazy_static! {
-    static ref SPATIAL_MAP: Mutex<SpatialMap> = Mutex::new(SpatialMap::new());
-}
-
-pub fn populate_blocked_from_map(map: &Map) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    for (i, tile) in map.tiles.iter().enumerate() {
-        lock.blocked[i].0 = !tile_walkable(*tile);
+    pub fn clear(&mut self) {
+        self.blocked.iter_mut().for_each(|b| {
+            b.0 = false;
+            b.1 = false;
+        });
+        for content in self.tile_content.iter_mut() {
+            content.clear();
+        }
     }
-}
 
-pub fn clear() {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked.iter_mut().for_each(|b| {
-        b.0 = false;
-        b.1 = false;
-    });
-    for content in lock.tile_content.iter_mut() {
-        content.clear();
+    //  realloc might be bit inefficient - but not used often
+    pub fn set_size(&mut self, map_tile_count: usize) {
+        self.blocked = vec![(false, false); map_tile_count];
+        self.tile_content = vec![Vec::new(); map_tile_count];
     }
-}
-
-//  realloc might be bit inefficient - but not used often
-pub fn set_size(map_tile_count: usize) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked = vec![(false, false); map_tile_count];
-    lock.tile_content = vec![Vec::new(); map_tile_count];
-}
-
-pub fn is_blocked(idx: usize) -> bool {
-    let lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked[idx].0 || lock.blocked[idx].1
-}
 
-pub fn index_entity(entity: Entity, idx: usize, blocks_tile: bool) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.tile_content[idx].push((entity, blocks_tile));
-    if blocks_tile {
-        lock.blocked[idx].1 = true;
+    pub fn is_blocked(&self, idx: usize) -> bool {
+        self.blocked[idx].0 || self.blocked[idx].1
     }
-}
 
-// iterating tile content via closure.
-// avoids returning lock & ensuring its freed, that leaks too much impl detail from API
-pub fn for_each_tile_content<F>(idx: usize, mut f: F)
-where
-    F: FnMut(Entity),
-{
-    let lock = SPATIAL_MAP.lock().unwrap();
-    for entity in lock.tile_content[idx].iter() {
-        f(entity.0);
+    pub fn index_entity(&mut self, entity: Entity, idx: usize, blocks_tile: bool) {
+        self.tile_content[idx].push((entity, blocks_tile));
+        if blocks_tile {
+            self.blocked[idx].1 = true;
+        }
     }
-}
 
-pub fn for_each_tile_content_with_gamemode<F>(idx: usize, mut f: F) -> RunState
-where
-    F: FnMut(Entity) -> Option<RunState>,
-{
-    let lock = SPATIAL_MAP.lock().unwrap();
-    for entity in lock.tile_content[idx].iter() {
-        if let Some(rs) = f(entity.0) {
-            return rs;
+    // iterating tile content via closure keeps the storage layout out of the API
+    pub fn for_each_tile_content<F>(&self, idx: usize, mut f: F)
+    where
+        F: FnMut(Entity),
+    {
+        for entity in self.tile_content[idx].iter() {
+            f(entity.0);
         }
     }
 
-    RunState::AwaitingInput
-}
-
-pub fn move_entity(entity: Entity, moving_from: usize, moving_to: usize) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    let mut entity_blocks = false;
-    lock.tile_content[moving_from].retain(|&(e, blocks)| {
-        let keep = e != entity;
-        if !keep {
-            entity_blocks = blocks;
+    pub fn for_each_tile_content_with_gamemode<F>(&self, idx: usize, mut f: F) -> RunState
+    where
+        F: FnMut(Entity) -> Option<RunState>,
+    {
+        for entity in self.tile_content[idx].iter() {
+            if let Some(rs) = f(entity.0) {
+                return rs;
+            }
         }
-        keep
-    });
-    lock.tile_content[moving_to].push((entity, entity_blocks));
-
-    // Recalculate entity blocks for both tiles
-    let from_blocked = lock.tile_content[moving_from]
-        .iter()
-        .any(|(_, blocks)| *blocks);
 
-    let to_blocked = lock.tile_content[moving_to]
-        .iter()
-        .any(|(_, blocks)| *blocks);
+        RunState::AwaitingInput
+    }
 
-    lock.blocked[moving_from].1 = from_blocked;
-    lock.blocked[moving_to].1 = to_blocked;
-}
+    pub fn move_entity(&mut self, entity: Entity, moving_from: usize, moving_to: usize) {
+        let mut entity_blocks = false;
+        self.tile_content[moving_from].retain(|&(e, blocks)| {
+            let keep = e != entity;
+            if !keep {
+                entity_blocks = blocks;
+            }
+            keep
+        });
+        self.tile_content[moving_to].push((entity, entity_blocks));
+
+        // Recalculate entity blocks for both tiles
+        let from_blocked = self.tile_content[moving_from]
+            .iter()
+            .any(|(_, blocks)| *blocks);
+
+        let to_blocked = self.tile_content[moving_to]
+            .iter()
+            .any(|(_, blocks)| *blocks);
+
+        self.blocked[moving_from].1 = from_blocked;
+        self.blocked[moving_to].1 = to_blocked;
+    }
 
-pub fn remove_entity(entity: Entity, idx: usize) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.tile_content[idx].retain(|(e, _)| *e != entity);
-    let from_blocked = lock.tile_content[idx].iter().any(|&(_, blocks)| blocks);
-    lock.blocked[idx].1 = from_blocked;
-}
+    pub fn remove_entity(&mut self, entity: Entity, idx: usize) {
+        self.tile_content[idx].retain(|(e, _)| *e != entity);
+        let from_blocked = self.tile_content[idx].iter().any(|&(_, blocks)| blocks);
+        self.blocked[idx].1 = from_blocked;
+    }
 
-pub fn set_blocked(idx: usize, blocked: bool) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked[idx] = (lock.blocked[idx].0, blocked);
+    pub fn set_blocked(&mut self, idx: usize, blocked: bool) {
+        self.blocked[idx] = (self.blocked[idx].0, blocked);
+    }
 }
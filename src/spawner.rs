@@ -1,16 +1,19 @@
 use std::collections::HashMap;
 
 use super::{
-    HungerClock, HungerState, IsSerialized, Map, Name, Player, Position, Rect, Renderable,
-    TileType, Viewshed,
+    Gold, HungerClock, HungerState, IsSerialized, Item, Map, Name, Player, Position, Rect,
+    Renderable, TileType, Viewshed,
 };
 use crate::{
     dungeon::MasterDungeonMap,
     gamesystem::{attr_bonus, mana_at_level, player_hp_at_level},
-    random_table::MasterTable,
     raws::{
-        rawsmaster::{get_spawn_table_for_depth, spawn_all_spells, spawn_named_entity, SpawnType},
-        RAWS,
+        get_raws,
+        rawsmaster::{
+            get_spawn_group, get_spawn_table_for_level, roll_loot, spawn_all_spells,
+            spawn_named_entity, Loot, RawMaster, SpawnType,
+        },
+        spawn_table_structs::SpawnGroup,
     },
     Attribute, AttributeBonus, Attributes, Duration, EntryTrigger, EquipmentChanged, Faction,
     Initiative, KnownSpells, LightSource, OtherLevelPosition, Pool, Pools, SingleActivation, Skill,
@@ -111,7 +114,7 @@ pub fn player(ecs: &mut World, player_pos: Position) -> Entity {
         .build();
 
     // Starting equipment
-    let raws = &RAWS.lock().unwrap();
+    let raws = &get_raws(ecs);
     spawn_named_entity(
         raws,
         ecs,
@@ -161,11 +164,13 @@ pub fn player(ecs: &mut World, player_pos: Position) -> Entity {
 
 /// Calls spawn_region() with all possible_targets (floor tiles) from given room
 pub fn spawn_room(
+    raws: &RawMaster,
     map: &Map,
     rng: &mut RandomNumberGenerator,
     room: &Rect,
     map_depth: i32,
     spawn_list: &mut Vec<(usize, String)>,
+    spawn_leaders: &mut HashMap<usize, usize>,
 ) {
     let mut possible_targets: Vec<usize> = Vec::new();
     for y in room.y1 + 1..room.y2 {
@@ -177,65 +182,170 @@ pub fn spawn_room(
         }
     }
 
-    spawn_region(map, rng, &possible_targets, map_depth, spawn_list);
+    spawn_region(
+        raws,
+        map,
+        rng,
+        &possible_targets,
+        map_depth,
+        spawn_list,
+        spawn_leaders,
+    );
 }
 
 pub fn spawn_region(
-    // TODO: Remove?
-    _map: &Map,
+    raws: &RawMaster,
+    map: &Map,
     rng: &mut RandomNumberGenerator,
     area: &[usize],
     map_depth: i32,
     spawn_list: &mut Vec<(usize, String)>,
+    spawn_leaders: &mut HashMap<usize, usize>,
 ) {
-    let spawn_table = room_table(map_depth);
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
+    let spawn_table = get_spawn_table_for_level(raws, map_depth, &map.tags);
     let mut areas: Vec<usize> = Vec::from(area);
 
-    {
-        let num_spawns = i32::min(
-            areas.len() as i32,
-            rng.roll_dice(1, MAX_MONSTERS + 3) + (map_depth - 1) - 3,
-        );
-        if num_spawns == 0 {
-            return;
+    let num_spawns = i32::min(
+        areas.len() as i32,
+        rng.roll_dice(1, MAX_MONSTERS + 3) + (map_depth - 1) - 3,
+    );
+    for _ in 0..num_spawns {
+        // Groups take more than one tile, so the area can run out early
+        if areas.is_empty() {
+            break;
+        }
+        let array_index: usize = if areas.len() == 1 {
+            0
+        } else {
+            (rng.roll_dice(1, areas.len() as i32) - 1) as usize
+        };
+        let map_idx = areas.remove(array_index);
+        let name = spawn_table.roll(rng);
+        match get_spawn_group(raws, &name) {
+            Some(group) => spawn_group(
+                map,
+                rng,
+                group,
+                map_idx,
+                &mut areas,
+                spawn_list,
+                spawn_leaders,
+            ),
+            None => spawn_list.push((map_idx, name)),
         }
+    }
+}
 
-        for _ in 0..num_spawns {
-            let array_index: usize = if areas.len() == 1 {
-                0
-            } else {
-                (rng.roll_dice(1, areas.len() as i32) - 1) as usize
-            };
-            let map_idx = areas[array_index];
-            spawn_points.insert(map_idx, spawn_table.roll(rng));
-            areas.remove(array_index);
+/// Places a group around `map_idx`: the leader (or first member) there, and everyone else on
+/// free tiles of the area next to those already taken, so the group spawns as one encounter.
+fn spawn_group(
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    group: &SpawnGroup,
+    map_idx: usize,
+    areas: &mut Vec<usize>,
+    spawn_list: &mut Vec<(usize, String)>,
+    spawn_leaders: &mut HashMap<usize, usize>,
+) {
+    let mut names: Vec<String> = group.leader.iter().cloned().collect();
+    for member in group.members.iter() {
+        for _ in 0..member.count.roll(rng) {
+            names.push(member.name.clone());
         }
     }
 
-    // Spawning things
-    for spawn in spawn_points.iter() {
-        spawn_list.push((*spawn.0, spawn.1.to_string()));
+    let mut placed = Vec::new();
+    for (i, name) in names.into_iter().enumerate() {
+        let idx = if i == 0 {
+            map_idx
+        } else {
+            match take_adjacent_tile(map, rng, &placed, areas) {
+                Some(idx) => idx,
+                None => break,
+            }
+        };
+        if group.leader.is_some() && i > 0 {
+            spawn_leaders.insert(idx, map_idx);
+        }
+        placed.push(idx);
+        spawn_list.push((idx, name));
+    }
+}
+
+/// Removes and returns a random tile of `areas` touching one of the `placed` tiles.
+fn take_adjacent_tile(
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    placed: &[usize],
+    areas: &mut Vec<usize>,
+) -> Option<usize> {
+    let candidates: Vec<usize> = (0..areas.len())
+        .filter(|i| {
+            let (x, y) = map.idx_xy(areas[*i]);
+            placed.iter().any(|p| {
+                let (px, py) = map.idx_xy(*p);
+                i32::abs(x - px) <= 1 && i32::abs(y - py) <= 1
+            })
+        })
+        .collect();
+    if candidates.is_empty() {
+        return None;
     }
+    let pick = candidates[(rng.roll_dice(1, candidates.len() as i32) - 1) as usize];
+    Some(areas.remove(pick))
 }
 
 /// Spawns a named entity at the location map[idx]
-pub fn spawn_entity(ecs: &mut World, (idx, name): &(&usize, &String)) {
+pub fn spawn_entity(ecs: &mut World, (idx, name): &(&usize, &String)) -> Option<Entity> {
     let map = ecs.fetch::<Map>();
     let (x, y) = map.idx_xy(**idx);
     std::mem::drop(map); // TODO: Needed?
 
-    let spawn_result = spawn_named_entity(
-        &RAWS.lock().unwrap(),
-        ecs,
-        name,
-        SpawnType::AtPosition { x, y },
-    );
-    if spawn_result.is_some() {
-        return;
+    let spawn_result =
+        spawn_named_entity(&get_raws(ecs), ecs, name, SpawnType::AtPosition { x, y });
+    if spawn_result.is_none() {
+        rltk::console::log(format!("WARNING: Unable to spawn [{name}]!"));
     }
+    spawn_result
+}
 
-    rltk::console::log(format!("WARNING: Unable to spawn [{name}]!"));
+/// Rolls the loot table `table` and drops the results at (x, y)
+pub fn drop_loot(ecs: &mut World, table: &str, x: i32, y: i32) {
+    let raws = get_raws(ecs);
+    let loot = roll_loot(&raws, &mut ecs.fetch_mut::<RandomNumberGenerator>(), table);
+    for drop in loot {
+        match drop {
+            Loot::Item(name) => {
+                if spawn_named_entity(&raws, ecs, &name, SpawnType::AtPosition { x, y }).is_none() {
+                    rltk::console::log(format!("WARNING: Unable to spawn loot [{name}]!"));
+                }
+            }
+            Loot::Gold(amount) => spawn_gold(ecs, amount, x, y),
+        }
+    }
+}
+
+/// Spawns a pile of gold at (x, y)
+pub fn spawn_gold(ecs: &mut World, amount: i32, x: i32, y: i32) {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('$'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: format!("{amount} gold"),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 0.0,
+        })
+        .with(Gold { amount })
+        .marked::<SimpleMarker<IsSerialized>>()
+        .build();
 }
 
 pub fn spawn_town_portal(ecs: &mut World) {
@@ -287,7 +397,3 @@ pub fn spawn_town_portal(ecs: &mut World) {
         .with(SingleActivation {})
         .build();
 }
-
-fn room_table(map_depth: i32) -> MasterTable {
-    get_spawn_table_for_depth(&RAWS.lock().unwrap(), map_depth)
-}
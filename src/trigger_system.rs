@@ -1,7 +1,7 @@
 use crate::{
-    effects::{add_effect, targetting::aoe_tiles, EffectType, Targets},
-    gamelog::Logger,
-    spatial, AreaOfEffect,
+    effects::{targetting::aoe_tiles, EffectQueue, EffectType, Targets},
+    gamelog::{GameLog, Logger},
+    AreaOfEffect,
 };
 
 use super::{EntityMoved, EntryTrigger, Map, Name, Position};
@@ -18,16 +18,27 @@ impl<'a> System<'a> for TriggerSystem {
         ReadStorage<'a, Name>,
         Entities<'a>,
         ReadStorage<'a, AreaOfEffect>,
+        WriteExpect<'a, EffectQueue>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (map, mut entity_moved, position, entry_trigger, names, entities, area_of_effect) =
-            data;
+        let (
+            map,
+            mut entity_moved,
+            position,
+            entry_trigger,
+            names,
+            entities,
+            area_of_effect,
+            mut effects,
+            mut gamelog,
+        ) = data;
 
         // Iterate the entities that moved and their final position
         for (entity, mut _entity_moved, pos) in (&entities, &mut entity_moved, &position).join() {
             let idx = map.xy_idx(pos.x, pos.y);
-            spatial::for_each_tile_content(idx, |entity_id| {
+            map.spatial.for_each_tile_content(idx, |entity_id| {
                 if entity == entity_id {
                     return;
                 }
@@ -36,11 +47,14 @@ impl<'a> System<'a> for TriggerSystem {
                     // We triggered it
                     let name = names.get(entity_id);
                     if let Some(name) = name {
-                        Logger::new().red(&name.name).white("triggers!").log();
+                        Logger::new()
+                            .red(&name.name)
+                            .white("triggers!")
+                            .log(&mut gamelog);
                     }
 
                     // Call the effects system
-                    add_effect(
+                    effects.add_effect(
                         Some(entity),
                         EffectType::TriggerFire { trigger: entity_id },
                         if let Some(aoe) = area_of_effect.get(entity_id) {
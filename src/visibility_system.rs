@@ -1,4 +1,4 @@
-use crate::{gamelog::Logger, spatial};
+use crate::gamelog::{GameLog, Logger};
 
 use super::{BlocksVisibility, Hidden, Map, Name, Player, Position, Viewshed};
 use rltk::{field_of_view, Point};
@@ -11,12 +11,13 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, Viewshed>,
-        WriteStorage<'a, Position>,
+        ReadStorage<'a, Position>,
         ReadStorage<'a, Player>,
         WriteStorage<'a, Hidden>,
         WriteExpect<'a, rltk::RandomNumberGenerator>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, BlocksVisibility>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -30,6 +31,7 @@ impl<'a> System<'a> for VisibilitySystem {
             mut rng,
             names,
             blocks_visibility,
+            mut gamelog,
         ) = data;
 
         map.view_blocked.clear();
@@ -58,10 +60,13 @@ impl<'a> System<'a> for VisibilitySystem {
                         map.visible_tiles[idx] = true;
 
                         // Chance to reveal hidden things
-                        spatial::for_each_tile_content(idx, |e| {
+                        map.spatial.for_each_tile_content(idx, |e| {
                             if hidden.get(e).is_some() && rng.roll_dice(1, 24) == 1 {
                                 if let Some(name) = names.get(e) {
-                                    Logger::new().white("You spotted:").red(&name.name).log();
+                                    Logger::new()
+                                        .white("You spotted:")
+                                        .red(&name.name)
+                                        .log(&mut gamelog);
                                 }
                                 hidden.remove(e);
                             }